@@ -0,0 +1,172 @@
+//! Formatting and argument helpers of `ld6002-cli`
+
+use hlk_ld6002::command::{Command, MAX_PAYLOAD};
+use std::fmt::Write;
+
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The last `width` values as a line of block characters, scaled between their minimum and maximum
+///
+/// Values of 0 mean there was no reading and are shown as a space.
+pub fn sparkline(values: &[f32], width: usize) -> String {
+    let values = &values[values.len().saturating_sub(width)..];
+    let readings = values.iter().copied().filter(|value| *value > 0.0);
+    let min = readings.clone().fold(f32::MAX, f32::min);
+    let max = readings.fold(f32::MIN, f32::max);
+    values
+        .iter()
+        .map(|value| {
+            if *value <= 0.0 {
+                ' '
+            } else if max > min {
+                let level = (value - min) / (max - min) * (BLOCKS.len() - 1) as f32;
+                BLOCKS[level.round() as usize]
+            } else {
+                BLOCKS[BLOCKS.len() / 2]
+            }
+        })
+        .collect()
+}
+
+/// Bytes as space separated hex
+pub fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 3);
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
+
+/// Parse hex bytes, with or without spaces between them
+pub fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = hex
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// A command parsed from the arguments of `configure`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Setting {
+    Typed(Command<'static>),
+    Raw { ty: u16, payload: Vec<u8> },
+}
+
+impl Setting {
+    pub fn command(&self) -> Command<'_> {
+        match self {
+            Setting::Typed(command) => *command,
+            Setting::Raw { ty, payload } => Command::Raw {
+                ty: *ty,
+                data: payload,
+            },
+        }
+    }
+}
+
+/// Parse the arguments of `configure` into the setting and the port given with `--port`
+pub fn parse_configure(args: &[&str]) -> Result<(Setting, Option<String>), String> {
+    let mut port = None;
+    let mut payload = None;
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--port" => port = Some(args.next().ok_or("--port needs a value")?.to_string()),
+            "--payload" => payload = Some(*args.next().ok_or("--payload needs a value")?),
+            _ => positional.push(*arg),
+        }
+    }
+
+    let number = |value: &str| {
+        value
+            .parse::<f32>()
+            .map_err(|_| format!("{value} isn't a number"))
+    };
+    let setting = match positional.as_slice() {
+        ["height", meters] => Setting::Typed(Command::SetHeight(number(meters)?)),
+        ["fall-threshold", meters] => Setting::Typed(Command::SetFallThreshold(number(meters)?)),
+        ["fall-sensitivity", frames] => Setting::Typed(Command::SetFallSensitivity(
+            frames
+                .parse()
+                .map_err(|_| format!("{frames} isn't a number of frames"))?,
+        )),
+        ["alarm-area", left, right, front, back] => Setting::Typed(Command::SetAlarmArea {
+            left: number(left)?,
+            right: number(right)?,
+            front: number(front)?,
+            back: number(back)?,
+        }),
+        ["user-log", "on"] => Setting::Typed(Command::UserLog(true)),
+        ["user-log", "off"] => Setting::Typed(Command::UserLog(false)),
+        ["reset"] => Setting::Typed(Command::Reset),
+        ["raw", ty] => Setting::Raw {
+            ty: u16::from_str_radix(ty.trim_start_matches("0x"), 16)
+                .map_err(|_| "the command type should be hex, e.g. 0x0e04".to_string())?,
+            payload: match payload {
+                Some(payload) => {
+                    let payload = parse_hex(payload).ok_or("the payload should be hex")?;
+                    if payload.len() > MAX_PAYLOAD {
+                        return Err(format!("the payload can be at most {MAX_PAYLOAD} bytes"));
+                    }
+                    payload
+                }
+                None => Vec::new(),
+            },
+        },
+        _ => return Err("unknown setting".into()),
+    };
+    if payload.is_some() && !matches!(setting, Setting::Raw { .. }) {
+        return Err("--payload is only used by raw commands".into());
+    }
+    Ok((setting, port))
+}
+
+/// The settings for the sensor on one port, from a `provision` manifest
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provision {
+    pub port: String,
+    pub settings: Vec<Setting>,
+}
+
+/// Parse a `provision` manifest
+///
+/// Every line holds a port followed by a setting, written like the arguments of `configure`.
+/// Empty lines and lines starting with `#` are skipped. The settings are grouped by port,
+/// in the order the ports and settings appear in.
+pub fn parse_manifest(manifest: &str) -> Result<Vec<Provision>, String> {
+    let mut ports: Vec<Provision> = Vec::new();
+    for (number, line) in manifest.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let line_error = |error: &str| format!("line {}: {error}", number + 1);
+        let [port, setting @ ..] = words.as_slice() else {
+            unreachable!("non-empty lines have a word");
+        };
+        let (setting, other_port) = parse_configure(setting).map_err(|e| line_error(&e))?;
+        if other_port.is_some() {
+            return Err(line_error("the port goes at the start of the line"));
+        }
+        match ports.iter_mut().find(|provision| provision.port == *port) {
+            Some(provision) => provision.settings.push(setting),
+            None => ports.push(Provision {
+                port: port.to_string(),
+                settings: vec![setting],
+            }),
+        }
+    }
+    Ok(ports)
+}
@@ -0,0 +1,287 @@
+//! Watch, dump, record and replay the reports of a sensor, and configure it
+//!
+//! ```text
+//! ld6002-cli watch [port]
+//! ld6002-cli dump [port]
+//! ld6002-cli record <capture> [port]
+//! ld6002-cli replay <capture> [--paced]
+//! ld6002-cli configure <setting> [--port <port>]
+//! ld6002-cli provision <manifest>
+//! ```
+//!
+//! Without a port, the port the sensor is connected to is detected by probing all serial ports.
+//! The settings of `configure` are those documented for the HLK-LD6002C fall detection firmware,
+//! `configure raw <type> [--payload <hex>]` sends any other command.
+//!
+//! `provision` configures the sensors on several ports at once, from a manifest with a port
+//! and a setting per line:
+//!
+//! ```text
+//! # bedroom
+//! /dev/ttyUSB0 height 2.2
+//! /dev/ttyUSB0 fall-threshold 0.5
+//! # bathroom
+//! /dev/ttyUSB1 height 2.4
+//! /dev/ttyUSB1 alarm-area 0.5 0.5 1.0 1.0
+//! ```
+//!
+//! Every setting the sensor replies to is verified to be applied, the exit code is non-zero
+//! if any port failed. The manifest doesn't name the model per port: none of the documented commands
+//! reports the model or firmware, so it couldn't be verified, and the typed settings exist only for
+//! the HLK-LD6002C anyway. Use `raw` settings for other models.
+
+use embedded_io_adapters::std::FromStd;
+use hlk_ld6002::command::{read_ack, write_command};
+use hlk_ld6002::replay::{Recorder, Replay};
+use hlk_ld6002::serial::{detect, BAUD_RATE};
+use hlk_ld6002::{Data, DataEvent, Field, LdError, MessageStream};
+use hlk_ld6002_cli::{hex, parse_configure, parse_manifest, sparkline, Setting};
+use serialport::{ClearBuffer, SerialPort};
+use std::collections::VecDeque;
+use std::env::args;
+use std::fs::{read_to_string, File};
+use std::io::{BufReader, BufWriter};
+use std::process::exit;
+use std::time::{Duration, Instant};
+
+/// Number of readings shown in the sparklines
+const HISTORY: usize = 60;
+
+const USAGE: &str = "usage:
+  ld6002-cli watch [port]                        live vitals with sparklines
+  ld6002-cli dump [port]                         hexdump of the received frames
+  ld6002-cli record <capture> [port]             record the raw serial traffic
+  ld6002-cli replay <capture> [--paced]          print the messages in a capture
+  ld6002-cli configure <setting> [--port <port>] send a setting and print the reply
+  ld6002-cli provision <manifest>                apply the settings of a manifest, one
+                                                 \"<port> <setting>\" per line
+
+settings:
+  height <m>                          mounting height, 1 to 5 m
+  fall-threshold <m>                  height below which a target counts as fallen
+  fall-sensitivity <frames>           frames a fall needs to be seen for, 3 to 10
+  alarm-area <left> <right> <front> <back>
+                                      area falls are reported in, 0.3 to 1.5 m each
+  user-log on|off                     user log reports
+  reset                               restore the default parameters
+  raw <type> [--payload <hex>]        any other command";
+
+fn main() {
+    let args: Vec<String> = args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["watch", port @ ..] => watch(port_path(port.first().copied())),
+        ["dump", port @ ..] => dump(port_path(port.first().copied())),
+        ["record", capture, port @ ..] => record(capture, port_path(port.first().copied())),
+        ["replay", capture, rest @ ..] => replay(capture, rest.contains(&"--paced")),
+        ["configure", rest @ ..] => {
+            let (setting, port) =
+                parse_configure(rest).unwrap_or_else(|e| fail(&format!("{e}\n\n{USAGE}")));
+            configure(&setting, port_path(port.as_deref()))
+        }
+        ["provision", manifest] => provision(manifest),
+        _ => fail(USAGE),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{message}");
+    exit(1);
+}
+
+fn port_path(port: Option<&str>) -> String {
+    match port {
+        Some(port) => port.to_string(),
+        None => match detect() {
+            Ok(Some(port)) => {
+                eprintln!("found sensor on {port}");
+                port
+            }
+            Ok(None) => fail("no sensor found, pass the port explicitly"),
+            Err(e) => fail(&format!("failed to list serial ports: {e}")),
+        },
+    }
+}
+
+fn open_port(path: &str) -> Box<dyn SerialPort> {
+    let port = serialport::new(path, BAUD_RATE)
+        .timeout(Duration::from_millis(50))
+        .open()
+        .unwrap_or_else(|e| fail(&format!("failed to open {path}: {e}")));
+    port.clear(ClearBuffer::All).expect("clear");
+    port
+}
+
+fn watch(port: String) {
+    let start = Instant::now();
+    let mut messages = MessageStream::with_clock(FromStd::new(open_port(&port)), move || {
+        start.elapsed().as_millis() as u64
+    })
+    .with_resync(true);
+
+    let mut data = Data::default();
+    let mut history: [VecDeque<f32>; 3] = Default::default();
+    let mut last = Instant::now();
+
+    print!("{}", termion::clear::All);
+    for message in messages.by_ref().flatten() {
+        if let DataEvent::Updated(field) = data.apply(message) {
+            let (index, value) = match field {
+                Field::Heartbeat => (0, data.heartbeat),
+                Field::Respiratory => (1, data.respiratory),
+                Field::Distance => (2, data.distance),
+            };
+            let history = &mut history[index];
+            if history.len() == HISTORY {
+                history.pop_front();
+            }
+            history.push_back(value);
+        }
+
+        if last.elapsed() > Duration::from_millis(250) {
+            last = Instant::now();
+            print!(
+                "{}{}",
+                termion::cursor::Goto(1, 1),
+                termion::clear::AfterCursor
+            );
+            println!("{port}\r\n\r");
+            let rows = [
+                ("heartbeat", data.heartbeat, "/min"),
+                ("respiratory", data.respiratory, "/min"),
+                ("distance", data.distance, "m"),
+            ];
+            for ((name, value, unit), history) in rows.into_iter().zip(&mut history) {
+                let line = sparkline(history.make_contiguous(), HISTORY);
+                println!("{name:<12} {value:>6.1}{unit:<5} {line}\r");
+            }
+        }
+    }
+}
+
+fn dump(port: String) {
+    let mut messages = MessageStream::new(FromStd::new(open_port(&port)));
+    loop {
+        match messages.next_raw() {
+            Ok((raw, message)) => println!("{:<60} {message:?}", hex(raw.as_ref())),
+            Err(LdError::Read(e)) if e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(e) => println!("{e:?}"),
+        }
+    }
+}
+
+fn record(capture: &str, port: String) {
+    let capture = File::create(capture).unwrap_or_else(|e| fail(&format!("{capture}: {e}")));
+    let start = Instant::now();
+    let recorder = Recorder::new(
+        FromStd::new(open_port(&port)),
+        FromStd::new(BufWriter::new(capture)),
+        move || start.elapsed().as_millis() as u64,
+    );
+    let mut frames = 0u32;
+    eprintln!("recording, press ctrl-c to stop");
+    for message in MessageStream::new(recorder).with_resync(true) {
+        match message {
+            Ok(_) => {
+                frames += 1;
+                if frames.is_multiple_of(100) {
+                    eprint!("\r{frames} frames");
+                }
+            }
+            Err(LdError::Read(hlk_ld6002::replay::RecordError::Write(e))) => {
+                fail(&format!("failed to write the capture: {e}"))
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+fn replay(capture: &str, paced: bool) {
+    let file = File::open(capture).unwrap_or_else(|e| fail(&format!("{capture}: {e}")));
+    let capture = FromStd::new(BufReader::new(file));
+    let delay = |ms: u64| {
+        if paced {
+            std::thread::sleep(Duration::from_millis(ms));
+        }
+    };
+    let messages = MessageStream::new(Replay::with_pacing(capture, delay)).with_resync(true);
+    for message in messages {
+        match message {
+            Ok(message) => println!("{message:?}"),
+            Err(LdError::Eof) => break,
+            Err(e) => println!("{e:?}"),
+        }
+    }
+}
+
+fn configure(setting: &Setting, port: String) {
+    let mut port = open_port(&port);
+    let reader = FromStd::new(port.try_clone().expect("clone port"));
+    // the sensor keeps sending reports, so the reply likely arrives in the middle of a frame
+    let mut messages = MessageStream::new(reader).with_resync(true);
+    let command = setting.command();
+    write_command(FromStd::new(&mut port), 1, &command)
+        .unwrap_or_else(|e| fail(&format!("failed to send the command: {e}")));
+    if !command.is_acknowledged() {
+        println!("sent");
+        return;
+    }
+    match read_ack(&mut messages, 1, &command, 100) {
+        Ok(Some(ack)) if matches!(setting, Setting::Raw { .. }) => {
+            println!("reply {:04x}: {}", ack.ty, hex(ack.data()))
+        }
+        Ok(Some(ack)) if ack.is_success() => println!("ok"),
+        Ok(Some(ack)) => fail(&format!("rejected: {}", hex(ack.data()))),
+        Ok(None) => fail("no reply"),
+        Err(e) => fail(&format!("no reply: {e:?}")),
+    }
+}
+
+fn provision(manifest: &str) {
+    let manifest = read_to_string(manifest).unwrap_or_else(|e| fail(&format!("{manifest}: {e}")));
+    let ports = parse_manifest(&manifest).unwrap_or_else(|e| fail(&e));
+    let mut failed = 0;
+    for provision in &ports {
+        match provision_port(&provision.port, &provision.settings) {
+            Ok(()) => println!("{}: ok", provision.port),
+            Err(e) => {
+                println!("{}: {e}", provision.port);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        fail(&format!("{failed} of {} ports failed", ports.len()));
+    }
+}
+
+/// Apply the settings to the sensor on `path`, stopping at the first setting that isn't applied
+///
+/// Raw commands count as applied when the sensor replies, since the meaning of their reply isn't known.
+fn provision_port(path: &str, settings: &[Setting]) -> Result<(), String> {
+    let open_error = |e: serialport::Error| format!("failed to open: {e}");
+    let mut port = serialport::new(path, BAUD_RATE)
+        .timeout(Duration::from_millis(50))
+        .open()
+        .map_err(open_error)?;
+    port.clear(ClearBuffer::All).map_err(open_error)?;
+    let reader = FromStd::new(port.try_clone().map_err(open_error)?);
+    let mut messages = MessageStream::new(reader).with_resync(true);
+    // a different id per setting, so a late reply isn't mistaken for the next one
+    for (id, setting) in (1..).zip(settings) {
+        let command = setting.command();
+        let name = format!("{:04x}", command.ty());
+        write_command(FromStd::new(&mut port), id, &command)
+            .map_err(|e| format!("failed to send {name}: {e}"))?;
+        if !command.is_acknowledged() {
+            continue;
+        }
+        match read_ack(&mut messages, id, &command, 100) {
+            Ok(Some(ack)) if ack.is_success() || matches!(setting, Setting::Raw { .. }) => {}
+            Ok(Some(ack)) => return Err(format!("{name} rejected: {}", hex(ack.data()))),
+            Ok(None) => return Err(format!("no reply to {name}")),
+            Err(e) => return Err(format!("no reply to {name}: {e:?}")),
+        }
+    }
+    Ok(())
+}
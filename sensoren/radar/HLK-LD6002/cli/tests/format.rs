@@ -0,0 +1,132 @@
+use hlk_ld6002::command::{self, Command};
+use hlk_ld6002_cli::{
+    hex, parse_configure, parse_hex, parse_manifest, sparkline, Provision, Setting,
+};
+
+#[test]
+fn sparkline_scales_between_min_and_max() {
+    assert_eq!(sparkline(&[60.0, 70.0, 80.0], 10), "▁▅█");
+    assert_eq!(sparkline(&[60.0, 0.0, 80.0], 10), "▁ █");
+    assert_eq!(sparkline(&[72.0, 72.0], 10), "▅▅");
+    // only the last values fit
+    assert_eq!(sparkline(&[10.0, 60.0, 70.0, 80.0], 3), "▁▅█");
+    assert_eq!(sparkline(&[], 3), "");
+}
+
+#[test]
+fn hex_round_trip() {
+    let bytes = [0x01, 0x00, 0x02, 0xff];
+    assert_eq!(hex(&bytes), "01 00 02 ff");
+    assert_eq!(parse_hex("01 00 02 ff").unwrap(), bytes);
+    assert_eq!(parse_hex("010002FF").unwrap(), bytes);
+    assert!(parse_hex("012").is_none());
+    assert!(parse_hex("zz").is_none());
+}
+
+#[test]
+fn configure_arguments() {
+    assert_eq!(
+        parse_configure(&["height", "2.2", "--port", "/dev/ttyUSB0"]).unwrap(),
+        (
+            Setting::Typed(Command::SetHeight(2.2)),
+            Some("/dev/ttyUSB0".into())
+        )
+    );
+    assert_eq!(
+        parse_configure(&["raw", "0x0e06"]).unwrap(),
+        (
+            Setting::Raw {
+                ty: 0x0e06,
+                payload: Vec::new()
+            },
+            None
+        )
+    );
+    // a port after the type isn't mistaken for the payload
+    let (setting, port) = parse_configure(&[
+        "raw",
+        "0100",
+        "--port",
+        "/dev/ttyUSB0",
+        "--payload",
+        "01 02",
+    ])
+    .unwrap();
+    assert_eq!(
+        setting.command(),
+        Command::Raw {
+            ty: 0x0100,
+            data: &[1, 2]
+        }
+    );
+    assert_eq!(port.as_deref(), Some("/dev/ttyUSB0"));
+
+    assert!(parse_configure(&["raw", "0x0100", "/dev/ttyUSB0"]).is_err());
+    assert!(parse_configure(&["height"]).is_err());
+    assert!(parse_configure(&["height", "high"]).is_err());
+    assert!(parse_configure(&["reset", "--payload", "01"]).is_err());
+    assert!(parse_configure(&["reset", "--port"]).is_err());
+
+    // the longest payload that fits into a command frame
+    let longest = "00".repeat(command::MAX_PAYLOAD);
+    assert!(parse_configure(&["raw", "0x0e04", "--payload", &longest]).is_ok());
+    let too_long = "00".repeat(command::MAX_PAYLOAD + 1);
+    assert_eq!(
+        parse_configure(&["raw", "0x0e04", "--payload", &too_long]).unwrap_err(),
+        "the payload can be at most 32 bytes"
+    );
+}
+
+#[test]
+fn manifest() {
+    let manifest = "
+        # bedroom
+        /dev/ttyUSB0 height 2.2
+        /dev/ttyUSB1 alarm-area 0.5 0.5 1.0 1.0
+
+        /dev/ttyUSB0 fall-threshold 0.5
+        /dev/ttyUSB0 raw 0x0e01 --payload 01000000
+    ";
+    assert_eq!(
+        parse_manifest(manifest).unwrap(),
+        [
+            Provision {
+                port: "/dev/ttyUSB0".into(),
+                settings: vec![
+                    Setting::Typed(Command::SetHeight(2.2)),
+                    Setting::Typed(Command::SetFallThreshold(0.5)),
+                    Setting::Raw {
+                        ty: 0x0e01,
+                        payload: vec![1, 0, 0, 0]
+                    },
+                ]
+            },
+            Provision {
+                port: "/dev/ttyUSB1".into(),
+                settings: vec![Setting::Typed(Command::SetAlarmArea {
+                    left: 0.5,
+                    right: 0.5,
+                    front: 1.0,
+                    back: 1.0
+                })]
+            },
+        ]
+    );
+    assert_eq!(parse_manifest("# nothing yet\n").unwrap(), []);
+
+    assert_eq!(
+        parse_manifest("/dev/ttyUSB0 height 2\n/dev/ttyUSB0 height").unwrap_err(),
+        "line 2: unknown setting"
+    );
+    assert!(parse_manifest("/dev/ttyUSB0").is_err());
+    assert!(parse_manifest("height 2 --port /dev/ttyUSB0").is_err());
+    // an oversized payload is rejected before any port is configured
+    let manifest = format!(
+        "/dev/ttyUSB0 height 2\n/dev/ttyUSB1 raw 0x0e04 --payload {}",
+        "00".repeat(command::MAX_PAYLOAD + 1)
+    );
+    assert_eq!(
+        parse_manifest(&manifest).unwrap_err(),
+        "line 2: the payload can be at most 32 bytes"
+    );
+}
@@ -0,0 +1,343 @@
+//! Exposing an HLK-LD6002 to Home Assistant over the [ESPHome native API](https://esphome.io/components/api.html)
+//!
+//! The sensor shows up as an ESPHome device with heartbeat, respiration and distance sensors and
+//! an occupancy binary sensor, without needing an MQTT broker. Only the plaintext protocol is implemented,
+//! so the device needs to be added in Home Assistant without an encryption key, by host and port
+//! (6053 by default) since there is no mDNS announcement.
+//!
+//! ```rust,no_run
+//! use hlk_ld6002_esphome::{serve, Device, State};
+//! use std::net::TcpListener;
+//! use std::sync::{Arc, Mutex};
+//!
+//! let state = Arc::new(Mutex::new(State::default()));
+//! let listener = TcpListener::bind("0.0.0.0:6053").unwrap();
+//! let device = Device::new("bedroom-radar", "Bedroom radar");
+//! std::thread::spawn({
+//!     let state = state.clone();
+//!     move || serve(listener, device, state)
+//! });
+//! // update `state` with the readings from the sensor
+//! ```
+
+pub mod proto;
+
+use hlk_ld6002::presence::Presence;
+use hlk_ld6002::Data;
+use proto::{read_frame, write_frame, Message};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// ESPHome version reported to Home Assistant, the API subset matches this release
+pub const ESPHOME_VERSION: &str = "2024.6.0";
+/// Version of the native API that is implemented
+const API_VERSION: (u32, u32) = (1, 10);
+
+/// How often a connection checks for changed readings
+const STATE_INTERVAL: Duration = Duration::from_millis(250);
+
+mod message {
+    pub const HELLO_REQUEST: u32 = 1;
+    pub const HELLO_RESPONSE: u32 = 2;
+    pub const CONNECT_REQUEST: u32 = 3;
+    pub const CONNECT_RESPONSE: u32 = 4;
+    pub const DISCONNECT_REQUEST: u32 = 5;
+    pub const DISCONNECT_RESPONSE: u32 = 6;
+    pub const PING_REQUEST: u32 = 7;
+    pub const PING_RESPONSE: u32 = 8;
+    pub const DEVICE_INFO_REQUEST: u32 = 9;
+    pub const DEVICE_INFO_RESPONSE: u32 = 10;
+    pub const LIST_ENTITIES_REQUEST: u32 = 11;
+    pub const LIST_ENTITIES_BINARY_SENSOR_RESPONSE: u32 = 12;
+    pub const LIST_ENTITIES_SENSOR_RESPONSE: u32 = 16;
+    pub const LIST_ENTITIES_DONE_RESPONSE: u32 = 19;
+    pub const SUBSCRIBE_STATES_REQUEST: u32 = 20;
+    pub const BINARY_SENSOR_STATE_RESPONSE: u32 = 21;
+    pub const SENSOR_STATE_RESPONSE: u32 = 25;
+}
+
+/// The entities of the device, with the key identifying them in state messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity {
+    Heartbeat = 1,
+    Respiratory = 2,
+    Distance = 3,
+    Presence = 4,
+}
+
+impl Entity {
+    pub const ALL: [Entity; 4] = [
+        Entity::Heartbeat,
+        Entity::Respiratory,
+        Entity::Distance,
+        Entity::Presence,
+    ];
+
+    pub fn key(self) -> u32 {
+        self as u32
+    }
+
+    pub fn object_id(self) -> &'static str {
+        match self {
+            Entity::Heartbeat => "heartbeat",
+            Entity::Respiratory => "respiratory",
+            Entity::Distance => "distance",
+            Entity::Presence => "presence",
+        }
+    }
+
+    fn describe(self, device: &Device) -> (u32, Message) {
+        let (name, icon) = match self {
+            Entity::Heartbeat => ("Heartbeat", "mdi:heart-pulse"),
+            Entity::Respiratory => ("Respiration", "mdi:lungs"),
+            Entity::Distance => ("Distance", "mdi:signal-distance-variant"),
+            Entity::Presence => ("Presence", "mdi:account"),
+        };
+        let message = Message::new()
+            .string(1, self.object_id())
+            .fixed32(2, self.key())
+            .string(3, name)
+            .string(4, &format!("{}_{}", device.name, self.object_id()));
+        match self {
+            Entity::Presence => (
+                message::LIST_ENTITIES_BINARY_SENSOR_RESPONSE,
+                message.string(5, "occupancy").string(8, icon),
+            ),
+            _ => {
+                let (unit, decimals, class) = match self {
+                    Entity::Distance => ("m", 2, "distance"),
+                    _ => (
+                        if self == Entity::Heartbeat {
+                            "bpm"
+                        } else {
+                            "rpm"
+                        },
+                        0,
+                        "",
+                    ),
+                };
+                (
+                    message::LIST_ENTITIES_SENSOR_RESPONSE,
+                    message
+                        .string(5, icon)
+                        .string(6, unit)
+                        .uint32(7, decimals)
+                        .string(9, class)
+                        // measurement
+                        .uint32(10, 1),
+                )
+            }
+        }
+    }
+}
+
+/// How the device presents itself to Home Assistant
+#[derive(Debug, Clone)]
+pub struct Device {
+    /// Node name, used as hostname-like id of the device and prefix of the entity ids
+    pub name: String,
+    /// Name shown in Home Assistant
+    pub friendly_name: String,
+    /// Identifies the device across restarts, Home Assistant uses it to recognize the device
+    pub mac_address: String,
+}
+
+impl Device {
+    /// A device with a made up MAC address derived from the name
+    pub fn new(name: &str, friendly_name: &str) -> Self {
+        let hash = name.bytes().fold(0u32, |hash, byte| {
+            hash.wrapping_mul(31).wrapping_add(byte as u32)
+        });
+        let [a, b, c, d] = hash.to_be_bytes();
+        Device {
+            name: name.into(),
+            friendly_name: friendly_name.into(),
+            // locally administered, so it doesn't collide with real hardware
+            mac_address: format!("02:00:{a:02X}:{b:02X}:{c:02X}:{d:02X}"),
+        }
+    }
+}
+
+/// The readings shown in Home Assistant, `None` shows the entity as unknown
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct State {
+    pub heartbeat: Option<f32>,
+    pub respiratory: Option<f32>,
+    pub distance: Option<f32>,
+    pub presence: Option<bool>,
+}
+
+impl State {
+    /// Readings of 0 and an unknown presence are shown as unknown
+    pub fn new(data: &Data, presence: Presence) -> Self {
+        let value = |value: f32| (value > 0.0).then_some(value);
+        State {
+            heartbeat: value(data.heartbeat),
+            respiratory: value(data.respiratory),
+            distance: value(data.distance),
+            presence: match presence {
+                Presence::Present => Some(true),
+                Presence::Absent => Some(false),
+                Presence::Unknown => None,
+            },
+        }
+    }
+
+    fn message(&self, entity: Entity) -> (u32, Message) {
+        let value = match entity {
+            Entity::Heartbeat => self.heartbeat,
+            Entity::Respiratory => self.respiratory,
+            Entity::Distance => self.distance,
+            Entity::Presence => {
+                let message = Message::new()
+                    .fixed32(1, entity.key())
+                    .bool(2, self.presence.unwrap_or_default())
+                    .bool(3, self.presence.is_none());
+                return (message::BINARY_SENSOR_STATE_RESPONSE, message);
+            }
+        };
+        let message = Message::new()
+            .fixed32(1, entity.key())
+            .float(2, value.unwrap_or_default())
+            .bool(3, value.is_none());
+        (message::SENSOR_STATE_RESPONSE, message)
+    }
+
+    fn changed(&self, previous: &State, entity: Entity) -> bool {
+        match entity {
+            Entity::Heartbeat => self.heartbeat != previous.heartbeat,
+            Entity::Respiratory => self.respiratory != previous.respiratory,
+            Entity::Distance => self.distance != previous.distance,
+            Entity::Presence => self.presence != previous.presence,
+        }
+    }
+}
+
+/// A connection to a single API client
+pub struct Connection<S> {
+    stream: S,
+    device: Device,
+    /// The last state sent, `None` until the client subscribed to the states
+    sent: Option<Option<State>>,
+}
+
+impl<S: Read + Write> Connection<S> {
+    pub fn new(stream: S, device: Device) -> Self {
+        Connection {
+            stream,
+            device,
+            sent: None,
+        }
+    }
+
+    /// Whether the client subscribed to the states
+    pub fn is_subscribed(&self) -> bool {
+        self.sent.is_some()
+    }
+
+    /// Read and answer the next request, returning `false` once the client disconnected
+    ///
+    /// Requests that aren't needed for the entities of the sensor, like subscribing to logs, are ignored.
+    pub fn handle(&mut self) -> io::Result<bool> {
+        let (ty, _payload) = match read_frame(&mut self.stream) {
+            Ok(frame) => frame,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        match ty {
+            message::HELLO_REQUEST => {
+                let hello = Message::new()
+                    .uint32(1, API_VERSION.0)
+                    .uint32(2, API_VERSION.1)
+                    .string(3, concat!("hlk_ld6002_esphome ", env!("CARGO_PKG_VERSION")))
+                    .string(4, &self.device.name);
+                self.send(message::HELLO_RESPONSE, &hello)?;
+            }
+            message::CONNECT_REQUEST => self.send(message::CONNECT_RESPONSE, &Message::new())?,
+            message::DISCONNECT_REQUEST => {
+                self.send(message::DISCONNECT_RESPONSE, &Message::new())?;
+                return Ok(false);
+            }
+            message::DISCONNECT_RESPONSE => return Ok(false),
+            message::PING_REQUEST => self.send(message::PING_RESPONSE, &Message::new())?,
+            message::DEVICE_INFO_REQUEST => {
+                let info = Message::new()
+                    .string(2, &self.device.name)
+                    .string(3, &self.device.mac_address)
+                    .string(4, ESPHOME_VERSION)
+                    .string(6, "HLK-LD6002")
+                    .string(12, "Hi-Link")
+                    .string(13, &self.device.friendly_name);
+                self.send(message::DEVICE_INFO_RESPONSE, &info)?;
+            }
+            message::LIST_ENTITIES_REQUEST => {
+                for entity in Entity::ALL {
+                    let (ty, message) = entity.describe(&self.device);
+                    self.send(ty, &message)?;
+                }
+                self.send(message::LIST_ENTITIES_DONE_RESPONSE, &Message::new())?;
+            }
+            message::SUBSCRIBE_STATES_REQUEST => self.sent = Some(None),
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Send the readings that changed since the last call, once the client subscribed
+    pub fn send_state(&mut self, state: &State) -> io::Result<()> {
+        let Some(sent) = self.sent else {
+            return Ok(());
+        };
+        for entity in Entity::ALL {
+            if sent.is_none_or(|sent| state.changed(&sent, entity)) {
+                let (ty, message) = state.message(entity);
+                self.send(ty, &message)?;
+            }
+        }
+        self.sent = Some(Some(*state));
+        Ok(())
+    }
+
+    fn send(&mut self, ty: u32, message: &Message) -> io::Result<()> {
+        write_frame(&mut self.stream, ty, message)?;
+        self.stream.flush()
+    }
+}
+
+/// Accept API clients on `listener` and serve them the readings in `state`, each on its own thread
+///
+/// Returns only if accepting connections fails.
+pub fn serve(listener: TcpListener, device: Device, state: Arc<Mutex<State>>) -> io::Error {
+    loop {
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) => return e,
+        };
+        let device = device.clone();
+        let state = state.clone();
+        std::thread::spawn(move || {
+            // the timeout only applies to the first byte of a request, see `read_frame`
+            let configured = stream
+                .set_read_timeout(Some(STATE_INTERVAL))
+                .and_then(|_| stream.set_nodelay(true));
+            if configured.is_err() {
+                return;
+            }
+            let mut connection = Connection::new(stream, device);
+            loop {
+                match connection.handle() {
+                    Ok(true) => {}
+                    Ok(false) => return,
+                    Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                    Err(_) => return,
+                }
+                let state = *state.lock().unwrap_or_else(|e| e.into_inner());
+                if connection.send_state(&state).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+}
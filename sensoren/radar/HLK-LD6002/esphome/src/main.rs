@@ -0,0 +1,67 @@
+//! Expose a sensor on a serial port to Home Assistant over the ESPHome native API
+//!
+//! ```text
+//! hlk_ld6002_esphome <port> [name] [friendly name] [listen address]
+//! ```
+//!
+//! Add the device in Home Assistant using the ESPHome integration, with the host of this machine
+//! and port 6053, without an encryption key.
+
+use embedded_io_adapters::std::FromStd;
+use hlk_ld6002::presence::PresenceDetector;
+use hlk_ld6002::{MessageStream, TimedData};
+use hlk_ld6002_esphome::{serve, Device, State};
+use serialport::ClearBuffer;
+use std::env::args;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Readings older than this are shown as unknown
+const MAX_AGE: u64 = 5_000;
+
+fn main() {
+    let mut args = args().skip(1);
+    let Some(port) = args.next() else {
+        eprintln!("usage: hlk_ld6002_esphome <port> [name] [friendly name] [listen address]");
+        std::process::exit(1);
+    };
+    let name = args.next().unwrap_or_else(|| "hlk-ld6002".into());
+    let friendly_name = args.next().unwrap_or_else(|| "HLK-LD6002".into());
+    let address = args.next().unwrap_or_else(|| "0.0.0.0:6053".into());
+
+    let state = Arc::new(Mutex::new(State::default()));
+    let listener = TcpListener::bind(&address).expect("Failed to listen");
+    let device = Device::new(&name, &friendly_name);
+    std::thread::spawn({
+        let state = state.clone();
+        move || {
+            let e = serve(listener, device, state);
+            eprintln!("failed to accept connections: {e}");
+            std::process::exit(1);
+        }
+    });
+
+    let port = serialport::new(port, 1_382_400)
+        .timeout(Duration::from_millis(50))
+        .open()
+        .expect("Failed to open port");
+    port.clear(ClearBuffer::All).expect("clear");
+
+    let start = Instant::now();
+    let now = move || start.elapsed().as_millis() as u64;
+    let messages = MessageStream::with_clock(FromStd::new(port), now).with_resync(true);
+
+    let mut data = TimedData::default();
+    let mut presence = PresenceDetector::default();
+
+    // read timeouts are returned as errors, refreshing the state on them lets silent readings expire
+    for message in messages {
+        let now = now();
+        if let Ok(message) = message {
+            presence.update(now, &message);
+            data.update(now, message);
+        }
+        *state.lock().unwrap() = State::new(&data.fresh(now, MAX_AGE), presence.state(now));
+    }
+}
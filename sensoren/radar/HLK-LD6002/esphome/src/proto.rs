@@ -0,0 +1,140 @@
+//! The framing and protobuf encoding of the plaintext native API
+//!
+//! Every message is sent as a zero byte, the length of the payload and the message type as varints,
+//! followed by the protobuf encoded payload. Only the encoding needed for the responses is implemented,
+//! requests are recognized by their type alone.
+
+use std::io::{self, Read, Write};
+
+/// Largest request payload accepted, requests are small and a larger length means a corrupted stream
+const MAX_PAYLOAD: usize = 4096;
+
+const VARINT: u32 = 0;
+const LENGTH_DELIMITED: u32 = 2;
+const FIXED32: u32 = 5;
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(input: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        input.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "varint too long",
+    ))
+}
+
+/// A protobuf message being built, fields with default values are left out like protobuf does
+#[derive(Debug, Default)]
+pub struct Message {
+    bytes: Vec<u8>,
+}
+
+impl Message {
+    pub fn new() -> Self {
+        Message::default()
+    }
+
+    fn tag(&mut self, field: u32, wire_type: u32) {
+        write_varint(&mut self.bytes, ((field << 3) | wire_type) as u64);
+    }
+
+    pub fn uint32(mut self, field: u32, value: u32) -> Self {
+        if value != 0 {
+            self.tag(field, VARINT);
+            write_varint(&mut self.bytes, value as u64);
+        }
+        self
+    }
+
+    pub fn bool(self, field: u32, value: bool) -> Self {
+        self.uint32(field, value as u32)
+    }
+
+    pub fn fixed32(mut self, field: u32, value: u32) -> Self {
+        if value != 0 {
+            self.tag(field, FIXED32);
+            self.bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        self
+    }
+
+    pub fn float(mut self, field: u32, value: f32) -> Self {
+        if value != 0.0 {
+            self.tag(field, FIXED32);
+            self.bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        self
+    }
+
+    pub fn string(mut self, field: u32, value: &str) -> Self {
+        if !value.is_empty() {
+            self.tag(field, LENGTH_DELIMITED);
+            write_varint(&mut self.bytes, value.len() as u64);
+            self.bytes.extend_from_slice(value.as_bytes());
+        }
+        self
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Write a message of type `ty`
+pub fn write_frame(out: &mut impl Write, ty: u32, message: &Message) -> io::Result<()> {
+    let mut frame = vec![0];
+    write_varint(&mut frame, message.bytes.len() as u64);
+    write_varint(&mut frame, ty as u64);
+    frame.extend_from_slice(&message.bytes);
+    out.write_all(&frame)
+}
+
+/// Read the next message, returning its type and payload
+///
+/// Only reads the first byte before failing on a read timeout, so a timeout doesn't lose part of a frame.
+pub fn read_frame(input: &mut impl Read) -> io::Result<(u32, Vec<u8>)> {
+    let mut preamble = [0];
+    input.read_exact(&mut preamble)?;
+    if preamble[0] != 0 {
+        // 1 is the preamble of the encrypted protocol
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "only the plaintext protocol is supported",
+        ));
+    }
+    read_message(input).map_err(|e| match e.kind() {
+        // the rest of the frame was lost, the stream can't be resynchronized
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+            io::Error::new(io::ErrorKind::InvalidData, "request cut off")
+        }
+        _ => e,
+    })
+}
+
+fn read_message(input: &mut impl Read) -> io::Result<(u32, Vec<u8>)> {
+    let len = read_varint(input)? as usize;
+    let ty = read_varint(input)? as u32;
+    if len > MAX_PAYLOAD {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "message too long",
+        ));
+    }
+    let mut payload = vec![0; len];
+    input.read_exact(&mut payload)?;
+    Ok((ty, payload))
+}
@@ -0,0 +1,158 @@
+use hlk_ld6002::presence::Presence;
+use hlk_ld6002::Data;
+use hlk_ld6002_esphome::proto::{read_frame, write_frame, Message};
+use hlk_ld6002_esphome::{Connection, Device, Entity, State};
+use std::io::{self, Cursor, Read, Write};
+
+/// Requests from a script, responses collected for checking
+struct Session {
+    requests: Cursor<Vec<u8>>,
+    responses: Vec<u8>,
+}
+
+impl Read for &mut Session {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.requests.read(buf)
+    }
+}
+
+impl Write for &mut Session {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.responses.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn session(requests: &[u32]) -> Session {
+    let mut bytes = Vec::new();
+    for ty in requests {
+        write_frame(&mut bytes, *ty, &Message::new()).unwrap();
+    }
+    Session {
+        requests: Cursor::new(bytes),
+        responses: Vec::new(),
+    }
+}
+
+fn responses(session: &Session) -> Vec<(u32, Vec<u8>)> {
+    let mut input = session.responses.as_slice();
+    let mut frames = Vec::new();
+    while !input.is_empty() {
+        frames.push(read_frame(&mut input).unwrap());
+    }
+    frames
+}
+
+/// The string fields of a protobuf message
+fn strings(mut payload: &[u8]) -> Vec<(u32, String)> {
+    let mut fields = Vec::new();
+    while let [tag, rest @ ..] = payload {
+        let (field, wire_type) = (tag >> 3, tag & 7);
+        payload = match wire_type {
+            0 => &rest[rest.iter().position(|b| b & 0x80 == 0).unwrap() + 1..],
+            5 => &rest[4..],
+            2 => {
+                let len = rest[0] as usize;
+                fields.push((
+                    field as u32,
+                    String::from_utf8(rest[1..len + 1].to_vec()).unwrap(),
+                ));
+                &rest[len + 1..]
+            }
+            _ => panic!("unexpected wire type {wire_type}"),
+        };
+    }
+    fields
+}
+
+#[test]
+fn home_assistant_handshake() {
+    // hello, connect, device info, list entities, subscribe states, subscribe logs, ping, disconnect
+    let mut session = session(&[1, 3, 9, 11, 20, 28, 7, 5]);
+    let device = Device::new("bedroom-radar", "Bedroom radar");
+    let mut connection = Connection::new(&mut session, device.clone());
+
+    while connection.handle().unwrap() {}
+    assert!(connection.is_subscribed());
+    drop(connection);
+
+    let frames = responses(&session);
+    let types: Vec<u32> = frames.iter().map(|(ty, _)| *ty).collect();
+    assert_eq!(types, [2, 4, 10, 16, 16, 16, 12, 19, 8, 6]);
+
+    let info = strings(&frames[2].1);
+    assert!(info.contains(&(2, "bedroom-radar".into())));
+    assert!(info.contains(&(3, device.mac_address.clone())));
+    assert!(info.contains(&(13, "Bedroom radar".into())));
+
+    let heartbeat = strings(&frames[3].1);
+    assert!(heartbeat.contains(&(1, "heartbeat".into())));
+    assert!(heartbeat.contains(&(4, "bedroom-radar_heartbeat".into())));
+    assert!(heartbeat.contains(&(6, "bpm".into())));
+    let distance = strings(&frames[5].1);
+    assert!(distance.contains(&(9, "distance".into())));
+    let presence = strings(&frames[6].1);
+    assert!(presence.contains(&(5, "occupancy".into())));
+}
+
+#[test]
+fn states_after_subscribing() {
+    let mut session = session(&[20]);
+    let mut connection = Connection::new(&mut session, Device::new("radar", "Radar"));
+
+    let data = Data {
+        heartbeat: 62.0,
+        ..Data::default()
+    };
+    // nothing is sent before subscribing
+    connection
+        .send_state(&State::new(&data, Presence::Present))
+        .unwrap();
+    connection.handle().unwrap();
+    // all entities once subscribed
+    connection
+        .send_state(&State::new(&data, Presence::Present))
+        .unwrap();
+    // only the changes afterwards
+    connection
+        .send_state(&State::new(&data, Presence::Present))
+        .unwrap();
+    connection
+        .send_state(&State::new(&data, Presence::Absent))
+        .unwrap();
+    drop(connection);
+
+    let frames = responses(&session);
+    let types: Vec<u32> = frames.iter().map(|(ty, _)| *ty).collect();
+    assert_eq!(types, [25, 25, 25, 21, 21]);
+
+    let key = Entity::Heartbeat.key().to_le_bytes();
+    let mut heartbeat = vec![0x0d];
+    heartbeat.extend_from_slice(&key);
+    heartbeat.push(0x15);
+    heartbeat.extend_from_slice(&62.0f32.to_le_bytes());
+    assert_eq!(frames[0].1, heartbeat);
+
+    // no respiratory reading, reported as missing
+    let mut respiratory = vec![0x0d];
+    respiratory.extend_from_slice(&Entity::Respiratory.key().to_le_bytes());
+    respiratory.extend_from_slice(&[0x18, 1]);
+    assert_eq!(frames[1].1, respiratory);
+
+    // presence: key and state, then key only once absent
+    assert_eq!(frames[3].1.len(), 7);
+    assert_eq!(frames[4].1.len(), 5);
+}
+
+#[test]
+fn encrypted_clients_are_refused() {
+    let mut session = Session {
+        requests: Cursor::new(vec![1, 0, 0]),
+        responses: Vec::new(),
+    };
+    let mut connection = Connection::new(&mut session, Device::new("radar", "Radar"));
+    assert!(connection.handle().is_err());
+}
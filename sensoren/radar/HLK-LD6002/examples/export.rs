@@ -0,0 +1,221 @@
+//! Convert the phase in a capture to WAV or EDF
+//!
+//! Reads a capture recorded with [`Recorder`](hlk_ld6002::replay::Recorder) (e.g. by `ld6002-cli record`)
+//! and writes the phase reports in it as 3 channel WAV, for inspecting the waveforms in audio editors,
+//! or EDF, for sleep analysis software. The sample rate is the average rate of the phase reports,
+//! measured from the timestamps in the capture.
+//!
+//! ```text
+//! cargo run --example export -- capture.bin phase.wav
+//! cargo run --example export -- capture.bin phase.edf
+//! ```
+
+use embedded_io_adapters::std::FromStd;
+use hlk_ld6002::detrend::remove_linear_trend;
+use hlk_ld6002::replay::Replay;
+use hlk_ld6002::{LdError, MessageBody, MessageStream};
+use std::cell::Cell;
+use std::env::args;
+use std::fs;
+use std::io::{self, BufReader, Write};
+use std::rc::Rc;
+
+const CHANNELS: usize = 3;
+
+const USAGE: &str = "usage: export <capture> <output.wav|output.edf>";
+
+/// File formats that can be written
+enum Format {
+    Wav,
+    Edf,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1);
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = args().skip(1).collect();
+    let [capture, output] = args.as_slice() else {
+        fail(USAGE);
+    };
+    let format = if output.ends_with(".wav") {
+        Format::Wav
+    } else if output.ends_with(".edf") {
+        Format::Edf
+    } else {
+        fail(USAGE);
+    };
+    let file = fs::File::open(capture).unwrap_or_else(|e| fail(&format!("{capture}: {e}")));
+
+    // the time of the chunk being read, advanced by the replay before every chunk
+    let time = Rc::new(Cell::new(0));
+    let replay = Replay::with_pacing(FromStd::new(BufReader::new(file)), {
+        let time = time.clone();
+        move |ms| time.set(time.get() + ms)
+    });
+    let mut channels: [Vec<f32>; CHANNELS] = Default::default();
+    let mut first = None;
+    let mut last = 0;
+    let messages = MessageStream::new(replay).with_resync(true);
+    for message in messages {
+        match message {
+            Ok(MessageBody::Phase(phase)) => {
+                for (channel, value) in channels.iter_mut().zip(phase) {
+                    channel.push(value);
+                }
+                first.get_or_insert(time.get());
+                last = time.get();
+            }
+            Ok(_) => {}
+            Err(LdError::Eof) => break,
+            Err(e) => eprintln!("skipping invalid frame: {e:?}"),
+        }
+    }
+
+    let samples = channels[0].len();
+    let rate = match first {
+        Some(first) if last > first => {
+            ((samples - 1) as f64 * 1000.0 / (last - first) as f64).round() as u32
+        }
+        _ => 0,
+    };
+    if rate == 0 {
+        fail(&format!(
+            "{capture}: {samples} phase samples, not enough to export (is it a capture of the sensor?)"
+        ));
+    }
+    println!("{samples} phase samples at {rate} Hz");
+
+    let mut file = io::BufWriter::new(fs::File::create(output)?);
+    match format {
+        Format::Wav => write_wav(&mut file, &mut channels, rate)?,
+        Format::Edf => write_edf(&mut file, &channels, rate)?,
+    }
+    file.flush()
+}
+
+/// 16 bit PCM, every channel detrended and scaled to the full range
+fn write_wav(
+    out: &mut impl Write,
+    channels: &mut [Vec<f32>; CHANNELS],
+    rate: u32,
+) -> io::Result<()> {
+    let len = channels[0].len() as u32;
+    let data_size = len * CHANNELS as u32 * 2;
+
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_size).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&(CHANNELS as u16).to_le_bytes())?;
+    out.write_all(&rate.to_le_bytes())?;
+    out.write_all(&(rate * CHANNELS as u32 * 2).to_le_bytes())?;
+    out.write_all(&(CHANNELS as u16 * 2).to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data_size.to_le_bytes())?;
+
+    let scales = channels.each_mut().map(|channel| {
+        remove_linear_trend(channel);
+        let peak = channel
+            .iter()
+            .fold(0.0f32, |peak, value| peak.max(value.abs()));
+        if peak > 0.0 {
+            i16::MAX as f32 / peak
+        } else {
+            0.0
+        }
+    });
+    for i in 0..len as usize {
+        for (channel, scale) in channels.iter().zip(scales) {
+            out.write_all(&((channel[i] * scale) as i16).to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// EDF numbers are limited to 8 characters, round away from the range with as many decimals as fit
+fn edf_number(value: f32, round: fn(f32) -> f32) -> f32 {
+    (0..=3)
+        .rev()
+        .map(|decimals| {
+            let factor = 10f32.powi(decimals);
+            round(value * factor) / factor
+        })
+        .find(|rounded| rounded.to_string().len() <= 8)
+        .unwrap_or(value)
+}
+
+/// EDF with one second data records, the last record is padded with the minimum value
+fn write_edf(out: &mut impl Write, channels: &[Vec<f32>; CHANNELS], rate: u32) -> io::Result<()> {
+    let rate = rate as usize;
+    let records = channels[0].len().div_ceil(rate);
+    let ranges = channels.each_ref().map(|channel| {
+        let min = channel.iter().copied().fold(f32::MAX, f32::min);
+        let max = channel.iter().copied().fold(f32::MIN, f32::max);
+        let (min, max) = if min < max {
+            (min, max)
+        } else {
+            (min - 1.0, min + 1.0)
+        };
+        (edf_number(min, f32::floor), edf_number(max, f32::ceil))
+    });
+
+    let mut header = String::new();
+    let mut field = |value: &str, width: usize| header.push_str(&format!("{value:<width$.width$}"));
+    field("0", 8);
+    field("X X X X", 80);
+    field("Startdate X X X HLK-LD6002", 80);
+    field("01.01.00", 8);
+    field("00.00.00", 8);
+    field(&(256 * (CHANNELS + 1)).to_string(), 8);
+    field("", 44);
+    field(&records.to_string(), 8);
+    field("1", 8);
+    field(&CHANNELS.to_string(), 4);
+    for channel in 1..=CHANNELS {
+        field(&format!("Phase {channel}"), 16);
+    }
+    for _ in 0..CHANNELS {
+        field("Radar phase", 80);
+    }
+    for _ in 0..CHANNELS {
+        field("", 8);
+    }
+    for (min, _) in ranges {
+        field(&format!("{min}"), 8);
+    }
+    for (_, max) in ranges {
+        field(&format!("{max}"), 8);
+    }
+    for _ in 0..CHANNELS {
+        field(&i16::MIN.to_string(), 8);
+    }
+    for _ in 0..CHANNELS {
+        field(&i16::MAX.to_string(), 8);
+    }
+    for _ in 0..CHANNELS {
+        field("", 80);
+    }
+    for _ in 0..CHANNELS {
+        field(&rate.to_string(), 8);
+    }
+    for _ in 0..CHANNELS {
+        field("", 32);
+    }
+    out.write_all(header.as_bytes())?;
+
+    for record in 0..records {
+        for (channel, (min, max)) in channels.iter().zip(ranges) {
+            for i in record * rate..(record + 1) * rate {
+                let value = channel.get(i).copied().unwrap_or(min);
+                let digital = (value - min) / (max - min) * u16::MAX as f32 + i16::MIN as f32;
+                out.write_all(&(digital as i16).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
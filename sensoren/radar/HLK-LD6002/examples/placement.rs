@@ -0,0 +1,122 @@
+//! Live placement assistant
+//!
+//! Shows the reporting rate, distance stability and vitals of the sensor with their confidence,
+//! together with hints for improving the mounting position.
+
+use embedded_io_adapters::std::FromStd;
+use hlk_ld6002::confidence::ConfidenceEstimator;
+use hlk_ld6002::{Data, DataEvent, Field, MessageStream, MessageType};
+use serialport::ClearBuffer;
+use std::collections::VecDeque;
+use std::env::args;
+use std::time::{Duration, Instant};
+
+/// Number of distance readings used to judge the stability
+const HISTORY: usize = 50;
+
+fn main() {
+    let port = args().nth(1).expect("no port provided");
+    let port = serialport::new(port, 1_382_400)
+        .timeout(Duration::from_millis(50))
+        .open()
+        .expect("Failed to open port");
+    port.clear(ClearBuffer::All).expect("clear");
+
+    let start = Instant::now();
+    let mut messages = MessageStream::with_clock(FromStd::new(port), move || {
+        start.elapsed().as_millis() as u64
+    });
+
+    let mut data = Data::default();
+    let mut confidence = ConfidenceEstimator::default();
+    let mut distances = VecDeque::with_capacity(HISTORY);
+
+    let mut last = Instant::now();
+
+    print!("{}", termion::clear::All);
+
+    while let Some(message) = messages.next() {
+        let Ok(message) = message else {
+            continue;
+        };
+        match confidence.apply(message, &mut data) {
+            DataEvent::Updated(Field::Distance) => {
+                if distances.len() == HISTORY {
+                    distances.pop_front();
+                }
+                distances.push_back(data.distance);
+            }
+            DataEvent::NoTarget => {
+                // the readings belonged to a subject that is gone
+                distances.clear();
+                data = Data::default();
+                confidence.reset();
+            }
+            _ => {}
+        }
+
+        if last.elapsed() > Duration::from_millis(250) {
+            last = Instant::now();
+            let rates = messages.rates();
+            let (mean, deviation) = stats(&distances);
+
+            print!(
+                "{}{}",
+                termion::cursor::Goto(1, 1),
+                termion::clear::AfterCursor
+            );
+            println!("distance:    {mean:.2}m (±{deviation:.3}m)\r");
+            println!(
+                "respiratory: {:.1}/min ({:.0}% confidence)\r",
+                data.respiratory,
+                data.respiratory_confidence * 100.0
+            );
+            println!(
+                "heartbeat:   {:.1}/min ({:.0}% confidence)\r",
+                data.heartbeat,
+                data.heartbeat_confidence * 100.0
+            );
+            for ty in [
+                MessageType::Phase,
+                MessageType::Respiratory,
+                MessageType::Heartbeat,
+                MessageType::Distance,
+            ] {
+                println!(
+                    "{:<12} {:.1} fps\r",
+                    format!("{ty:?}:"),
+                    rates.get(ty).unwrap_or(0.0)
+                );
+            }
+            println!("\r");
+            println!("{}\r", advice(&data, mean, deviation));
+        }
+    }
+}
+
+fn stats(values: &VecDeque<f32>) -> (f32, f32) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+    (mean, variance.sqrt())
+}
+
+fn advice(data: &Data, distance: f32, deviation: f32) -> &'static str {
+    if distance == 0.0 {
+        "no target detected, point the sensor at the chest of the subject"
+    } else if distance < 0.3 {
+        "too close, move the sensor further away"
+    } else if distance > 1.5 {
+        "too far, move the sensor closer to the subject"
+    } else if deviation > 0.1 {
+        "distance is unstable, the angle might be too steep or the subject is moving"
+    } else if data.respiratory == 0.0 || data.heartbeat == 0.0 {
+        "target detected, waiting for vitals"
+    } else if data.respiratory_confidence < 0.5 || data.heartbeat_confidence < 0.5 {
+        "vitals are unreliable, the subject might be moving"
+    } else {
+        "good placement"
+    }
+}
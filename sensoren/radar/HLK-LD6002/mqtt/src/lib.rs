@@ -0,0 +1,192 @@
+//! Publishing the readings of an HLK-LD6002 to MQTT, with [Home Assistant discovery](https://www.home-assistant.io/integrations/mqtt/#mqtt-discovery)
+//!
+//! [`Publisher`] announces the heartbeat, respiration, distance and presence of a sensor as entities of
+//! one device, so they show up in Home Assistant without any configuration, and publishes the readings
+//! as a single JSON state message.
+//!
+//! ```rust,no_run
+//! use hlk_ld6002::Data;
+//! use hlk_ld6002_mqtt::{Config, Publisher};
+//!
+//! let config = Config::new("bedroom", "Bedroom radar");
+//! let (client, mut connection) = rumqttc::Client::new(config.mqtt_options("localhost", 1883), 10);
+//! std::thread::spawn(move || for _ in connection.iter() {});
+//!
+//! let mut publisher = Publisher::new(client, config);
+//! publisher.announce().unwrap();
+//! publisher.publish(&Data::default(), false).unwrap();
+//! ```
+
+use hlk_ld6002::Data;
+use rumqttc::{Client, ClientError, LastWill, MqttOptions, QoS};
+use serde_json::json;
+
+/// Naming of the device and its topics
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Unique id of the sensor, used in topics and entity ids
+    pub node_id: String,
+    /// Name of the device shown in Home Assistant
+    pub name: String,
+    /// Prefix Home Assistant listens to for discovery, `homeassistant` by default
+    pub discovery_prefix: String,
+    /// Prefix of the state and availability topics, `hlk_ld6002/<node_id>` by default
+    pub base_topic: String,
+}
+
+impl Config {
+    pub fn new(node_id: &str, name: &str) -> Self {
+        Config {
+            node_id: node_id.into(),
+            name: name.into(),
+            discovery_prefix: "homeassistant".into(),
+            base_topic: format!("hlk_ld6002/{node_id}"),
+        }
+    }
+
+    /// Topic of the JSON state message
+    pub fn state_topic(&self) -> String {
+        format!("{}/state", self.base_topic)
+    }
+
+    /// Topic of the availability, `online` while connected and `offline` after the connection is lost
+    pub fn availability_topic(&self) -> String {
+        format!("{}/availability", self.base_topic)
+    }
+
+    /// Connection options for the broker at `host`, with a last will marking the sensor offline
+    pub fn mqtt_options(&self, host: &str, port: u16) -> MqttOptions {
+        let mut options = MqttOptions::new(format!("hlk_ld6002_{}", self.node_id), host, port);
+        options.set_last_will(LastWill::new(
+            self.availability_topic(),
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        options
+    }
+
+    /// The discovery messages as `(topic, payload)` for all entities
+    pub fn discovery(&self) -> Vec<(String, String)> {
+        let device = json!({
+            "identifiers": [format!("hlk_ld6002_{}", self.node_id)],
+            "name": self.name,
+            "manufacturer": "Hi-Link",
+            "model": "HLK-LD6002",
+        });
+        let entities = [
+            (
+                "sensor",
+                "heartbeat",
+                "Heartbeat",
+                Some("bpm"),
+                None,
+                "mdi:heart-pulse",
+            ),
+            (
+                "sensor",
+                "respiratory",
+                "Respiration",
+                Some("rpm"),
+                None,
+                "mdi:lungs",
+            ),
+            (
+                "sensor",
+                "distance",
+                "Distance",
+                Some("m"),
+                Some("distance"),
+                "mdi:signal-distance-variant",
+            ),
+            (
+                "binary_sensor",
+                "presence",
+                "Presence",
+                None,
+                Some("occupancy"),
+                "mdi:account",
+            ),
+        ];
+        entities
+            .into_iter()
+            .map(|(component, field, name, unit, class, icon)| {
+                let mut config = json!({
+                    "name": name,
+                    "unique_id": format!("hlk_ld6002_{}_{field}", self.node_id),
+                    "state_topic": self.state_topic(),
+                    "value_template": format!("{{{{ value_json.{field} }}}}"),
+                    "availability_topic": self.availability_topic(),
+                    "icon": icon,
+                    "device": device,
+                });
+                if let Some(unit) = unit {
+                    config["unit_of_measurement"] = unit.into();
+                    config["state_class"] = "measurement".into();
+                }
+                if let Some(class) = class {
+                    config["device_class"] = class.into();
+                }
+                let topic = format!(
+                    "{}/{component}/hlk_ld6002_{}/{field}/config",
+                    self.discovery_prefix, self.node_id
+                );
+                (topic, config.to_string())
+            })
+            .collect()
+    }
+}
+
+/// The JSON state message for the readings
+///
+/// Rates and distances of 0 mean there is no reading and are published as `null`,
+/// which Home Assistant shows as unknown.
+pub fn state(data: &Data, present: bool) -> String {
+    let value = |value: f32| (value > 0.0).then_some(value);
+    json!({
+        "heartbeat": value(data.heartbeat),
+        "respiratory": value(data.respiratory),
+        "distance": value(data.distance),
+        "presence": if present { "ON" } else { "OFF" },
+    })
+    .to_string()
+}
+
+/// Publishes the readings of one sensor
+pub struct Publisher {
+    client: Client,
+    config: Config,
+}
+
+impl Publisher {
+    /// Publish using `client`, whose connection needs to be polled on another thread
+    pub fn new(client: Client, config: Config) -> Self {
+        Publisher { client, config }
+    }
+
+    /// Send the discovery messages and mark the sensor online
+    ///
+    /// Should be called again after reconnecting, as Home Assistant might have restarted.
+    pub fn announce(&mut self) -> Result<(), ClientError> {
+        for (topic, payload) in self.config.discovery() {
+            self.client
+                .publish(topic, QoS::AtLeastOnce, true, payload)?;
+        }
+        self.client.publish(
+            self.config.availability_topic(),
+            QoS::AtLeastOnce,
+            true,
+            "online",
+        )
+    }
+
+    /// Publish the readings, see [`state`]
+    pub fn publish(&mut self, data: &Data, present: bool) -> Result<(), ClientError> {
+        self.client.publish(
+            self.config.state_topic(),
+            QoS::AtMostOnce,
+            false,
+            state(data, present),
+        )
+    }
+}
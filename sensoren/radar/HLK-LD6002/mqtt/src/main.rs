@@ -0,0 +1,87 @@
+//! Publish the readings of a sensor on a serial port to an MQTT broker
+//!
+//! ```text
+//! hlk_ld6002_mqtt <port> <broker host> [node id] [name]
+//! ```
+
+use embedded_io_adapters::std::FromStd;
+use hlk_ld6002::presence::{Presence, PresenceDetector};
+use hlk_ld6002::{MessageStream, TimedData};
+use hlk_ld6002_mqtt::{Config, Publisher};
+use rumqttc::{Client, Event, Packet};
+use serialport::ClearBuffer;
+use std::env::args;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Readings older than this are published as unknown
+const MAX_AGE: u64 = 5_000;
+const INTERVAL: Duration = Duration::from_secs(1);
+
+fn main() {
+    let mut args = args().skip(1);
+    let (Some(port), Some(host)) = (args.next(), args.next()) else {
+        eprintln!("usage: hlk_ld6002_mqtt <port> <broker host> [node id] [name]");
+        std::process::exit(1);
+    };
+    let node_id = args.next().unwrap_or_else(|| "ld6002".into());
+    let name = args.next().unwrap_or_else(|| "HLK-LD6002".into());
+    let config = Config::new(&node_id, &name);
+
+    let (client, mut connection) = Client::new(config.mqtt_options(&host, 1883), 10);
+    // announce again on every (re)connect
+    let connected = Arc::new(AtomicBool::new(false));
+    let reconnected = connected.clone();
+    std::thread::spawn(move || {
+        for event in connection.iter() {
+            match event {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    reconnected.store(true, Ordering::Relaxed);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("mqtt connection error: {e}");
+                    std::thread::sleep(Duration::from_secs(5));
+                }
+            }
+        }
+    });
+    let mut publisher = Publisher::new(client, config);
+
+    let port = serialport::new(port, 1_382_400)
+        .timeout(Duration::from_millis(50))
+        .open()
+        .expect("Failed to open port");
+    port.clear(ClearBuffer::All).expect("clear");
+
+    let start = Instant::now();
+    let now = move || start.elapsed().as_millis() as u64;
+    let messages = MessageStream::with_clock(FromStd::new(port), now).with_resync(true);
+
+    let mut data = TimedData::default();
+    let mut presence = PresenceDetector::default();
+    let mut last = Instant::now();
+
+    // read timeouts are returned as errors, so this keeps publishing while the sensor is silent
+    for message in messages {
+        let now = now();
+        if let Ok(message) = message {
+            presence.update(now, &message);
+            data.update(now, message);
+        }
+
+        if connected.swap(false, Ordering::Relaxed) {
+            if let Err(e) = publisher.announce() {
+                eprintln!("failed to announce: {e}");
+            }
+        }
+        if last.elapsed() >= INTERVAL {
+            last = Instant::now();
+            let present = presence.state(now) == Presence::Present;
+            if let Err(e) = publisher.publish(&data.fresh(now, MAX_AGE), present) {
+                eprintln!("failed to publish: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use hlk_ld6002::Data;
+use hlk_ld6002_mqtt::{state, Config};
+use serde_json::Value;
+
+#[test]
+fn discovery_topics() {
+    let config = Config::new("bedroom", "Bedroom radar");
+    let topics: Vec<_> = config
+        .discovery()
+        .into_iter()
+        .map(|(topic, _)| topic)
+        .collect();
+    assert_eq!(
+        topics,
+        [
+            "homeassistant/sensor/hlk_ld6002_bedroom/heartbeat/config",
+            "homeassistant/sensor/hlk_ld6002_bedroom/respiratory/config",
+            "homeassistant/sensor/hlk_ld6002_bedroom/distance/config",
+            "homeassistant/binary_sensor/hlk_ld6002_bedroom/presence/config",
+        ]
+    );
+}
+
+#[test]
+fn discovery_payloads() {
+    let config = Config::new("bedroom", "Bedroom radar");
+    let payloads: Vec<Value> = config
+        .discovery()
+        .into_iter()
+        .map(|(_, payload)| serde_json::from_str(&payload).unwrap())
+        .collect();
+
+    let heartbeat = &payloads[0];
+    assert_eq!(heartbeat["unit_of_measurement"], "bpm");
+    assert_eq!(heartbeat["state_topic"], "hlk_ld6002/bedroom/state");
+    assert_eq!(heartbeat["value_template"], "{{ value_json.heartbeat }}");
+    assert_eq!(heartbeat["unique_id"], "hlk_ld6002_bedroom_heartbeat");
+    assert_eq!(
+        heartbeat["availability_topic"],
+        "hlk_ld6002/bedroom/availability"
+    );
+    assert_eq!(payloads[1]["unit_of_measurement"], "rpm");
+    assert_eq!(payloads[2]["unit_of_measurement"], "m");
+    assert_eq!(payloads[2]["device_class"], "distance");
+
+    let presence = &payloads[3];
+    assert_eq!(presence["device_class"], "occupancy");
+    assert!(presence.get("unit_of_measurement").is_none());
+
+    // all entities belong to the same device
+    for payload in &payloads {
+        assert_eq!(payload["device"], payloads[0]["device"]);
+    }
+    assert_eq!(payloads[0]["device"]["name"], "Bedroom radar");
+}
+
+#[test]
+fn state_payload() {
+    let data = Data {
+        heartbeat: 62.0,
+        respiratory: 14.0,
+        ..Data::default()
+    };
+    let published: Value = serde_json::from_str(&state(&data, true)).unwrap();
+    assert_eq!(published["heartbeat"], 62.0);
+    assert_eq!(published["respiratory"], 14.0);
+    assert!(published["distance"].is_null());
+    assert_eq!(published["presence"], "ON");
+
+    let published: Value = serde_json::from_str(&state(&Data::default(), false)).unwrap();
+    assert_eq!(published["presence"], "OFF");
+}
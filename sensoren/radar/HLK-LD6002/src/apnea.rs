@@ -0,0 +1,147 @@
+//! Detecting pauses and irregularities in the breathing
+//!
+//! While someone breathes, the phase keeps swinging with the movement of the chest. [`ApneaDetector`]
+//! watches the peak to peak amplitude of the phase over a sliding window and reports when it stays flat
+//! for longer than a minimum pause, and watches the spread of the reported respiratory rates for
+//! irregular breathing.
+//!
+//! The thresholds depend on the mounting and the subject, so they need tuning against recordings.
+//! This is not a medical device.
+
+use crate::window::Window;
+use crate::MessageBody;
+
+/// A change in the breathing reported by an [`ApneaDetector`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BreathingEvent {
+    /// The phase has been flat for at least the minimum pause
+    BreathingStopped,
+    /// The phase is moving again after breathing stopped
+    BreathingResumed,
+    /// The respiratory rate started varying more than the configured spread
+    IrregularBreathing,
+}
+
+/// Thresholds used by an [`ApneaDetector`]
+#[derive(Debug, Clone, Copy)]
+pub struct ApneaConfig {
+    /// Peak to peak phase amplitude below which the breathing counts as stopped
+    pub min_amplitude: f32,
+    /// Milliseconds of phase the amplitude is measured over
+    pub amplitude_window: u64,
+    /// Milliseconds the amplitude needs to stay low before breathing counts as stopped
+    pub min_pause: u64,
+    /// Spread of the respiratory rates, `(max - min) / average`, above which the breathing is irregular
+    pub max_spread: f32,
+    /// Milliseconds of respiratory rates the spread is measured over
+    pub rate_window: u64,
+}
+
+impl Default for ApneaConfig {
+    fn default() -> Self {
+        ApneaConfig {
+            min_amplitude: 0.2,
+            amplitude_window: 4_000,
+            min_pause: 10_000,
+            max_spread: 0.5,
+            rate_window: 60_000,
+        }
+    }
+}
+
+/// Watches the phase and respiratory rate for pauses and irregular breathing
+///
+/// `N` needs to hold the phase reports of the amplitude window and the rate reports of the rate window,
+/// e.g. 80 for a 4 second amplitude window with phase reports at 20 Hz.
+#[derive(Debug, Clone)]
+pub struct ApneaDetector<const N: usize> {
+    config: ApneaConfig,
+    phase: Window<N>,
+    rates: Window<N>,
+    first_phase: Option<u64>,
+    flat_since: Option<u64>,
+    stopped: bool,
+    irregular: bool,
+}
+
+impl<const N: usize> ApneaDetector<N> {
+    pub fn new(config: ApneaConfig) -> Self {
+        ApneaDetector {
+            config,
+            phase: Window::default(),
+            rates: Window::default(),
+            first_phase: None,
+            flat_since: None,
+            stopped: false,
+            irregular: false,
+        }
+    }
+
+    /// Feed a message received at `now` milliseconds, returning a change in the breathing
+    pub fn update(&mut self, now: u64, message: &MessageBody) -> Option<BreathingEvent> {
+        match *message {
+            MessageBody::Phase([phase, ..]) => {
+                self.phase.push(now, phase);
+                self.update_amplitude(now)
+            }
+            MessageBody::Respiratory(rate) if rate > 0.0 => {
+                self.rates.push(now, rate);
+                self.update_spread(now)
+            }
+            _ => None,
+        }
+    }
+
+    fn update_amplitude(&mut self, now: u64) -> Option<BreathingEvent> {
+        let first = *self.first_phase.get_or_insert(now);
+        if now.saturating_sub(first) < self.config.amplitude_window {
+            return None;
+        }
+        let aggregate = self.phase.aggregate(now, self.config.amplitude_window)?;
+        if aggregate.max - aggregate.min >= self.config.min_amplitude {
+            self.flat_since = None;
+            return core::mem::take(&mut self.stopped).then_some(BreathingEvent::BreathingResumed);
+        }
+
+        // the amplitude is measured over the window, so the pause started at its beginning
+        let since = *self
+            .flat_since
+            .get_or_insert(now.saturating_sub(self.config.amplitude_window));
+        if !self.stopped && now.saturating_sub(since) >= self.config.min_pause {
+            self.stopped = true;
+            return Some(BreathingEvent::BreathingStopped);
+        }
+        None
+    }
+
+    fn update_spread(&mut self, now: u64) -> Option<BreathingEvent> {
+        let aggregate = self.rates.aggregate(now, self.config.rate_window)?;
+        let irregular = (aggregate.max - aggregate.min) / aggregate.avg > self.config.max_spread;
+        let started = irregular && !self.irregular;
+        self.irregular = irregular;
+        started.then_some(BreathingEvent::IrregularBreathing)
+    }
+
+    /// Whether the breathing is currently stopped
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Whether the respiratory rate is currently irregular
+    pub fn is_irregular(&self) -> bool {
+        self.irregular
+    }
+
+    /// Forget the observed readings, e.g. when the subject left
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+}
+
+impl<const N: usize> Default for ApneaDetector<N> {
+    fn default() -> Self {
+        Self::new(ApneaConfig::default())
+    }
+}
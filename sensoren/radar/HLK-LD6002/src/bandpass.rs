@@ -0,0 +1,129 @@
+//! Band-pass filtering of the phase into breathing and heartbeat signals
+//!
+//! The phase reported by the sensor follows the movement of the chest, which combines breathing,
+//! the much smaller movement caused by the heartbeat, and slow drift from the person shifting around.
+//! [`PhaseFilter`] separates the breathing and heartbeat bands with a band-pass [`Biquad`] each.
+//!
+//! The filters run on fixed-point coefficients and state, so they don't accumulate rounding drift on long
+//! recordings. The samples are still converted from and to `f32`. The coefficients depend on the rate of
+//! the phase reports, which can be measured with [`Rates`](crate::Rates).
+
+use crate::MessageBody;
+use core::f32::consts::PI;
+
+/// Fractional bits of the coefficients
+const COEFFICIENT_BITS: u32 = 29;
+/// Fractional bits of the samples and state
+const SAMPLE_BITS: u32 = 12;
+
+/// Breathing band, 6 to 30 breaths per minute
+pub const BREATHING_BAND: (f32, f32) = (0.1, 0.5);
+/// Heartbeat band, 48 to 150 beats per minute
+pub const HEART_BAND: (f32, f32) = (0.8, 2.5);
+
+/// A second order IIR filter with fixed-point coefficients
+#[derive(Debug, Clone)]
+pub struct Biquad {
+    b: [i64; 3],
+    a: [i64; 2],
+    x: [i64; 2],
+    y: [i64; 2],
+}
+
+impl Biquad {
+    /// A band-pass filter passing frequencies between `low` and `high` Hz with 0 dB gain at the center
+    ///
+    /// # Panics
+    ///
+    /// Panics if the band isn't below half of the sample rate.
+    pub fn band_pass(sample_rate: f32, low: f32, high: f32) -> Self {
+        assert!(
+            0.0 < low && low < high && high < sample_rate / 2.0,
+            "band outside of the sample rate"
+        );
+        let center = libm::sqrtf(low * high);
+        let w0 = 2.0 * PI * center / sample_rate;
+        let octaves = libm::log2f(high / low);
+        let (sin, cos) = (libm::sinf(w0), libm::cosf(w0));
+        let alpha = sin * libm::sinhf(core::f32::consts::LN_2 / 2.0 * octaves * w0 / sin);
+
+        let a0 = 1.0 + alpha;
+        Biquad {
+            b: [alpha / a0, 0.0, -alpha / a0].map(fixed),
+            a: [-2.0 * cos / a0, (1.0 - alpha) / a0].map(fixed),
+            x: [0; 2],
+            y: [0; 2],
+        }
+    }
+
+    /// Filter the next sample
+    pub fn update(&mut self, sample: f32) -> f32 {
+        let x = libm::roundf(sample * (1 << SAMPLE_BITS) as f32) as i64;
+        let y = (self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1])
+            >> COEFFICIENT_BITS;
+
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y as f32 / (1 << SAMPLE_BITS) as f32
+    }
+
+    /// Clear the filter state, e.g. after a gap in the samples
+    pub fn reset(&mut self) {
+        self.x = [0; 2];
+        self.y = [0; 2];
+    }
+}
+
+fn fixed(coefficient: f32) -> i64 {
+    libm::roundf(coefficient * (1u32 << COEFFICIENT_BITS) as f32) as i64
+}
+
+/// The phase split into the breathing and heartbeat bands
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FilteredPhase {
+    pub breathing: f32,
+    pub heart: f32,
+}
+
+/// Splits the phase reports into breathing and heartbeat signals
+#[derive(Debug, Clone)]
+pub struct PhaseFilter {
+    breathing: Biquad,
+    heart: Biquad,
+}
+
+impl PhaseFilter {
+    /// Filters for phase reports arriving at `sample_rate` Hz, using the default bands
+    pub fn new(sample_rate: f32) -> Self {
+        Self::with_bands(sample_rate, BREATHING_BAND, HEART_BAND)
+    }
+
+    /// Filters for phase reports arriving at `sample_rate` Hz, with custom `(low, high)` bands in Hz
+    pub fn with_bands(sample_rate: f32, breathing: (f32, f32), heart: (f32, f32)) -> Self {
+        PhaseFilter {
+            breathing: Biquad::band_pass(sample_rate, breathing.0, breathing.1),
+            heart: Biquad::band_pass(sample_rate, heart.0, heart.1),
+        }
+    }
+
+    /// Filter a message, returns `None` for messages other than [`MessageBody::Phase`]
+    ///
+    /// The first of the phase values is filtered.
+    pub fn update(&mut self, message: &MessageBody) -> Option<FilteredPhase> {
+        let MessageBody::Phase([phase, ..]) = message else {
+            return None;
+        };
+        Some(FilteredPhase {
+            breathing: self.breathing.update(*phase),
+            heart: self.heart.update(*phase),
+        })
+    }
+
+    /// Clear the filter state, e.g. after a gap in the phase reports
+    pub fn reset(&mut self) {
+        self.breathing.reset();
+        self.heart.reset();
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{checksum, Frame, FrameData, FrameHeader, LdError, Quirks, RawFrame, Stats};
+
+/// Length of the start of frame byte and the header
+const HEADER_LEN: usize = 8;
+/// Length of the largest frame: header, payload and checksum
+pub(crate) const MAX_FRAME: usize = HEADER_LEN + 16 + 1;
+
+/// Collects the bytes of a single frame across multiple reads
+///
+/// Progress is kept between reads, so a read that is cancelled or fails halfway through
+/// a frame doesn't lose the bytes received so far. Since all received bytes pass through the buffer,
+/// it also keeps the [`Stats`] of the link.
+#[derive(Debug, Clone)]
+pub(crate) struct FrameBuffer {
+    bytes: [u8; MAX_FRAME],
+    filled: usize,
+    /// Whether bytes were discarded by a resync since the last valid frame
+    resyncing: bool,
+    stats: Stats,
+}
+
+impl FrameBuffer {
+    pub const fn new() -> Self {
+        FrameBuffer {
+            bytes: [0; MAX_FRAME],
+            filled: 0,
+            resyncing: false,
+            stats: Stats {
+                frames: 0,
+                checksum_failures: 0,
+                unknown_types: 0,
+                resyncs: 0,
+                bytes_discarded: 0,
+                oversized_frames: 0,
+                longest_oversized: 0,
+                #[cfg(feature = "latency")]
+                latency: crate::latency::Latency::EMPTY,
+            },
+        }
+    }
+
+    /// The part of the buffer that needs to be filled next
+    ///
+    /// Returns an empty slice once the frame is complete. An invalid start of frame or header
+    /// is reported as soon as it's received, after which the buffer needs to be [`clear`](Self::clear)ed
+    /// or [`resync`](Self::resync)ed.
+    pub fn remaining<E>(&mut self, quirks: &Quirks) -> Result<&mut [u8], LdError<E>> {
+        let needed = self.needed(quirks).inspect_err(|e| self.count_error(e))?;
+        Ok(&mut self.bytes[self.filled..needed])
+    }
+
+    /// Discard all buffered bytes
+    pub fn clear(&mut self) {
+        self.discard(self.filled);
+        self.filled = 0;
+    }
+
+    /// Discard the buffered bytes up to the next start of frame byte, returning the number of discarded bytes
+    ///
+    /// The bytes after the start of the current (invalid) frame are kept, since the next valid frame
+    /// might already have started inside of them. Consecutive resyncs until the next valid frame
+    /// are counted as a single one.
+    pub fn resync(&mut self) -> usize {
+        let skip = self.bytes[1..self.filled.max(1)]
+            .iter()
+            .position(|byte| *byte == 1)
+            .map_or(self.filled, |position| position + 1);
+        self.bytes.copy_within(skip..self.filled, 0);
+        self.filled -= skip;
+        if !self.resyncing {
+            self.resyncing = true;
+            self.stats.resyncs = self.stats.resyncs.wrapping_add(1);
+        }
+        self.discard(skip);
+        skip
+    }
+
+    /// Mark `read` more bytes of [`remaining`](Self::remaining) as filled
+    pub fn advance(&mut self, read: usize) {
+        self.filled += read;
+    }
+
+    /// Whether part of a frame has been received
+    pub fn is_partial(&self) -> bool {
+        self.filled > 0
+    }
+
+    /// Whether exactly the header of a frame has been received so far
+    pub fn is_header_complete(&self) -> bool {
+        self.filled == HEADER_LEN
+    }
+
+    /// Total number of bytes needed for the frame, based on what has been received so far
+    fn needed<E>(&self, quirks: &Quirks) -> Result<usize, LdError<E>> {
+        if self.filled == 0 {
+            return Ok(1);
+        }
+        if self.bytes[0] != 1 {
+            return Err(LdError::InvalidFrameStart(self.bytes[0]));
+        }
+        if self.filled < HEADER_LEN {
+            return Ok(HEADER_LEN);
+        }
+        let header = self.header(quirks)?;
+        FrameData::<16>::validate(&header, quirks)?;
+        Ok(HEADER_LEN + header.length as usize + 1)
+    }
+
+    pub fn header<E>(&self, quirks: &Quirks) -> Result<FrameHeader, LdError<E>> {
+        let mut header = [0; 7];
+        header.copy_from_slice(&self.bytes[1..HEADER_LEN]);
+        FrameHeader::parse(header, quirks)
+    }
+
+    /// Take the completed frame out of the buffer
+    ///
+    /// The buffer is cleared if the frame is valid, on a checksum error the buffer is left as is,
+    /// to be [`clear`](Self::clear)ed or [`resync`](Self::resync)ed.
+    pub fn take<E>(&mut self, quirks: &Quirks) -> Result<Frame, LdError<E>> {
+        let header = self.header(quirks)?;
+        let end = HEADER_LEN + header.length as usize;
+        let data = FrameData::from_slice(&self.bytes[HEADER_LEN..end]);
+        let data_checksum = self.bytes[end];
+
+        let calculated_checksum = checksum(data.as_ref());
+        if data_checksum != calculated_checksum {
+            let error = LdError::InvalidChecksum {
+                ty: "body",
+                got: calculated_checksum,
+                expected: data_checksum,
+            };
+            self.count_error(&error);
+            return Err(error);
+        };
+
+        let raw = RawFrame::from_slice(&self.bytes[..end + 1]);
+        self.filled = 0;
+        self.resyncing = false;
+        self.stats.frames = self.stats.frames.wrapping_add(1);
+        if header.ty.is_none() {
+            self.stats.unknown_types = self.stats.unknown_types.wrapping_add(1);
+        }
+        Ok(Frame { header, data, raw })
+    }
+
+    /// The link statistics of all bytes that passed through the buffer
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    #[cfg(feature = "latency")]
+    pub fn latency_mut(&mut self) -> &mut crate::latency::Latency {
+        &mut self.stats.latency
+    }
+
+    fn discard(&mut self, bytes: usize) {
+        self.stats.bytes_discarded = self.stats.bytes_discarded.wrapping_add(bytes as u32);
+    }
+
+    fn count_error<E>(&mut self, error: &LdError<E>) {
+        match *error {
+            LdError::InvalidChecksum { .. } => {
+                self.stats.checksum_failures = self.stats.checksum_failures.wrapping_add(1);
+            }
+            LdError::FrameTooLong { length, .. } => {
+                self.stats.oversized_frames = self.stats.oversized_frames.wrapping_add(1);
+                self.stats.longest_oversized = self.stats.longest_oversized.max(length);
+            }
+            _ => {}
+        }
+    }
+}
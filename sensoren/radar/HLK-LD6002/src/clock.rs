@@ -0,0 +1,21 @@
+/// A source of monotonic timestamps in milliseconds
+///
+/// Implemented for any `FnMut() -> u64`, so a closure around the platform timer is enough:
+///
+/// ```rust
+/// use std::time::Instant;
+///
+/// let start = Instant::now();
+/// let clock = move || start.elapsed().as_millis() as u64;
+/// # let _ = clock;
+/// ```
+pub trait Clock {
+    /// The current time in milliseconds, relative to an arbitrary fixed point
+    fn now_ms(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> Clock for F {
+    fn now_ms(&mut self) -> u64 {
+        self()
+    }
+}
@@ -0,0 +1,222 @@
+//! Sending commands to the sensor
+//!
+//! Commands are sent as TinyFrame frames, the same framing the sensor uses for its reports.
+//! Following the TinyFrame convention, the sensor replies with a frame carrying the same id and type,
+//! which can be read with [`read_ack`] while the sensor keeps sending reports.
+//!
+//! The typed commands are the configuration frames documented in the HLK-LD6002C (fall detection)
+//! communication protocol, the only command set with documentation available. Firmwares without
+//! fall detection likely ignore them. Commands for the report rate, detection range or toggling individual
+//! reports aren't documented for any firmware, [`Command::Raw`] sends any other frame type.
+//!
+//! ```rust,no_run
+//! use embedded_io_adapters::std::FromStd;
+//! use hlk_ld6002::command::{read_ack, write_command, Command};
+//! use hlk_ld6002::MessageStream;
+//! use std::time::Duration;
+//!
+//! let port = serialport::new("/dev/ttyUSB0", 1_382_400)
+//!     .timeout(Duration::from_millis(50))
+//!     .open()
+//!     .unwrap();
+//! let writer = port.try_clone().unwrap();
+//! let mut messages = MessageStream::new(FromStd::new(port)).with_resync(true);
+//!
+//! let command = Command::SetHeight(2.2);
+//! write_command(FromStd::new(writer), 7, &command).unwrap();
+//! match read_ack(&mut messages, 7, &command, 50).unwrap() {
+//!     Some(ack) if ack.is_success() => println!("done"),
+//!     Some(_) => println!("rejected"),
+//!     None => println!("no reply"),
+//! }
+//! ```
+
+use crate::encode::encode_frame;
+use crate::{AsyncMessageStream, Clock, LdError, MessageStream, Payload, RawFrame};
+use embedded_io::{Read, Write};
+use embedded_io_async::{Read as AsyncRead, Write as AsyncWrite};
+
+/// Length of the start of frame byte and the header
+const HEADER_LEN: usize = 8;
+/// Largest payload that can be sent
+pub const MAX_PAYLOAD: usize = 32;
+/// Length of the largest command frame
+pub const MAX_FRAME: usize = HEADER_LEN + MAX_PAYLOAD + 1;
+
+/// A command to send to the sensor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command<'a> {
+    /// Set the mounting height in meters, between 1 and 5
+    SetHeight(f32),
+    /// Set the height in meters below which a target counts as fallen, 0.6 by default
+    SetFallThreshold(f32),
+    /// Set how many frames a fall needs to be seen for, between 3 (the default) and 10
+    SetFallSensitivity(u32),
+    /// Set the area in which falls are reported, in meters from the sensor, each between 0.3 and 1.5
+    SetAlarmArea {
+        left: f32,
+        right: f32,
+        front: f32,
+        back: f32,
+    },
+    /// Turn the user log reports on or off, not acknowledged by the sensor
+    UserLog(bool),
+    /// Restore the default parameters, not acknowledged by the sensor
+    Reset,
+    /// A command frame with the given frame type and payload
+    Raw { ty: u16, data: &'a [u8] },
+}
+
+impl Command<'_> {
+    /// The frame type of the command
+    pub fn ty(&self) -> u16 {
+        match self {
+            Command::SetHeight(_) => 0x0e04,
+            Command::SetFallThreshold(_) => 0x0e08,
+            Command::SetFallSensitivity(_) => 0x0e0a,
+            Command::SetAlarmArea { .. } => 0x0e0c,
+            Command::UserLog(_) => 0x0e01,
+            Command::Reset => 0x2110,
+            Command::Raw { ty, .. } => *ty,
+        }
+    }
+
+    /// Whether the sensor replies to the command, raw commands are assumed to be acknowledged
+    pub fn is_acknowledged(&self) -> bool {
+        !matches!(self, Command::UserLog(_) | Command::Reset)
+    }
+
+    /// Write the payload of the command into `buf`, returning its length
+    fn payload(&self, buf: &mut [u8; MAX_PAYLOAD]) -> usize {
+        let values: &[[u8; 4]] = match *self {
+            Command::SetHeight(value) | Command::SetFallThreshold(value) => &[value.to_le_bytes()],
+            Command::SetFallSensitivity(frames) => &[frames.to_le_bytes()],
+            Command::SetAlarmArea {
+                left,
+                right,
+                front,
+                back,
+            } => &[
+                left.to_le_bytes(),
+                right.to_le_bytes(),
+                front.to_le_bytes(),
+                back.to_le_bytes(),
+            ],
+            Command::UserLog(on) => &[(on as u32).to_le_bytes()],
+            Command::Reset => &[],
+            Command::Raw { data, .. } => {
+                assert!(data.len() <= MAX_PAYLOAD, "command payload too long");
+                buf[..data.len()].copy_from_slice(data);
+                return data.len();
+            }
+        };
+        for (bytes, value) in buf.chunks_exact_mut(4).zip(values) {
+            bytes.copy_from_slice(value);
+        }
+        values.len() * 4
+    }
+
+    /// Encode the command as a frame with the given id into `buf`, returning the length of the frame
+    ///
+    /// # Panics
+    ///
+    /// Panics if the payload of a raw command is longer than [`MAX_PAYLOAD`].
+    pub fn encode(&self, id: u16, buf: &mut [u8; MAX_FRAME]) -> usize {
+        let mut payload = [0; MAX_PAYLOAD];
+        let len = self.payload(&mut payload);
+        encode_frame(id, self.ty(), &payload[..len], buf)
+    }
+}
+
+/// Send a command to the sensor using the frame id `id`
+pub fn write_command<W: Write>(mut writer: W, id: u16, command: &Command) -> Result<(), W::Error> {
+    let mut buf = [0; MAX_FRAME];
+    let len = command.encode(id, &mut buf);
+    writer.write_all(&buf[..len])?;
+    writer.flush()
+}
+
+/// Send a command to the sensor using the frame id `id`
+pub async fn write_command_async<W: AsyncWrite>(
+    mut writer: W,
+    id: u16,
+    command: &Command<'_>,
+) -> Result<(), W::Error> {
+    let mut buf = [0; MAX_FRAME];
+    let len = command.encode(id, &mut buf);
+    writer.write_all(&buf[..len]).await?;
+    writer.flush().await
+}
+
+/// The reply of the sensor to a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ack {
+    pub id: u16,
+    pub ty: u16,
+    data: Payload,
+}
+
+impl Ack {
+    fn new(frame: &RawFrame) -> Self {
+        Ack {
+            id: frame.id(),
+            ty: frame.ty(),
+            data: Payload::from_slice(frame.payload()),
+        }
+    }
+
+    /// The payload of the reply
+    pub fn data(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+
+    /// Whether the sensor reported that the setting was applied, for the typed commands
+    pub fn is_success(&self) -> bool {
+        self.data() == [1]
+    }
+}
+
+/// Read frames until the reply to `command`, sent with frame id `id`, is received
+///
+/// Reports and other frames received in the meantime are discarded, along with invalid or oversized
+/// frames, so enable [`with_resync`](MessageStream::with_resync) to find the reply in the middle of
+/// a stream of reports quickly. Returns `None` if no reply arrived within `frames` frames (counting discarded
+/// ones), the sensor reports a few dozen per second. Only read and end of data errors are returned.
+pub fn read_ack<R: Read, C: Clock>(
+    messages: &mut MessageStream<R, C>,
+    id: u16,
+    command: &Command,
+    frames: usize,
+) -> Result<Option<Ack>, LdError<R::Error>> {
+    for _ in 0..frames {
+        match messages.next_raw() {
+            Ok((frame, _)) if frame.id() == id && frame.ty() == command.ty() => {
+                return Ok(Some(Ack::new(&frame)))
+            }
+            Err(e @ (LdError::Read(_) | LdError::Eof)) => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Read frames until the reply to `command`, sent with frame id `id`, is received
+///
+/// See [`read_ack`].
+pub async fn read_ack_async<R: AsyncRead, C: Clock>(
+    messages: &mut AsyncMessageStream<R, C>,
+    id: u16,
+    command: &Command<'_>,
+    frames: usize,
+) -> Result<Option<Ack>, LdError<R::Error>> {
+    for _ in 0..frames {
+        match messages.next_raw().await {
+            Ok((frame, _)) if frame.id() == id && frame.ty() == command.ty() => {
+                return Ok(Some(Ack::new(&frame)))
+            }
+            Err(e @ (LdError::Read(_) | LdError::Eof)) => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,91 @@
+//! A few bytes per snapshot of the readings, for radio payloads and small displays
+//!
+//! Converting [`Data`] into [`CompactData`] and back loses the fractional digits,
+//! the flags keep track of which readings were received.
+
+use crate::Data;
+
+/// A compact representation of [`Data`] for small radio payloads and displays
+///
+/// Rates are rounded to whole breaths/beats per minute and the distance to whole centimeters,
+/// which is well within the accuracy of the sensor. Encodes to 5 bytes using [`to_bytes`](Self::to_bytes).
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CompactData {
+    pub respiratory: u8,
+    pub heartbeat: u8,
+    /// Distance in centimeters
+    pub distance: u16,
+    pub flags: u8,
+}
+
+impl CompactData {
+    /// A respiratory rate has been received
+    pub const RESPIRATORY: u8 = 1 << 0;
+    /// A heartbeat rate has been received
+    pub const HEARTBEAT: u8 = 1 << 1;
+    /// A distance has been received
+    pub const DISTANCE: u8 = 1 << 2;
+
+    /// Encode as `[respiratory, heartbeat, distance (2 bytes, little endian), flags]`
+    pub fn to_bytes(&self) -> [u8; 5] {
+        let [distance_low, distance_high] = self.distance.to_le_bytes();
+        [
+            self.respiratory,
+            self.heartbeat,
+            distance_low,
+            distance_high,
+            self.flags,
+        ]
+    }
+
+    pub fn from_bytes(bytes: [u8; 5]) -> Self {
+        CompactData {
+            respiratory: bytes[0],
+            heartbeat: bytes[1],
+            distance: u16::from_le_bytes([bytes[2], bytes[3]]),
+            flags: bytes[4],
+        }
+    }
+}
+
+impl From<Data> for CompactData {
+    fn from(data: Data) -> Self {
+        let mut flags = 0;
+        for (value, flag) in [
+            (data.respiratory, Self::RESPIRATORY),
+            (data.heartbeat, Self::HEARTBEAT),
+            (data.distance, Self::DISTANCE),
+        ] {
+            if value > 0.0 {
+                flags |= flag;
+            }
+        }
+
+        // float to int casts saturate, so out of range values are clamped
+        CompactData {
+            respiratory: libm::roundf(data.respiratory) as u8,
+            heartbeat: libm::roundf(data.heartbeat) as u8,
+            distance: libm::roundf(data.distance * 100.0) as u16,
+            flags,
+        }
+    }
+}
+
+/// Readings without their flag are left at `0.0`, the value [`Data`] uses for missing readings
+impl From<CompactData> for Data {
+    fn from(data: CompactData) -> Self {
+        let value = |flag, value: f32| {
+            if data.flags & flag != 0 {
+                value
+            } else {
+                0.0
+            }
+        };
+        Data {
+            respiratory: value(CompactData::RESPIRATORY, data.respiratory as f32),
+            distance: value(CompactData::DISTANCE, data.distance as f32 / 100.0),
+            heartbeat: value(CompactData::HEARTBEAT, data.heartbeat as f32),
+            ..Data::default()
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Confidence of the vital sign readings
+//!
+//! The sensor keeps reporting rates while the subject moves, but these readings are mostly junk.
+//! [`ConfidenceEstimator`] scores every rate between 0 and 1 from how much the phase moves,
+//! how stable the distance is and how far the rate jumped from the previous one, and stores the score
+//! in [`Data::heartbeat_confidence`] and [`Data::respiratory_confidence`].
+
+use crate::{Data, DataEvent, Field, MessageBody};
+
+/// Weight of a new sample in the running averages of the phase movement and distance jitter
+const SMOOTHING: f32 = 0.1;
+
+/// Scores the readings applied to [`Data`]
+///
+/// Each of the three scores is `scale / (scale + deviation)`, so it is 1 for a perfectly still subject
+/// and drops to 0.5 when the deviation reaches the configured scale. The confidence is their product.
+#[derive(Debug, Clone)]
+pub struct ConfidenceEstimator {
+    phase_scale: f32,
+    distance_scale: f32,
+    rate_scale: f32,
+    /// Running average of the change of the phase between reports
+    motion: f32,
+    /// Running average of the change of the distance between reports
+    jitter: f32,
+    phase: Option<f32>,
+    distance: Option<f32>,
+}
+
+impl ConfidenceEstimator {
+    /// Score with the given scales
+    ///
+    /// - `phase_scale`: average change of the phase between reports
+    /// - `distance_scale`: average change of the distance between reports in meters
+    /// - `rate_scale`: change of a rate relative to the previous reading, e.g. `0.2` for 20 %
+    pub fn new(phase_scale: f32, distance_scale: f32, rate_scale: f32) -> Self {
+        ConfidenceEstimator {
+            phase_scale,
+            distance_scale,
+            rate_scale,
+            motion: 0.0,
+            jitter: 0.0,
+            phase: None,
+            distance: None,
+        }
+    }
+
+    /// Update `data` with a message like [`Data::apply`], scoring new rates
+    pub fn apply(&mut self, message: MessageBody, data: &mut Data) -> DataEvent {
+        match message {
+            MessageBody::Phase([phase, ..]) => {
+                self.motion = smooth(self.motion, &mut self.phase, phase);
+            }
+            MessageBody::Distance(Some(distance)) if distance > 0.0 => {
+                self.jitter = smooth(self.jitter, &mut self.distance, distance);
+            }
+            _ => {}
+        }
+
+        let previous = *data;
+        let event = data.apply(message);
+        match event {
+            DataEvent::Updated(Field::Heartbeat) => {
+                data.heartbeat_confidence = self.score(previous.heartbeat, data.heartbeat);
+            }
+            DataEvent::Updated(Field::Respiratory) => {
+                data.respiratory_confidence = self.score(previous.respiratory, data.respiratory);
+            }
+            _ => {}
+        }
+        event
+    }
+
+    fn score(&self, previous: f32, rate: f32) -> f32 {
+        let jump = if previous > 0.0 {
+            (rate - previous).abs() / previous
+        } else {
+            0.0
+        };
+        let score = |scale: f32, deviation: f32| scale / (scale + deviation);
+        score(self.phase_scale, self.motion)
+            * score(self.distance_scale, self.jitter)
+            * score(self.rate_scale, jump)
+    }
+
+    /// Forget the movement history, e.g. after a gap in the reports
+    pub fn reset(&mut self) {
+        self.motion = 0.0;
+        self.jitter = 0.0;
+        self.phase = None;
+        self.distance = None;
+    }
+}
+
+impl Default for ConfidenceEstimator {
+    fn default() -> Self {
+        Self::new(0.5, 0.05, 0.2)
+    }
+}
+
+/// Add the change from `last` to `value` to the running `average`
+fn smooth(average: f32, last: &mut Option<f32>, value: f32) -> f32 {
+    let average = match *last {
+        Some(last) => average + SMOOTHING * ((value - last).abs() - average),
+        None => average,
+    };
+    *last = Some(value);
+    average
+}
@@ -0,0 +1,52 @@
+//! Dropping frames received twice through redundant transports
+//!
+//! When the same sensor is reachable over two links (e.g. UART and BLE), every report arrives twice.
+//! The sensor numbers its frames, so the copies carry the same frame id and identical bytes
+//! and arrive within a short time of each other, which is used to recognize them. Two reports with
+//! the same reading (like a steady heartbeat) have different frame ids and are both kept.
+//! Read the frames with [`MessageStream::next_raw`](crate::MessageStream::next_raw) to get their bytes.
+
+use crate::RawFrame;
+
+/// Remembers the last `N` frames to detect duplicates arriving within a time window
+#[derive(Debug, Clone)]
+pub struct Dedup<const N: usize> {
+    seen: [Option<(u64, RawFrame)>; N],
+    next: usize,
+    window: u64,
+}
+
+impl<const N: usize> Dedup<N> {
+    /// Consider identical frames arriving within `window` milliseconds of each other duplicates
+    ///
+    /// `N` needs to be large enough to hold all frames received during the window from all transports.
+    pub fn new(window: u64) -> Self {
+        const { assert!(N > 0, "dedup needs to remember at least one frame") };
+        Dedup {
+            seen: [None; N],
+            next: 0,
+            window,
+        }
+    }
+
+    /// Check whether `frame` received at `now` milliseconds is a duplicate of a recently seen frame
+    ///
+    /// Frames that aren't duplicates are remembered, a duplicate is only reported once,
+    /// so a third copy of the same frame counts as new.
+    pub fn is_duplicate(&mut self, now: u64, frame: &RawFrame) -> bool {
+        let duplicate = self.seen.iter_mut().find(|seen| {
+            matches!(seen, Some((time, seen)) if now.saturating_sub(*time) <= self.window && seen == frame)
+        });
+        match duplicate {
+            Some(seen) => {
+                *seen = None;
+                true
+            }
+            None => {
+                self.seen[self.next] = Some((now, *frame));
+                self.next = (self.next + 1) % N;
+                false
+            }
+        }
+    }
+}
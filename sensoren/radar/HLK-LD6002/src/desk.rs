@@ -0,0 +1,122 @@
+//! Desk occupancy for sensors mounted at a monitor or under a desk
+//!
+//! [`DeskDetector`] debounces the presence at the desk with a [`PresenceDetector`] and infers a coarse
+//! posture from the smoothed distance: a sitting person is closer to a monitor mounted sensor than a
+//! standing one stepping back from a standing desk. The distance bands depend on the desk and the
+//! mounting, so they are configurable.
+
+use crate::filter::{Ewma, Smoother};
+use crate::presence::{Presence, PresenceConfig, PresenceDetector};
+use crate::MessageBody;
+
+/// Coarse posture at the desk, inferred from the distance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Posture {
+    Sitting,
+    Standing,
+    /// The distance is outside of both bands
+    Unknown,
+}
+
+/// Whether the desk is occupied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Occupancy {
+    Occupied(Posture),
+    Away,
+    /// Not enough reports received yet, or the sensor stopped reporting
+    Unknown,
+}
+
+/// Configuration of a [`DeskDetector`]
+#[derive(Debug, Clone, Copy)]
+pub struct DeskConfig {
+    /// Debouncing of the presence, the maximum distance should cover both bands
+    pub presence: PresenceConfig,
+    /// Distances in meters of a sitting person, from the nearest to the farthest
+    pub sitting: (f32, f32),
+    /// Distances in meters of a standing person, from the nearest to the farthest
+    pub standing: (f32, f32),
+    /// Weight of a new distance in the smoothed distance, between 0 and 1
+    pub smoothing: f32,
+}
+
+impl Default for DeskConfig {
+    /// Tuned for a sensor at the monitor, going away after 2 minutes without a target
+    fn default() -> Self {
+        DeskConfig {
+            presence: PresenceConfig {
+                enter_hold: 5_000,
+                leave_hold: 120_000,
+                max_distance: 1.4,
+                hysteresis: 0.2,
+                timeout: 10_000,
+                vitals: false,
+            },
+            sitting: (0.3, 0.9),
+            standing: (0.9, 1.6),
+            smoothing: 0.3,
+        }
+    }
+}
+
+/// Detects whether someone is at the desk and whether they sit or stand
+#[derive(Debug, Clone)]
+pub struct DeskDetector {
+    config: DeskConfig,
+    presence: PresenceDetector,
+    distance: Ewma,
+    smoothed: Option<f32>,
+}
+
+impl DeskDetector {
+    pub fn new(config: DeskConfig) -> Self {
+        DeskDetector {
+            config,
+            presence: PresenceDetector::new(config.presence),
+            distance: Ewma::new(config.smoothing),
+            smoothed: None,
+        }
+    }
+
+    /// Feed a message received at `now` milliseconds
+    pub fn update(&mut self, now: u64, message: &MessageBody) -> Occupancy {
+        if let MessageBody::Distance(Some(distance)) = *message {
+            if distance > 0.0 {
+                self.smoothed = Some(self.distance.update(distance));
+            }
+        }
+        self.presence.update(now, message);
+        self.state(now)
+    }
+
+    /// The occupancy as of `now` milliseconds
+    pub fn state(&self, now: u64) -> Occupancy {
+        match self.presence.state(now) {
+            Presence::Present => Occupancy::Occupied(self.posture()),
+            Presence::Absent => Occupancy::Away,
+            Presence::Unknown => Occupancy::Unknown,
+        }
+    }
+
+    fn posture(&self) -> Posture {
+        let within = |(near, far): (f32, f32), distance: f32| (near..far).contains(&distance);
+        match self.smoothed {
+            Some(distance) if within(self.config.sitting, distance) => Posture::Sitting,
+            Some(distance) if within(self.config.standing, distance) => Posture::Standing,
+            _ => Posture::Unknown,
+        }
+    }
+
+    /// Forget the current state, e.g. after restarting the sensor
+    pub fn reset(&mut self) {
+        self.presence.reset();
+        self.distance.reset();
+        self.smoothed = None;
+    }
+}
+
+impl Default for DeskDetector {
+    fn default() -> Self {
+        Self::new(DeskConfig::default())
+    }
+}
@@ -0,0 +1,72 @@
+//! Removing slow drift from phase histories
+//!
+//! The phase keeps drifting when the person shifts around, so over a long recording the breathing
+//! movement ends up as a small wiggle on top of a large offset. [`Detrend`] subtracts a slowly moving
+//! baseline from a stream of samples, [`remove_linear_trend`] straightens an already recorded history.
+
+/// Subtracts a moving baseline from a stream of samples
+///
+/// The baseline is an exponential moving average, which acts as a first order high-pass filter.
+#[derive(Debug, Clone)]
+pub struct Detrend {
+    alpha: f32,
+    baseline: Option<f32>,
+}
+
+impl Detrend {
+    /// Follow drift slower than `time_constant` seconds, for samples arriving at `sample_rate` Hz
+    ///
+    /// The time constant should be well above the period of the signal that needs to be kept,
+    /// e.g. 30 seconds for breathing.
+    pub fn new(time_constant: f32, sample_rate: f32) -> Self {
+        Detrend {
+            alpha: 1.0 - libm::expf(-1.0 / (time_constant * sample_rate)),
+            baseline: None,
+        }
+    }
+
+    /// Remove the baseline from the next sample
+    ///
+    /// The baseline starts at the first sample, so there's no settling period at the start.
+    pub fn update(&mut self, sample: f32) -> f32 {
+        let baseline = self.baseline.get_or_insert(sample);
+        *baseline += self.alpha * (sample - *baseline);
+        sample - *baseline
+    }
+
+    /// The current baseline, `None` before the first sample
+    pub fn baseline(&self) -> Option<f32> {
+        self.baseline
+    }
+
+    /// Forget the baseline, e.g. after a gap in the samples
+    pub fn reset(&mut self) {
+        self.baseline = None;
+    }
+}
+
+/// Subtract the least squares line from equally spaced samples
+pub fn remove_linear_trend(samples: &mut [f32]) {
+    let n = samples.len() as f32;
+    if samples.len() < 2 {
+        samples.fill(0.0);
+        return;
+    }
+
+    // centering the sample index keeps the sums small on long histories
+    let center = (n - 1.0) / 2.0;
+    let mean = samples.iter().sum::<f32>() / n;
+    let (covariance, variance) =
+        samples
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(covariance, variance), (i, sample)| {
+                let x = i as f32 - center;
+                (covariance + x * (sample - mean), variance + x * x)
+            });
+    let slope = covariance / variance;
+
+    for (i, sample) in samples.iter_mut().enumerate() {
+        *sample -= mean + slope * (i as f32 - center);
+    }
+}
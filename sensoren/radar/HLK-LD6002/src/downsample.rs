@@ -0,0 +1,62 @@
+//! Reducing long time series to a plottable number of points
+//!
+//! Implements "Largest-Triangle-Three-Buckets" downsampling, which keeps the visual shape
+//! of the series (peaks and dips) far better than taking every n-th sample.
+
+/// Downsample `points` into `out` using LTTB, returning the number of points written
+///
+/// Points are `(time, value)` pairs sorted by time. The first and last point are always kept.
+/// If `points` fits into `out` as-is, it's copied unchanged. An `out` of fewer than 3 points
+/// can't hold the first, last and any bucket, so only the first (and last) point are written.
+pub fn lttb(points: &[(f32, f32)], out: &mut [(f32, f32)]) -> usize {
+    let threshold = out.len();
+    if points.len() <= threshold {
+        out[..points.len()].copy_from_slice(points);
+        return points.len();
+    }
+    if threshold < 3 {
+        out[..threshold].copy_from_slice(&[points[0], points[points.len() - 1]][..threshold]);
+        return threshold;
+    }
+
+    let bucket_size = (points.len() - 2) as f32 / (threshold - 2) as f32;
+    let bucket = |i: usize| {
+        let start = (i as f32 * bucket_size) as usize + 1;
+        let end = (((i + 1) as f32 * bucket_size) as usize + 1).min(points.len() - 1);
+        start..end
+    };
+
+    let mut selected = 0;
+    out[0] = points[0];
+
+    for i in 0..threshold - 2 {
+        // average of the next bucket, or the last point for the final bucket
+        let (avg_x, avg_y) = if i + 1 < threshold - 2 {
+            let next = &points[bucket(i + 1)];
+            let (sum_x, sum_y) = next
+                .iter()
+                .fold((0.0, 0.0), |(x, y), point| (x + point.0, y + point.1));
+            (sum_x / next.len() as f32, sum_y / next.len() as f32)
+        } else {
+            points[points.len() - 1]
+        };
+
+        let (a_x, a_y) = points[selected];
+        let range = bucket(i);
+        let mut max_area = -1.0;
+        let mut max_index = range.start;
+        for (index, (x, y)) in points[range.clone()].iter().enumerate() {
+            let area = ((a_x - avg_x) * (y - a_y) - (a_x - x) * (avg_y - a_y)).abs();
+            if area > max_area {
+                max_area = area;
+                max_index = range.start + index;
+            }
+        }
+
+        out[i + 1] = points[max_index];
+        selected = max_index;
+    }
+
+    out[threshold - 1] = points[points.len() - 1];
+    threshold
+}
@@ -0,0 +1,66 @@
+//! Encoding messages into frames, the way the sensor sends them
+//!
+//! This is the counterpart of the parser, e.g. for emulating a sensor on a second UART in a test rig.
+//! Frames encoded here decode back to the same message.
+
+use crate::{checksum, MessageBody, MessageType};
+
+/// Length of the start of frame byte and the header
+const HEADER_LEN: usize = 8;
+/// Length of the largest frame sent by the sensor
+pub const MAX_FRAME: usize = crate::buffer::MAX_FRAME;
+
+/// Encode a frame with the given id, type and payload into `buf`, returning the length of the frame
+///
+/// # Panics
+///
+/// Panics if `buf` can't hold the frame, which needs 9 bytes more than the payload.
+pub fn encode_frame(id: u16, ty: u16, payload: &[u8], buf: &mut [u8]) -> usize {
+    let len = HEADER_LEN + payload.len() + 1;
+    assert!(buf.len() >= len, "buffer too small for the frame");
+
+    buf[0] = 1;
+    buf[1..3].copy_from_slice(&id.to_be_bytes());
+    buf[3..5].copy_from_slice(&(payload.len() as u16).to_be_bytes());
+    buf[5..7].copy_from_slice(&ty.to_be_bytes());
+    buf[7] = checksum(&buf[0..7]);
+    buf[HEADER_LEN..len - 1].copy_from_slice(payload);
+    buf[len - 1] = checksum(payload);
+    len
+}
+
+/// Encode a message as a frame with the given id into `buf`, returning the length of the frame
+///
+/// `Distance(None)` is encoded as the short distance frame, see [`Quirks::short_distance`](crate::Quirks).
+pub fn encode(id: u16, message: &MessageBody, buf: &mut [u8; MAX_FRAME]) -> usize {
+    let mut payload = [0; 16];
+    let (ty, len) = match message {
+        MessageBody::Phase(phase) => {
+            for (bytes, value) in payload.chunks_exact_mut(4).zip(phase) {
+                bytes.copy_from_slice(&value.to_le_bytes());
+            }
+            (MessageType::Phase as u16, 12)
+        }
+        MessageBody::Respiratory(rate) => {
+            payload[..4].copy_from_slice(&rate.to_le_bytes());
+            (MessageType::Respiratory as u16, 4)
+        }
+        MessageBody::Heartbeat(rate) => {
+            payload[..4].copy_from_slice(&rate.to_le_bytes());
+            (MessageType::Heartbeat as u16, 4)
+        }
+        MessageBody::Distance(Some(distance)) => {
+            // a distance of 0 is sent by the sensor as "no target"
+            let flag = (*distance != 0.0) as u32;
+            payload[..4].copy_from_slice(&flag.to_le_bytes());
+            payload[4..8].copy_from_slice(&distance.to_le_bytes());
+            (MessageType::Distance as u16, 8)
+        }
+        MessageBody::Distance(None) => (MessageType::Distance as u16, 4),
+        MessageBody::Unknown { ty, data } => {
+            payload[..data.as_slice().len()].copy_from_slice(data.as_slice());
+            (*ty, data.as_slice().len())
+        }
+    };
+    encode_frame(id, ty, &payload[..len], buf)
+}
@@ -0,0 +1,207 @@
+//! A common event type for the output of the detectors
+//!
+//! Every detector reports in its own type, which is fine for acting on a single detector but makes it hard
+//! to route everything to the same place. [`Event`] wraps the output of any detector together with
+//! a [`Severity`], the [`Source`] it came from and a correlation id, so the start and the end of
+//! the same episode (e.g. [`BreathingStopped`](BreathingEvent::BreathingStopped) and
+//! [`BreathingResumed`](BreathingEvent::BreathingResumed)) can be traced from the detector to whatever
+//! handles the event. [`Correlator`] assigns the ids.
+
+use crate::apnea::BreathingEvent;
+use crate::fall::PossibleFall;
+use crate::inactivity::InactivityEvent;
+use crate::intrusion::Intrusion;
+use crate::presence::Presence;
+use crate::stuck::Liveness;
+use crate::tamper::Tamper;
+
+/// How urgently an event needs attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Severity {
+    /// A change of state, like someone entering the room or an alert being resolved
+    Info,
+    /// Something that might need attention, like a blocked sensor
+    Warning,
+    /// Someone might need help, like a fall or a breathing pause
+    Critical,
+}
+
+/// Which sensor, in which room, an event came from
+///
+/// The ids are chosen by the application, e.g. indices into its own list of sensors and rooms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Source {
+    pub sensor: u16,
+    pub room: u16,
+}
+
+/// The output of a detector
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EventKind {
+    Presence(Presence),
+    Breathing(BreathingEvent),
+    Fall(PossibleFall),
+    Inactivity(InactivityEvent),
+    Intrusion(Intrusion),
+    Tamper(Tamper),
+    /// The heartbeat (or another value fed to a [`StuckDetector`](crate::stuck::StuckDetector)) froze or recovered
+    Stuck(Liveness),
+}
+
+impl EventKind {
+    /// The severity an event of this kind has by default
+    pub fn severity(&self) -> Severity {
+        match self {
+            EventKind::Breathing(BreathingEvent::BreathingStopped)
+            | EventKind::Fall(_)
+            | EventKind::Intrusion(Intrusion::Detected { .. }) => Severity::Critical,
+            EventKind::Breathing(BreathingEvent::IrregularBreathing)
+            | EventKind::Inactivity(InactivityEvent::Inactive { .. })
+            | EventKind::Tamper(Tamper::SensorBlocked | Tamper::SensorTampered)
+            | EventKind::Stuck(Liveness::Stale) => Severity::Warning,
+            _ => Severity::Info,
+        }
+    }
+
+    /// Whether this event starts an episode, ends one, or stands on its own
+    fn episode(&self) -> Episode {
+        match self {
+            EventKind::Presence(Presence::Present)
+            | EventKind::Breathing(BreathingEvent::BreathingStopped)
+            | EventKind::Inactivity(InactivityEvent::Inactive { .. })
+            | EventKind::Intrusion(Intrusion::Detected { .. })
+            | EventKind::Tamper(Tamper::SensorBlocked | Tamper::SensorTampered)
+            | EventKind::Stuck(Liveness::Stale) => Episode::Start,
+            EventKind::Presence(_)
+            | EventKind::Breathing(BreathingEvent::BreathingResumed)
+            | EventKind::Inactivity(InactivityEvent::Active)
+            | EventKind::Intrusion(Intrusion::Cleared)
+            | EventKind::Tamper(Tamper::Normal)
+            | EventKind::Stuck(Liveness::Live) => Episode::End,
+            EventKind::Breathing(BreathingEvent::IrregularBreathing) | EventKind::Fall(_) => {
+                Episode::Single
+            }
+        }
+    }
+
+    fn category(&self) -> usize {
+        match self {
+            EventKind::Presence(_) => 0,
+            EventKind::Breathing(_) => 1,
+            EventKind::Fall(_) => 2,
+            EventKind::Inactivity(_) => 3,
+            EventKind::Tamper(_) => 4,
+            EventKind::Stuck(_) => 5,
+            EventKind::Intrusion(_) => 6,
+        }
+    }
+}
+
+const CATEGORIES: usize = 7;
+
+enum Episode {
+    Start,
+    End,
+    Single,
+}
+
+macro_rules! event_kind_from {
+    ($($variant:ident($ty:ty)),*) => {
+        $(
+            impl From<$ty> for EventKind {
+                fn from(value: $ty) -> Self {
+                    EventKind::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+event_kind_from!(
+    Presence(Presence),
+    Breathing(BreathingEvent),
+    Fall(PossibleFall),
+    Inactivity(InactivityEvent),
+    Intrusion(Intrusion),
+    Tamper(Tamper),
+    Stuck(Liveness)
+);
+
+/// An event from a detector, with where it came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Event {
+    /// When the event happened, in milliseconds
+    pub at: u64,
+    pub severity: Severity,
+    pub source: Source,
+    /// Shared by all events of the same episode, e.g. a breathing pause and its end
+    pub correlation: u32,
+    pub kind: EventKind,
+}
+
+/// Turns the output of the detectors of one sensor into [`Event`]s with correlation ids
+///
+/// An event that starts an episode, like someone becoming present or the breathing stopping, gets
+/// a new correlation id, which is reused for the event ending that episode. Events that stand on their own,
+/// like a possible fall, always get a new id. Ids are unique per correlator, include the sensor in
+/// the [`Source`] when tracing across sensors.
+#[derive(Debug, Clone)]
+pub struct Correlator {
+    source: Source,
+    next: u32,
+    /// The correlation id of the open episode per category, 0 if there is none
+    open: [u32; CATEGORIES],
+}
+
+impl Correlator {
+    pub fn new(source: Source) -> Self {
+        Correlator {
+            source,
+            next: 1,
+            open: [0; CATEGORIES],
+        }
+    }
+
+    /// The event for a detector output at `now` milliseconds, with the default severity of its kind
+    ///
+    /// Only pass changes, the detectors that report a state on every update (like the presence)
+    /// would otherwise open a new episode for every message.
+    pub fn event(&mut self, now: u64, kind: impl Into<EventKind>) -> Event {
+        let kind = kind.into();
+        let open = &mut self.open[kind.category()];
+        let correlation = match kind.episode() {
+            Episode::End if *open != 0 => core::mem::take(open),
+            Episode::End | Episode::Single => next_id(&mut self.next),
+            Episode::Start => {
+                *open = next_id(&mut self.next);
+                *open
+            }
+        };
+        Event {
+            at: now,
+            severity: kind.severity(),
+            source: self.source,
+            correlation,
+            kind,
+        }
+    }
+
+    pub fn source(&self) -> Source {
+        self.source
+    }
+}
+
+/// Take the next id, skipping 0 which marks no open episode
+fn next_id(next: &mut u32) -> u32 {
+    let id = *next;
+    *next = next.wrapping_add(1).max(1);
+    id
+}
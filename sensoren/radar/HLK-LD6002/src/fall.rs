@@ -0,0 +1,167 @@
+//! Experimental detection of falls
+//!
+//! A fall shows up as the height of the target dropping quickly to close to the floor,
+//! followed by the person lying still. [`FallDetector`] converts the distance reports to a height
+//! using the [`Mounting`] of the sensor and watches for this pattern, the stillness is judged from
+//! the phase.
+//!
+//! This is a heuristic that hasn't been validated against real falls. It needs a sensor mounted
+//! above the area it watches, and will miss falls outside of it or report someone lying down
+//! quickly on the floor. Don't rely on it as the only safety measure.
+
+use crate::geometry::Mounting;
+use crate::window::Window;
+use crate::MessageBody;
+
+/// A possible fall reported by a [`FallDetector`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PossibleFall {
+    /// When the drop in height was observed, in milliseconds
+    pub at: u64,
+    /// Height of the target after the drop in meters
+    pub height: f32,
+    /// How closely the readings match a fall, between 0 and 1
+    pub confidence: f32,
+}
+
+/// Thresholds used by a [`FallDetector`]
+#[derive(Debug, Clone, Copy)]
+pub struct FallConfig {
+    /// Mounting of the sensor
+    pub mounting: Mounting,
+    /// Horizontal offset in meters of the watched area from the point below the sensor
+    pub horizontal: f32,
+    /// Minimum drop of the height in meters
+    pub min_drop: f32,
+    /// Milliseconds within which the drop has to happen
+    pub drop_duration: u64,
+    /// Height in meters below which the target counts as on the floor
+    pub floor_height: f32,
+    /// Milliseconds the target has to stay on the floor and still
+    pub still_duration: u64,
+    /// Peak to peak phase amplitude above which the target is moving
+    pub max_motion: f32,
+}
+
+impl FallConfig {
+    /// Defaults for a sensor at `height` meters above the floor, right above the watched area
+    pub fn new(height: f32) -> Self {
+        FallConfig {
+            mounting: Mounting::new(height, 0.0),
+            horizontal: 0.0,
+            min_drop: 0.6,
+            drop_duration: 2_000,
+            floor_height: 0.5,
+            still_duration: 10_000,
+            max_motion: 2.0,
+        }
+    }
+}
+
+/// A drop to the floor waiting for the stillness to be confirmed
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    at: u64,
+    drop: f32,
+    height: f32,
+    motion: Option<(f32, f32)>,
+}
+
+/// Watches the distance and phase for the pattern of a fall
+///
+/// `N` needs to hold the distance reports within the drop duration.
+#[derive(Debug, Clone)]
+pub struct FallDetector<const N: usize> {
+    config: FallConfig,
+    heights: Window<N>,
+    candidate: Option<Candidate>,
+    /// A fall was reported and the target hasn't got up since
+    reported: bool,
+}
+
+impl<const N: usize> FallDetector<N> {
+    pub fn new(config: FallConfig) -> Self {
+        FallDetector {
+            config,
+            heights: Window::default(),
+            candidate: None,
+            reported: false,
+        }
+    }
+
+    /// Feed a message received at `now` milliseconds, returning a possible fall once it's confirmed
+    pub fn update(&mut self, now: u64, message: &MessageBody) -> Option<PossibleFall> {
+        match *message {
+            MessageBody::Distance(Some(distance)) if distance > 0.0 => {
+                let config = &self.config;
+                let height = config
+                    .mounting
+                    .target_height(distance, config.horizontal)
+                    .ok()?;
+                self.update_height(now, height);
+                None
+            }
+            MessageBody::Phase([phase, ..]) => self.update_motion(now, phase),
+            _ => None,
+        }
+    }
+
+    fn update_height(&mut self, now: u64, height: f32) {
+        let highest = self
+            .heights
+            .aggregate(now, self.config.drop_duration)
+            .map(|aggregate| aggregate.max);
+        self.heights.push(now, height);
+
+        if height > self.config.floor_height {
+            self.candidate = None;
+            self.reported = false;
+            return;
+        }
+        let drop = highest.unwrap_or(height) - height;
+        if self.candidate.is_none() && !self.reported && drop >= self.config.min_drop {
+            self.candidate = Some(Candidate {
+                at: now,
+                drop,
+                height,
+                motion: None,
+            });
+        }
+    }
+
+    fn update_motion(&mut self, now: u64, phase: f32) -> Option<PossibleFall> {
+        let config = self.config;
+        let candidate = self.candidate.as_mut()?;
+        let (min, max) = candidate.motion.unwrap_or((phase, phase));
+        let (min, max) = (min.min(phase), max.max(phase));
+        candidate.motion = Some((min, max));
+
+        if max - min > config.max_motion {
+            // moving around on the floor, e.g. sitting down to pick something up
+            self.candidate = None;
+            return None;
+        }
+        if now.saturating_sub(candidate.at) < config.still_duration {
+            return None;
+        }
+
+        let drop = (candidate.drop / (2.0 * config.min_drop)).min(1.0);
+        let low = 1.0 - candidate.height / config.floor_height;
+        let still = 1.0 - (max - min) / config.max_motion;
+        let fall = PossibleFall {
+            at: candidate.at,
+            height: candidate.height,
+            confidence: ((drop + low + still) / 3.0).clamp(0.0, 1.0),
+        };
+        self.candidate = None;
+        self.reported = true;
+        Some(fall)
+    }
+
+    /// Forget the observed readings, e.g. after a fall was handled
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+}
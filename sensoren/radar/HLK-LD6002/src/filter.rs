@@ -0,0 +1,197 @@
+//! Smoothing the reported values
+//!
+//! The heartbeat rate reported by the sensor jumps by several beats between reports. The [`Smoother`]s
+//! here take the jitter out of a single value, they can be chained as a tuple (e.g. a [`Median`] to drop
+//! outliers followed by an [`Ewma`]) and applied to every field of [`Data`] with [`FilteredData`].
+
+use crate::{Data, DataEvent, Field, MessageBody};
+
+/// Smooths a sequence of values
+pub trait Smoother {
+    /// Add a value, returning the smoothed value
+    fn update(&mut self, value: f32) -> f32;
+
+    /// Forget all previous values
+    fn reset(&mut self);
+}
+
+/// Runs the first smoother and feeds its output into the second
+impl<A: Smoother, B: Smoother> Smoother for (A, B) {
+    fn update(&mut self, value: f32) -> f32 {
+        self.1.update(self.0.update(value))
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+        self.1.reset();
+    }
+}
+
+/// Average of the last `N` values
+#[derive(Debug, Clone)]
+pub struct MovingAverage<const N: usize> {
+    values: [f32; N],
+    len: usize,
+    next: usize,
+}
+
+impl<const N: usize> Default for MovingAverage<N> {
+    fn default() -> Self {
+        MovingAverage {
+            values: [0.0; N],
+            len: 0,
+            next: 0,
+        }
+    }
+}
+
+impl<const N: usize> Smoother for MovingAverage<N> {
+    fn update(&mut self, value: f32) -> f32 {
+        self.values[self.next] = value;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+        // summing again instead of keeping a running sum, which would accumulate rounding errors
+        self.values[..self.len].iter().sum::<f32>() / self.len as f32
+    }
+
+    fn reset(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+}
+
+/// Median of the last `N` values, ignoring single outliers entirely
+#[derive(Debug, Clone)]
+pub struct Median<const N: usize> {
+    values: [f32; N],
+    len: usize,
+    next: usize,
+}
+
+impl<const N: usize> Default for Median<N> {
+    fn default() -> Self {
+        Median {
+            values: [0.0; N],
+            len: 0,
+            next: 0,
+        }
+    }
+}
+
+impl<const N: usize> Smoother for Median<N> {
+    fn update(&mut self, value: f32) -> f32 {
+        self.values[self.next] = value;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+
+        let mut sorted = self.values;
+        let sorted = &mut sorted[..self.len];
+        sorted.sort_unstable_by(f32::total_cmp);
+        let middle = self.len / 2;
+        if self.len.is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / 2.0
+        } else {
+            sorted[middle]
+        }
+    }
+
+    fn reset(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+}
+
+/// Exponentially weighted moving average
+#[derive(Debug, Clone)]
+pub struct Ewma {
+    alpha: f32,
+    value: Option<f32>,
+}
+
+impl Ewma {
+    /// Average giving new values a weight of `alpha` between 0 and 1, smaller values smooth more
+    pub fn new(alpha: f32) -> Self {
+        Ewma { alpha, value: None }
+    }
+}
+
+impl Smoother for Ewma {
+    fn update(&mut self, value: f32) -> f32 {
+        let smoothed = match self.value {
+            Some(last) => last + self.alpha * (value - last),
+            None => value,
+        };
+        self.value = Some(smoothed);
+        smoothed
+    }
+
+    fn reset(&mut self) {
+        self.value = None;
+    }
+}
+
+/// [`Data`] with every field passed through a [`Smoother`]
+#[derive(Debug, Clone)]
+pub struct FilteredData<S> {
+    raw: Data,
+    filtered: Data,
+    respiratory: S,
+    distance: S,
+    heartbeat: S,
+}
+
+impl<S: Smoother + Clone> FilteredData<S> {
+    /// Smooth all fields with copies of `smoother`
+    pub fn new(smoother: S) -> Self {
+        Self::with_smoothers(smoother.clone(), smoother.clone(), smoother)
+    }
+}
+
+impl<S: Smoother> FilteredData<S> {
+    /// Smooth every field with its own smoother
+    pub fn with_smoothers(respiratory: S, distance: S, heartbeat: S) -> Self {
+        FilteredData {
+            raw: Data::default(),
+            filtered: Data::default(),
+            respiratory,
+            distance,
+            heartbeat,
+        }
+    }
+
+    /// Update the data with a message like [`Data::apply`], smoothing the updated field
+    ///
+    /// The confidence fields stay at 0, score the rates with a
+    /// [`ConfidenceEstimator`](crate::confidence::ConfidenceEstimator) instead.
+    pub fn update(&mut self, message: MessageBody) -> DataEvent {
+        let event = self.raw.apply(message);
+        if let DataEvent::Updated(field) = event {
+            let value = field.get(&self.raw);
+            match field {
+                Field::Respiratory => self.filtered.respiratory = self.respiratory.update(value),
+                Field::Distance => self.filtered.distance = self.distance.update(value),
+                Field::Heartbeat => self.filtered.heartbeat = self.heartbeat.update(value),
+            }
+        }
+        event
+    }
+
+    /// The smoothed readings
+    pub fn data(&self) -> Data {
+        self.filtered
+    }
+
+    /// The last readings as reported by the sensor
+    pub fn raw(&self) -> Data {
+        self.raw
+    }
+
+    /// Forget all readings, e.g. when the subject changed
+    pub fn reset(&mut self) {
+        self.raw = Data::default();
+        self.filtered = Data::default();
+        self.respiratory.reset();
+        self.distance.reset();
+        self.heartbeat.reset();
+    }
+}
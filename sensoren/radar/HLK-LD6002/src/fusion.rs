@@ -0,0 +1,72 @@
+//! Combining the readings of multiple sensors covering the same subject
+//!
+//! When more than one sensor watches the same bed (e.g. one at the headboard and one on the ceiling)
+//! the readings can be fused into a single set of vitals, using a quality score per sensor.
+
+use crate::Data;
+
+/// How the readings of overlapping sensors are combined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FusionPolicy {
+    /// Use the reading of the sensor with the highest quality score
+    #[default]
+    Best,
+    /// Average the readings, weighted by their quality score
+    Weighted,
+}
+
+/// The reading of a single sensor together with its quality score
+///
+/// The quality is a non-negative weight, sensors with a quality of `0` are ignored.
+#[derive(Debug, Clone, Copy)]
+pub struct Estimate {
+    pub data: Data,
+    pub quality: f32,
+}
+
+/// Fuse the readings of multiple sensors into one
+///
+/// Every field is fused separately, only sensors that currently report a value for the field are considered.
+/// Since distances are relative to each sensor's own mounting position, the distance is always taken
+/// from the best sensor, regardless of the policy.
+pub fn fuse(estimates: &[Estimate], policy: FusionPolicy) -> Data {
+    Data {
+        respiratory: fuse_field(estimates, policy, |data| data.respiratory),
+        distance: fuse_field(estimates, FusionPolicy::Best, |data| data.distance),
+        heartbeat: fuse_field(estimates, policy, |data| data.heartbeat),
+        heartbeat_confidence: fuse_field(estimates, policy, |data| data.heartbeat_confidence),
+        respiratory_confidence: fuse_field(estimates, policy, |data| data.respiratory_confidence),
+    }
+}
+
+fn fuse_field(estimates: &[Estimate], policy: FusionPolicy, field: impl Fn(&Data) -> f32) -> f32 {
+    let candidates = estimates
+        .iter()
+        .filter(|estimate| estimate.quality > 0.0)
+        .map(|estimate| (field(&estimate.data), estimate.quality))
+        .filter(|(value, _)| *value > 0.0);
+
+    match policy {
+        FusionPolicy::Best => {
+            candidates
+                .fold((0.0, 0.0), |best, candidate| {
+                    if candidate.1 > best.1 {
+                        candidate
+                    } else {
+                        best
+                    }
+                })
+                .0
+        }
+        FusionPolicy::Weighted => {
+            let (sum, weight) = candidates.fold((0.0, 0.0), |(sum, weight), (value, quality)| {
+                (sum + value * quality, weight + quality)
+            });
+            if weight > 0.0 {
+                sum / weight
+            } else {
+                0.0
+            }
+        }
+    }
+}
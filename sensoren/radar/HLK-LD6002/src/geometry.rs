@@ -0,0 +1,75 @@
+//! Converting the reported distance using the mounting position of the sensor
+//!
+//! The sensor reports the straight-line ("slant") distance to the target, for a sensor mounted
+//! above the target this differs from the horizontal distance across the room.
+
+/// Reason a distance can't be explained by the mounting geometry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Implausible {
+    /// The distance is shorter than the vertical offset between the sensor and the target
+    TooClose,
+    /// The distance is larger than the horizontal extent of the room allows
+    TooFar,
+    /// The target would have to be below the floor or above the sensor
+    OutOfRoom,
+}
+
+/// The installation position of the sensor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mounting {
+    /// Height of the sensor above the floor in meters
+    pub height: f32,
+    /// Height of the tracked point (usually the chest) above the floor in meters
+    pub target_height: f32,
+}
+
+impl Mounting {
+    /// A sensor mounted at `height` meters tracking a target at `target_height` meters
+    pub fn new(height: f32, target_height: f32) -> Self {
+        Mounting {
+            height,
+            target_height,
+        }
+    }
+
+    /// Convert a slant distance as reported by the sensor to the horizontal distance
+    ///
+    /// Returns `None` if the slant distance is shorter than the vertical offset between the sensor and the target.
+    pub fn horizontal_distance(&self, slant: f32) -> Option<f32> {
+        let vertical = self.height - self.target_height;
+        let squared = slant * slant - vertical * vertical;
+        (squared >= 0.0).then(|| libm::sqrtf(squared))
+    }
+
+    /// Estimate the horizontal offset of the target for a ceiling mounted sensor
+    ///
+    /// Unlike [`horizontal_distance`](Self::horizontal_distance) this also rejects targets further away
+    /// than `max_horizontal` meters, which usually indicates a reflection or a target in another room.
+    pub fn horizontal_offset(&self, slant: f32, max_horizontal: f32) -> Result<f32, Implausible> {
+        let horizontal = self
+            .horizontal_distance(slant)
+            .ok_or(Implausible::TooClose)?;
+        if horizontal > max_horizontal {
+            Err(Implausible::TooFar)
+        } else {
+            Ok(horizontal)
+        }
+    }
+
+    /// Estimate the height of the target above the floor, given its known horizontal offset
+    ///
+    /// Useful for ceiling installs where the position of the bed or desk is known,
+    /// to tell apart a person lying down, sitting or standing.
+    pub fn target_height(&self, slant: f32, horizontal: f32) -> Result<f32, Implausible> {
+        let squared = slant * slant - horizontal * horizontal;
+        if squared < 0.0 {
+            return Err(Implausible::TooClose);
+        }
+        let height = self.height - libm::sqrtf(squared);
+        if (0.0..=self.height).contains(&height) {
+            Ok(height)
+        } else {
+            Err(Implausible::OutOfRoom)
+        }
+    }
+}
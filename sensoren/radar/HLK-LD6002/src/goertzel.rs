@@ -0,0 +1,95 @@
+//! Estimating the breathing rate from the phase with the Goertzel algorithm
+//!
+//! The Goertzel algorithm computes the power of a signal at a single frequency. Scanning a handful
+//! of candidate frequencies in the breathing band is enough to find the breathing rate, without the
+//! code size and memory of an FFT. This gives an estimate independent of the rate reported by the sensor.
+
+use crate::MessageBody;
+use core::f32::consts::PI;
+
+/// Power of `samples` taken at `sample_rate` Hz at `frequency` Hz
+pub fn power(samples: impl Iterator<Item = f32>, frequency: f32, sample_rate: f32) -> f32 {
+    let coefficient = 2.0 * libm::cosf(2.0 * PI * frequency / sample_rate);
+    let (s1, s2) = samples.fold((0.0, 0.0), |(s1, s2), sample| {
+        (sample + coefficient * s1 - s2, s1)
+    });
+    s1 * s1 + s2 * s2 - coefficient * s1 * s2
+}
+
+/// Estimates the breathing rate from the last `N` phase samples
+///
+/// `N` needs to cover a few breaths, e.g. 512 samples are about 25 seconds of phase reports at 20 Hz.
+#[derive(Debug, Clone)]
+pub struct BreathRateEstimator<const N: usize> {
+    samples: [f32; N],
+    len: usize,
+    next: usize,
+    sample_rate: f32,
+}
+
+impl<const N: usize> BreathRateEstimator<N> {
+    /// Estimator for phase reports arriving at `sample_rate` Hz
+    pub fn new(sample_rate: f32) -> Self {
+        BreathRateEstimator {
+            samples: [0.0; N],
+            len: 0,
+            next: 0,
+            sample_rate,
+        }
+    }
+
+    /// Add a phase sample
+    pub fn push(&mut self, sample: f32) {
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// Add the first phase value of a [`MessageBody::Phase`], other messages are ignored
+    pub fn update(&mut self, message: &MessageBody) {
+        if let MessageBody::Phase([phase, ..]) = message {
+            self.push(*phase);
+        }
+    }
+
+    /// The breathing rate in breaths per minute, scanning 6 to 30 breaths per minute in steps of 1
+    ///
+    /// `None` until the window is filled.
+    pub fn estimate(&self) -> Option<f32> {
+        self.estimate_in(6.0, 30.0, 1.0)
+    }
+
+    /// The rate between `low` and `high` per minute with the most power, scanning in `step`s
+    ///
+    /// `None` until the window is filled, or if the range isn't finite, `low` is above `high`
+    /// or `step` isn't positive.
+    pub fn estimate_in(&self, low: f32, high: f32, step: f32) -> Option<f32> {
+        let valid = low.is_finite() && high.is_finite() && low <= high && step > 0.0;
+        if self.len < N || !valid {
+            return None;
+        }
+        // remove the offset of the phase, which would otherwise leak into the low candidates
+        let mean = self.samples.iter().sum::<f32>() / N as f32;
+        let (older, newer) = self.samples.split_at(self.next);
+
+        // counting the steps, adding a step too small to change the rate would never reach `high`,
+        // with some slack so rounding doesn't drop `high` itself
+        let steps = libm::floorf((high - low) / step + 1e-3) as u32;
+        let mut best = (low, f32::MIN);
+        for i in 0..=steps {
+            let rate = low + i as f32 * step;
+            let samples = newer.iter().chain(older).map(|sample| sample - mean);
+            let power = power(samples, rate / 60.0, self.sample_rate);
+            if power > best.1 {
+                best = (rate, power);
+            }
+        }
+        Some(best.0)
+    }
+
+    /// Forget all samples, e.g. after a gap in the phase reports
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+}
@@ -0,0 +1,169 @@
+//! Rough heart rate variability estimates
+//!
+//! HRV is normally computed from the exact time between heartbeats, measured with an ECG.
+//! The sensor doesn't report individual beats, so [`HrvEstimator`] offers two proxies:
+//!
+//! - from the reported heartbeat rates, converted to the average beat interval of each report
+//! - from the phase, timing the rising zero crossings of the heartbeat band as beats
+//!
+//! Both give SDNN (standard deviation of the intervals) and RMSSD (root mean square of the differences of
+//! successive intervals) style metrics, but they are not comparable to ECG based HRV:
+//!
+//! - the reported rates are averaged by the sensor over several beats, which hides most of the
+//!   beat to beat variability, so the values are much lower than the real HRV
+//! - beats in the phase are only resolved as well as the phase report rate allows (interpolated between
+//!   reports) and are easily disturbed by breathing harmonics and movement
+//!
+//! The values are best used as a trend for the same person and mounting, e.g. over a night.
+
+use crate::bandpass::{Biquad, HEART_BAND};
+use crate::MessageBody;
+
+/// Shortest beat interval accepted, in milliseconds (150 beats per minute)
+const MIN_INTERVAL: f32 = 400.0;
+/// Longest beat interval accepted, in milliseconds (40 beats per minute)
+const MAX_INTERVAL: f32 = 1500.0;
+
+/// HRV metrics over a window of beat intervals
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HrvMetrics {
+    /// Standard deviation of the intervals in milliseconds
+    pub sdnn: f32,
+    /// Root mean square of the differences between successive intervals in milliseconds
+    pub rmssd: f32,
+    /// Number of intervals the metrics are based on
+    pub count: usize,
+}
+
+/// The last `N` beat intervals in milliseconds
+#[derive(Debug, Clone)]
+pub struct Intervals<const N: usize> {
+    intervals: [f32; N],
+    len: usize,
+    next: usize,
+}
+
+impl<const N: usize> Default for Intervals<N> {
+    fn default() -> Self {
+        Intervals {
+            intervals: [0.0; N],
+            len: 0,
+            next: 0,
+        }
+    }
+}
+
+impl<const N: usize> Intervals<N> {
+    /// Add an interval, intervals outside of 40 to 150 beats per minute are ignored
+    pub fn push(&mut self, interval: f32) {
+        if !(MIN_INTERVAL..=MAX_INTERVAL).contains(&interval) {
+            return;
+        }
+        self.intervals[self.next] = interval;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// The intervals from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = f32> + '_ {
+        let start = (self.next + N - self.len) % N;
+        (0..self.len).map(move |i| self.intervals[(start + i) % N])
+    }
+
+    /// The metrics of the intervals, `None` with less than 3 intervals
+    pub fn metrics(&self) -> Option<HrvMetrics> {
+        if self.len < 3 {
+            return None;
+        }
+        let mean = self.iter().sum::<f32>() / self.len as f32;
+        let variance = self.iter().map(|i| (i - mean) * (i - mean)).sum::<f32>() / self.len as f32;
+        let successive = self
+            .iter()
+            .zip(self.iter().skip(1))
+            .map(|(a, b)| (b - a) * (b - a))
+            .sum::<f32>()
+            / (self.len - 1) as f32;
+        Some(HrvMetrics {
+            sdnn: libm::sqrtf(variance),
+            rmssd: libm::sqrtf(successive),
+            count: self.len,
+        })
+    }
+
+    /// Forget all intervals
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+}
+
+/// Collects beat intervals from the heartbeat rates and the phase, keeping the last `N` of each
+#[derive(Debug, Clone)]
+pub struct HrvEstimator<const N: usize> {
+    rates: Intervals<N>,
+    beats: Intervals<N>,
+    heart: Biquad,
+    /// The last filtered phase sample and when it was received
+    last: Option<(u64, f32)>,
+    /// The time of the last beat, as the time of the report before it and milliseconds after that report
+    ///
+    /// Keeping the fraction separate preserves the precision of large timestamps, which an `f32` can't hold.
+    last_beat: Option<(u64, f32)>,
+}
+
+impl<const N: usize> HrvEstimator<N> {
+    /// Estimator for phase reports arriving at `sample_rate` Hz
+    pub fn new(sample_rate: f32) -> Self {
+        HrvEstimator {
+            rates: Intervals::default(),
+            beats: Intervals::default(),
+            heart: Biquad::band_pass(sample_rate, HEART_BAND.0, HEART_BAND.1),
+            last: None,
+            last_beat: None,
+        }
+    }
+
+    /// Add a message received at `now` milliseconds
+    pub fn update(&mut self, now: u64, message: &MessageBody) {
+        match *message {
+            MessageBody::Heartbeat(rate) if rate > 0.0 => self.rates.push(60_000.0 / rate),
+            MessageBody::Phase([phase, ..]) => {
+                let sample = self.heart.update(phase);
+                if let Some((last_time, last)) = self.last {
+                    if last < 0.0 && sample >= 0.0 {
+                        // interpolate the time of the zero crossing between the two reports
+                        let fraction = -last / (sample - last);
+                        let offset = fraction * now.saturating_sub(last_time) as f32;
+                        if let Some((beat_time, beat_offset)) = self.last_beat {
+                            let interval =
+                                last_time.saturating_sub(beat_time) as f32 + offset - beat_offset;
+                            self.beats.push(interval);
+                        }
+                        self.last_beat = Some((last_time, offset));
+                    }
+                }
+                self.last = Some((now, sample));
+            }
+            _ => {}
+        }
+    }
+
+    /// Metrics from the reported heartbeat rates
+    pub fn from_rates(&self) -> Option<HrvMetrics> {
+        self.rates.metrics()
+    }
+
+    /// Metrics from the beats detected in the phase
+    pub fn from_phase(&self) -> Option<HrvMetrics> {
+        self.beats.metrics()
+    }
+
+    /// Forget all intervals, e.g. after a gap in the reports
+    pub fn reset(&mut self) {
+        self.rates.reset();
+        self.beats.reset();
+        self.heart.reset();
+        self.last = None;
+        self.last_beat = None;
+    }
+}
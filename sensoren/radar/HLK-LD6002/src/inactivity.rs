@@ -0,0 +1,259 @@
+//! Alerting when no one has been seen for too long
+//!
+//! In elderly care, the absence of any sign of life during the day is a reason to check on someone.
+//! [`InactivityWatchdog`] remembers when vital signs or a target were last reported, and raises an alert
+//! when that's longer ago than a limit while inside one of the daily periods someone is expected to be active.
+//! Outside of these periods, e.g. at night or while usually out, no alerts are raised.
+//!
+//! The crate has no notion of the time of day, so it's passed in by the caller as the minute of the day
+//! in local time, e.g. from an RTC or the host clock.
+//!
+//! Vital signs keep being reported for someone lying motionless on the floor, so [`WelfareMonitor`]
+//! instead watches for movement: it raises a welfare check when the home is occupied, according to
+//! the [`HouseMode`] or the recent presence, but the target hasn't moved for a configurable period.
+
+use crate::MessageBody;
+
+/// Minutes in a day
+const DAY: u16 = 24 * 60;
+
+/// A daily period in which someone is expected to be active
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActivePeriod {
+    /// Start as minute of the day
+    pub start: u16,
+    /// End as minute of the day, an end before the start wraps around midnight
+    pub end: u16,
+}
+
+impl ActivePeriod {
+    /// A period from `start` to `end`, both as `(hour, minute)`
+    pub const fn new(start: (u16, u16), end: (u16, u16)) -> Self {
+        ActivePeriod {
+            start: start.0 * 60 + start.1,
+            end: end.0 * 60 + end.1,
+        }
+    }
+
+    /// Whether `minute` of the day is inside of the period
+    pub fn contains(&self, minute: u16) -> bool {
+        let minute = minute % DAY;
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+/// A change reported by an [`InactivityWatchdog`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InactivityEvent {
+    /// No sign of life since `since` milliseconds, during an active period
+    Inactive { since: u64 },
+    /// Signs of life were reported again after an alert
+    Active,
+}
+
+/// Raises an alert when no vital signs were reported for too long during the active periods
+#[derive(Debug, Clone)]
+pub struct InactivityWatchdog<const N: usize> {
+    periods: [ActivePeriod; N],
+    max_inactive: u64,
+    last_seen: Option<u64>,
+    alerted: bool,
+}
+
+impl<const N: usize> InactivityWatchdog<N> {
+    /// Alert after `max_inactive` milliseconds without signs of life during the `periods`
+    pub fn new(periods: [ActivePeriod; N], max_inactive: u64) -> Self {
+        InactivityWatchdog {
+            periods,
+            max_inactive,
+            last_seen: None,
+            alerted: false,
+        }
+    }
+
+    /// Feed a message received at `now` milliseconds
+    ///
+    /// Nonzero rates and distances count as signs of life. Returns [`InactivityEvent::Active`]
+    /// for the first sign of life after an alert.
+    pub fn update(&mut self, now: u64, message: &MessageBody) -> Option<InactivityEvent> {
+        let alive = match *message {
+            MessageBody::Respiratory(rate) | MessageBody::Heartbeat(rate) => rate > 0.0,
+            MessageBody::Distance(distance) => distance.is_some_and(|distance| distance > 0.0),
+            _ => false,
+        };
+        if !alive {
+            return None;
+        }
+        self.last_seen = Some(now);
+        core::mem::take(&mut self.alerted).then_some(InactivityEvent::Active)
+    }
+
+    /// Check for inactivity at `now` milliseconds, which is `minute` of the day in local time
+    ///
+    /// Needs to be called regularly, also while no messages are received. Before the first sign of life,
+    /// the inactivity is counted from the first check. Returns [`InactivityEvent::Inactive`] once per
+    /// period of inactivity.
+    pub fn check(&mut self, now: u64, minute: u16) -> Option<InactivityEvent> {
+        let since = *self.last_seen.get_or_insert(now);
+        let active = self.periods.iter().any(|period| period.contains(minute));
+        if self.alerted || !active || now.saturating_sub(since) < self.max_inactive {
+            return None;
+        }
+        self.alerted = true;
+        Some(InactivityEvent::Inactive { since })
+    }
+
+    /// When signs of life were last reported, in milliseconds
+    pub fn last_seen(&self) -> Option<u64> {
+        self.last_seen
+    }
+
+    /// Whether an alert was raised and no sign of life was reported since
+    pub fn is_alerted(&self) -> bool {
+        self.alerted
+    }
+}
+
+/// Whether the home is occupied, e.g. from an alarm system or home automation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HouseMode {
+    /// Someone is home
+    Home,
+    /// Everyone is out, no welfare checks are raised
+    Away,
+    /// Everyone is out for a longer time, handled like [`Away`](HouseMode::Away) by the welfare checks
+    Vacation,
+    /// Not known, the home counts as occupied while a target was reported recently
+    Unknown,
+}
+
+/// Thresholds used by a [`WelfareMonitor`]
+#[derive(Debug, Clone, Copy)]
+pub struct WelfareConfig {
+    /// Milliseconds without movement in an occupied home before a welfare check is raised
+    pub max_still: u64,
+    /// Change of the distance in meters that counts as movement
+    pub movement: f32,
+    /// With an [`Unknown`](HouseMode::Unknown) mode, milliseconds after the last reported target
+    /// during which the home still counts as occupied
+    pub occupied_for: u64,
+}
+
+impl Default for WelfareConfig {
+    /// A welfare check after 4 hours without movement
+    fn default() -> Self {
+        WelfareConfig {
+            max_still: 4 * 60 * 60 * 1000,
+            movement: 0.15,
+            occupied_for: 12 * 60 * 60 * 1000,
+        }
+    }
+}
+
+/// Raises a welfare check when an occupied home shows no movement for too long
+#[derive(Debug, Clone)]
+pub struct WelfareMonitor {
+    config: WelfareConfig,
+    mode: HouseMode,
+    /// Distance the movement is measured from, updated on every movement
+    reference: Option<f32>,
+    last_movement: Option<u64>,
+    last_present: Option<u64>,
+    alerted: bool,
+}
+
+impl WelfareMonitor {
+    pub fn new(config: WelfareConfig) -> Self {
+        WelfareMonitor {
+            config,
+            mode: HouseMode::Unknown,
+            reference: None,
+            last_movement: None,
+            last_present: None,
+            alerted: false,
+        }
+    }
+
+    /// Switch the house mode at `now` milliseconds
+    ///
+    /// Coming home counts as movement, so the period without movement starts over.
+    pub fn set_mode(&mut self, now: u64, mode: HouseMode) {
+        if mode == HouseMode::Home && self.mode != HouseMode::Home {
+            self.last_movement = Some(now);
+        }
+        self.mode = mode;
+    }
+
+    /// Feed a message received at `now` milliseconds
+    ///
+    /// A change of the distance by more than the configured movement counts as movement. Returns
+    /// [`InactivityEvent::Active`] for the first movement after a welfare check.
+    pub fn update(&mut self, now: u64, message: &MessageBody) -> Option<InactivityEvent> {
+        let MessageBody::Distance(Some(distance)) = *message else {
+            return None;
+        };
+        if distance <= 0.0 {
+            return None;
+        }
+        self.last_present = Some(now);
+        let moved = self
+            .reference
+            .is_none_or(|reference| (distance - reference).abs() > self.config.movement);
+        if !moved {
+            return None;
+        }
+        self.reference = Some(distance);
+        self.last_movement = Some(now);
+        core::mem::take(&mut self.alerted).then_some(InactivityEvent::Active)
+    }
+
+    /// Whether the home counts as occupied at `now` milliseconds
+    pub fn is_occupied(&self, now: u64) -> bool {
+        match self.mode {
+            HouseMode::Home => true,
+            HouseMode::Away | HouseMode::Vacation => false,
+            HouseMode::Unknown => self
+                .last_present
+                .is_some_and(|last| now.saturating_sub(last) < self.config.occupied_for),
+        }
+    }
+
+    /// Check for a lack of movement at `now` milliseconds
+    ///
+    /// Needs to be called regularly, also while no messages are received. Before the first movement,
+    /// the time without movement is counted from the first check. Returns [`InactivityEvent::Inactive`]
+    /// with the time of the last movement once per period without movement.
+    pub fn check(&mut self, now: u64) -> Option<InactivityEvent> {
+        let since = *self.last_movement.get_or_insert(now);
+        if self.alerted
+            || !self.is_occupied(now)
+            || now.saturating_sub(since) < self.config.max_still
+        {
+            return None;
+        }
+        self.alerted = true;
+        Some(InactivityEvent::Inactive { since })
+    }
+
+    /// When the target last moved, in milliseconds
+    pub fn last_movement(&self) -> Option<u64> {
+        self.last_movement
+    }
+
+    /// Whether a welfare check was raised and the target didn't move since
+    pub fn is_alerted(&self) -> bool {
+        self.alerted
+    }
+}
+
+impl Default for WelfareMonitor {
+    fn default() -> Self {
+        Self::new(WelfareConfig::default())
+    }
+}
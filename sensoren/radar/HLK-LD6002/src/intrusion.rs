@@ -0,0 +1,97 @@
+//! Alerting on presence while no one should be home
+//!
+//! While the home is [`Away`](HouseMode::Away) or on [`Vacation`](HouseMode::Vacation), anyone seen by
+//! a sensor is unexpected, which turns the sensors into an additional layer of a burglar alarm.
+//! [`IntrusionDetector`] raises an alert once a target is reported for longer than a confirmation time,
+//! so a single spurious frame (e.g. from a curtain moving) doesn't set it off. The house mode comes from
+//! the application, e.g. from an alarm system or home automation, like for the
+//! [`WelfareMonitor`](crate::inactivity::WelfareMonitor).
+
+use crate::inactivity::HouseMode;
+use crate::MessageBody;
+
+/// Output of the [`IntrusionDetector`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Intrusion {
+    /// A target has been reported since `since` milliseconds while no one should be home
+    Detected { since: u64 },
+    /// The target of an alert left, or someone came home
+    Cleared,
+}
+
+/// Raises an alert when a target is reported while the home is away or on vacation
+#[derive(Debug, Clone)]
+pub struct IntrusionDetector {
+    confirm: u64,
+    mode: HouseMode,
+    present_since: Option<u64>,
+    alerted: bool,
+}
+
+impl IntrusionDetector {
+    /// Raise an alert once a target is reported for `confirm` milliseconds without interruption
+    pub fn new(confirm: u64) -> Self {
+        IntrusionDetector {
+            confirm,
+            mode: HouseMode::Unknown,
+            present_since: None,
+            alerted: false,
+        }
+    }
+
+    /// Switch the house mode
+    ///
+    /// Alerts are only raised while [`Away`](HouseMode::Away) or on [`Vacation`](HouseMode::Vacation),
+    /// switching to any other mode clears an alert, e.g. when the alarm system is disarmed.
+    pub fn set_mode(&mut self, mode: HouseMode) -> Option<Intrusion> {
+        self.mode = mode;
+        if self.is_armed() {
+            return None;
+        }
+        self.present_since = None;
+        core::mem::take(&mut self.alerted).then_some(Intrusion::Cleared)
+    }
+
+    /// Feed a message received at `now` milliseconds
+    ///
+    /// Nonzero distances count as a target, a report without one ends the presence.
+    /// Returns [`Intrusion::Detected`] once per presence, and [`Intrusion::Cleared`] when the target
+    /// of an alert is no longer reported.
+    pub fn update(&mut self, now: u64, message: &MessageBody) -> Option<Intrusion> {
+        let MessageBody::Distance(distance) = *message else {
+            return None;
+        };
+        if !distance.is_some_and(|distance| distance > 0.0) {
+            self.present_since = None;
+            return core::mem::take(&mut self.alerted).then_some(Intrusion::Cleared);
+        }
+        if !self.is_armed() {
+            return None;
+        }
+        let since = *self.present_since.get_or_insert(now);
+        if self.alerted || now.saturating_sub(since) < self.confirm {
+            return None;
+        }
+        self.alerted = true;
+        Some(Intrusion::Detected { since })
+    }
+
+    /// Whether presence raises an alert in the current house mode
+    pub fn is_armed(&self) -> bool {
+        matches!(self.mode, HouseMode::Away | HouseMode::Vacation)
+    }
+
+    /// Whether an alert was raised and not cleared since
+    pub fn is_alerted(&self) -> bool {
+        self.alerted
+    }
+}
+
+impl Default for IntrusionDetector {
+    /// Confirm a target for 2 seconds
+    fn default() -> Self {
+        Self::new(2_000)
+    }
+}
@@ -0,0 +1,41 @@
+use crate::LdError;
+use embedded_io::{Error, ErrorKind, Read};
+
+/// Number of consecutive retryable errors tolerated before giving up
+pub(crate) const MAX_RETRIES: u8 = 8;
+
+/// Fill `buf` completely, accumulating as many short reads as needed
+///
+/// Unlike `read_exact` this retries interrupted reads, and once part of a frame has been read (`mid_frame`)
+/// also reads that timed out. Some serial adapters hand out a frame in small chunks with timeouts in between,
+/// giving up on the first timeout would drop the rest of the frame and desync the stream.
+pub(crate) fn read_full<R: Read>(
+    mut reader: R,
+    buf: &mut [u8],
+    mid_frame: bool,
+) -> Result<(), LdError<R::Error>> {
+    let mut filled = 0;
+    let mut retries = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => return Err(LdError::Eof),
+            Ok(read) => {
+                filled += read;
+                retries = 0;
+            }
+            Err(e) if retries < MAX_RETRIES && retryable(&e, mid_frame || filled > 0) => {
+                retries += 1;
+            }
+            Err(e) => return Err(LdError::Read(e)),
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn retryable<E: Error>(error: &E, partial: bool) -> bool {
+    match error.kind() {
+        ErrorKind::Interrupted => true,
+        ErrorKind::TimedOut => partial,
+        _ => false,
+    }
+}
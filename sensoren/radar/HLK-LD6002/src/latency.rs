@@ -0,0 +1,158 @@
+//! Measuring the latency from receiving a frame to acting on it
+//!
+//! With the `latency` feature, streams with a [`Clock`](crate::Clock) measure how long it takes from
+//! the first byte of a frame arriving until the message is decoded, and collect the measurements in
+//! a [`Latency`] histogram per [`Stage`] that's part of the [`Stats`](crate::Stats). The time spent
+//! after decoding, in filters and when publishing, can be recorded with `record_latency` on the stream.
+//!
+//! The histograms use power of two buckets in milliseconds, so percentiles are upper bounds
+//! that are exact to within a factor of two.
+
+/// Number of buckets, the last one counts everything from 1024 ms
+const BUCKETS: usize = 12;
+
+/// A step between receiving a frame and acting on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// From the first byte of a frame arriving to the message being decoded, measured by the stream
+    ///
+    /// Includes the time the frame takes on the wire.
+    Decode,
+    /// Processing the message, e.g. in a pipeline of filters and detectors
+    Filter,
+    /// Handing the result on, e.g. sending it over the network
+    Publish,
+}
+
+/// Histogram of the latencies of one stage
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: [u32; BUCKETS],
+    max: u32,
+}
+
+impl Histogram {
+    const EMPTY: Histogram = Histogram {
+        buckets: [0; BUCKETS],
+        max: 0,
+    };
+
+    /// Add a latency in milliseconds
+    pub fn record(&mut self, ms: u32) {
+        let bucket = (u32::BITS - ms.leading_zeros()) as usize;
+        let bucket = &mut self.buckets[bucket.min(BUCKETS - 1)];
+        *bucket = bucket.wrapping_add(1);
+        self.max = self.max.max(ms);
+    }
+
+    /// Number of recorded latencies
+    pub fn count(&self) -> u32 {
+        self.buckets
+            .iter()
+            .fold(0, |sum, count| sum.wrapping_add(*count))
+    }
+
+    /// The largest recorded latency in milliseconds
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+
+    /// Upper bound of the latency that `percentile` percent of the measurements are below
+    ///
+    /// `None` if nothing was recorded.
+    pub fn percentile(&self, percentile: f32) -> Option<u32> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = libm::ceilf(count as f32 * percentile / 100.0).max(1.0) as u32;
+        let mut seen = 0;
+        for (bucket, bucket_count) in self.buckets.iter().enumerate() {
+            seen += bucket_count;
+            if seen >= rank {
+                // bucket `i` holds latencies below 2^i ms
+                return Some((1u32 << bucket).min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+
+    /// The median, 90th and 99th percentile and maximum
+    pub fn summary(&self) -> Option<Summary> {
+        Some(Summary {
+            count: self.count(),
+            p50: self.percentile(50.0)?,
+            p90: self.percentile(90.0)?,
+            p99: self.percentile(99.0)?,
+            max: self.max,
+        })
+    }
+}
+
+/// Percentiles of the latencies of one stage in milliseconds, see [`Histogram::percentile`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub count: u32,
+    pub p50: u32,
+    pub p90: u32,
+    pub p99: u32,
+    pub max: u32,
+}
+
+/// Latency histograms for all stages
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Latency {
+    pub decode: Histogram,
+    pub filter: Histogram,
+    pub publish: Histogram,
+}
+
+impl Latency {
+    pub(crate) const EMPTY: Latency = Latency {
+        decode: Histogram::EMPTY,
+        filter: Histogram::EMPTY,
+        publish: Histogram::EMPTY,
+    };
+
+    pub fn stage(&self, stage: Stage) -> &Histogram {
+        match stage {
+            Stage::Decode => &self.decode,
+            Stage::Filter => &self.filter,
+            Stage::Publish => &self.publish,
+        }
+    }
+
+    pub fn record(&mut self, stage: Stage, ms: u32) {
+        match stage {
+            Stage::Decode => self.decode.record(ms),
+            Stage::Filter => self.filter.record(ms),
+            Stage::Publish => self.publish.record(ms),
+        }
+    }
+}
+
+/// Measures the decode latency inside the streams
+#[derive(Default, Debug, Clone)]
+pub(crate) struct FrameTimer {
+    started: Option<u64>,
+}
+
+impl FrameTimer {
+    /// The first bytes of a frame arrived at `now`
+    pub(crate) fn started(&mut self, now: u64) {
+        self.started = Some(now);
+    }
+
+    /// The frame in progress was decoded at `now`
+    pub(crate) fn decoded(&mut self, now: u64, latency: &mut Latency) {
+        if let Some(started) = self.started.take() {
+            let ms = now.saturating_sub(started).min(u32::MAX as u64) as u32;
+            latency.decode.record(ms);
+        }
+    }
+
+    /// The frame in progress was dropped
+    pub(crate) fn dropped(&mut self) {
+        self.started = None;
+    }
+}
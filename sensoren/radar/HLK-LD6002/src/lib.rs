@@ -24,14 +24,141 @@
 //!     println!("{data:?}");
 //! }
 //! ```
+//!
+//! ## Features
+//!
+//! The crate is split in tiers, so the parser alone can be used on small targets.
+//!
+//! - without any features: frame parsing (also from pushed bytes using the [`parser`]), [`encode`]-ing, [`command`]s,
+//!   [`Data`], frame rates, firmware [`quirks`] and recording and [`replay`] of captures
+//! - `filters` (default): [`window`]ed aggregates, smoothing [`filter`]s, [`bandpass`] filters,
+//!   [`detrend`]ing, breathing rate estimation using [`goertzel`] and [`waveform`]s for the phase,
+//!   rough heart rate variability ([`hrv`]), [`confidence`] of the readings, [`downsample`]-ing,
+//!   [`fusion`] and [`dedup`]lication for multiple sensors, mounting [`geometry`] and heap-free [`pipeline`]s
+//! - `detectors` (default, implies `filters`): detectors for the readings, like debounced [`presence`]
+//!   and [`desk`] occupancy, breathing pauses in [`apnea`], experimental [`fall`] detection, [`inactivity`]
+//!   during the day or in an occupied home, [`intrusion`] alerts while away, frozen values in [`stuck`]
+//!   or a blocked sensor in [`tamper`],
+//!   and a common [`event`] type for their output with severities and correlation ids
+//! - `tracking` (implies `filters`): Kalman filter [`tracking`] of the rates with their uncertainty
+//! - `latency`: [`latency`] histograms from a frame arriving to the message being decoded and acted upon
+//! - `alloc`: together with `filters`, [`sleep`] session summaries over a night of readings
+//! - `futures`: [`AsyncMessageStream::into_stream`] and [`AsyncMessageStream::stream`] returning a
+//!   `futures_core::Stream`, without needing alloc
+//! - `std`: opening [`serial`] ports with [`MessageStream::open`], or finding the port the sensor
+//!   is connected to with [`MessageStream::open_auto`], and a [`logger`] writing the readings to rotating
+//!   CSV or JSON lines files
+//! - `tokio` (implies `std` and `futures`): [`serial::TokioMessageStream`] opening a serial port with
+//!   `tokio-serial` and reopening it after persistent errors
+//! - `serde`: `Serialize` and `Deserialize` for [`Data`], [`MessageBody`] and [`MessageType`]
+//! - `mqtt`: publishing the readings from microcontrollers with [`mqtt`], using `minimq`
+//! - `defmt`: `defmt::Format` for [`LdError`] and the readings, for logging over RTT
+//! - `testing`: a simulated sensor and a transport injecting faults in the [`testing`] module,
+//!   for testing code that reads from the sensor
+//!
+//! Code that isn't used doesn't end up in the binary either way, the tiers mostly keep the
+//! API (and compile times) small for targets that only need the parser.
+//! See the readme for measured flash and RAM costs.
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 use bytemuck::{cast, cast_slice};
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::Poll;
 use embedded_io::{Error, Read, ReadExactError};
 use embedded_io_async::Read as AsyncRead;
 use num_enum::TryFromPrimitive;
 
+#[cfg(feature = "detectors")]
+pub mod apnea;
+#[cfg(feature = "filters")]
+pub mod bandpass;
+mod buffer;
+mod clock;
+pub mod command;
+mod compact;
+#[cfg(feature = "filters")]
+pub mod confidence;
+#[cfg(feature = "filters")]
+pub mod dedup;
+#[cfg(feature = "detectors")]
+pub mod desk;
+#[cfg(feature = "filters")]
+pub mod detrend;
+#[cfg(feature = "filters")]
+pub mod downsample;
+pub mod encode;
+#[cfg(feature = "detectors")]
+pub mod event;
+#[cfg(feature = "detectors")]
+pub mod fall;
+#[cfg(feature = "filters")]
+pub mod filter;
+#[cfg(feature = "filters")]
+pub mod fusion;
+#[cfg(feature = "filters")]
+pub mod geometry;
+#[cfg(feature = "filters")]
+pub mod goertzel;
+#[cfg(feature = "filters")]
+pub mod hrv;
+#[cfg(feature = "detectors")]
+pub mod inactivity;
+#[cfg(feature = "detectors")]
+pub mod intrusion;
+mod io;
+#[cfg(feature = "latency")]
+pub mod latency;
+#[cfg(feature = "std")]
+pub mod logger;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod parser;
+#[cfg(feature = "filters")]
+pub mod pipeline;
+#[cfg(feature = "detectors")]
+pub mod presence;
+pub mod quirks;
+mod rate;
+pub mod replay;
+#[cfg(feature = "std")]
+pub mod serial;
+#[cfg(all(feature = "alloc", feature = "filters"))]
+pub mod sleep;
+mod stats;
+#[cfg(feature = "futures")]
+mod stream;
+#[cfg(feature = "detectors")]
+pub mod stuck;
+#[cfg(feature = "detectors")]
+pub mod tamper;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod timed;
+#[cfg(feature = "tracking")]
+pub mod tracking;
+#[cfg(feature = "filters")]
+pub mod waveform;
+#[cfg(feature = "filters")]
+pub mod window;
+
+use buffer::FrameBuffer;
+pub use clock::Clock;
+pub use compact::CompactData;
+use io::{read_full, retryable, MAX_RETRIES};
+pub use parser::{FrameParser, ParserEvent};
+pub use quirks::Quirks;
+pub use rate::{RateMeter, Rates};
+pub use stats::Stats;
+pub use timed::TimedData;
+
 /// Error type for reading data from the sensor
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum LdError<E> {
     /// The message received from the sensor had an unknown message type
     InvalidMessageType(u16),
@@ -49,6 +176,8 @@ pub enum LdError<E> {
     },
     /// The data read from the sensor didn't start as expected
     InvalidFrameStart(u8),
+    /// The frame received from the sensor was too long to be buffered
+    FrameTooLong { ty: u16, length: u16 },
     /// Unexpected end of data
     Eof,
     /// Error while reading from the serial device
@@ -66,6 +195,8 @@ impl<E> From<ReadExactError<E>> for LdError<E> {
 
 /// Message type sent by the sensor
 #[derive(Debug, Clone, Copy, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u16)]
 pub enum MessageType {
     Phase = 0x0a13,
@@ -77,7 +208,7 @@ pub enum MessageType {
 impl MessageType {
     pub fn read<R: Read>(mut reader: R) -> Result<Self, LdError<R::Error>> {
         let mut bytes = [0u8; 2];
-        reader.read_exact(&mut bytes)?;
+        read_full(&mut reader, &mut bytes, false)?;
         let ty = u16::from_be_bytes(bytes);
         MessageType::try_from(ty).map_err(|e| LdError::InvalidMessageType(e.number))
     }
@@ -97,45 +228,36 @@ impl MessageType {
 /// based on TinyFrame
 #[derive(Clone, Debug)]
 struct FrameHeader {
-    _id: u16,
+    id: u16,
     length: u16,
-    ty: MessageType,
+    /// The message type, `None` for types unknown to this crate
+    ty: Option<MessageType>,
+    raw_ty: u16,
 }
 
 impl FrameHeader {
-    pub fn parse<E>(data: [u8; 7]) -> Result<Self, LdError<E>> {
-        let ty = u16::from_be_bytes([data[4], data[5]]);
-        let ty = MessageType::try_from(ty).map_err(|e| LdError::InvalidMessageType(e.number))?;
-
-        // let checksum = checksum(&header_bytes);
-        // if header_bytes[6] != checksum {
-        //     return Err(LdError::InvalidChecksum {
-        //         ty: "header",
-        //         got: checksum,
-        //         expected: header_bytes[6],
-        //     });
-        // }
+    pub fn parse<E>(data: [u8; 7], quirks: &Quirks) -> Result<Self, LdError<E>> {
+        let raw_ty = u16::from_be_bytes([data[4], data[5]]);
+
+        if !quirks.ignore_header_checksum {
+            // the header checksum also covers the start of frame byte
+            let checksum = !(1 ^ !checksum(&data[0..6]));
+            if data[6] != checksum {
+                return Err(LdError::InvalidChecksum {
+                    ty: "header",
+                    got: checksum,
+                    expected: data[6],
+                });
+            }
+        }
 
         Ok(FrameHeader {
-            _id: u16::from_be_bytes([data[0], data[1]]),
+            id: u16::from_be_bytes([data[0], data[1]]),
             length: u16::from_be_bytes([data[2], data[3]]),
-            ty,
+            ty: MessageType::try_from(raw_ty).ok(),
+            raw_ty,
         })
     }
-
-    pub fn read<R: Read>(mut reader: R) -> Result<Self, LdError<R::Error>> {
-        let mut header_bytes = [0; 7];
-        reader.read_exact(&mut header_bytes)?;
-
-        Self::parse(header_bytes)
-    }
-
-    pub async fn read_async<R: AsyncRead>(mut reader: R) -> Result<Self, LdError<R::Error>> {
-        let mut header_bytes = [0; 7];
-        reader.read_exact(&mut header_bytes).await?;
-
-        Self::parse(header_bytes)
-    }
 }
 
 /// A frame of data received from the sensor
@@ -144,58 +266,7 @@ impl FrameHeader {
 struct Frame {
     header: FrameHeader,
     data: FrameData<16>,
-}
-
-impl Frame {
-    pub fn read<R: Read>(mut reader: R) -> Result<Self, LdError<R::Error>> {
-        let mut magic = [0];
-        reader.read_exact(&mut magic)?;
-        if magic[0] != 1 {
-            return Err(LdError::InvalidFrameStart(magic[0]));
-        }
-
-        let header = FrameHeader::read(&mut reader)?;
-        let data = FrameData::read(&mut reader, &header)?;
-        let mut data_checksum = [0];
-        reader.read_exact(&mut data_checksum)?;
-        let data_checksum = data_checksum[0];
-
-        let calculated_checksum = checksum(data.as_ref());
-        if data_checksum != calculated_checksum {
-            return Err(LdError::InvalidChecksum {
-                ty: "body",
-                got: calculated_checksum,
-                expected: data_checksum,
-            });
-        };
-
-        Ok(Frame { header, data })
-    }
-
-    pub async fn read_async<R: AsyncRead>(mut reader: R) -> Result<Self, LdError<R::Error>> {
-        let mut magic = [0];
-        reader.read_exact(&mut magic).await?;
-        if magic[0] != 1 {
-            return Err(LdError::InvalidFrameStart(magic[0]));
-        }
-
-        let header = FrameHeader::read_async(&mut reader).await?;
-        let data = FrameData::read_async(&mut reader, &header).await?;
-        let mut data_checksum = [0];
-        reader.read_exact(&mut data_checksum).await?;
-        let data_checksum = data_checksum[0];
-
-        let calculated_checksum = checksum(data.as_ref());
-        if data_checksum != calculated_checksum {
-            return Err(LdError::InvalidChecksum {
-                ty: "body",
-                got: calculated_checksum,
-                expected: data_checksum,
-            });
-        };
-
-        Ok(Frame { header, data })
-    }
+    raw: RawFrame,
 }
 
 #[derive(Debug, Clone)]
@@ -210,47 +281,41 @@ impl<const N: usize> FrameData<N> {
         self.len
     }
 
-    fn validate<E>(header: &FrameHeader) -> Result<(), LdError<E>> {
-        if header.length as usize > N || header.length != header.ty.expected_length() {
+    fn validate<E>(header: &FrameHeader, quirks: &Quirks) -> Result<(), LdError<E>> {
+        let Some(ty) = header.ty else {
+            return if header.length as usize > N {
+                Err(LdError::FrameTooLong {
+                    ty: header.raw_ty,
+                    length: header.length,
+                })
+            } else {
+                Ok(())
+            };
+        };
+
+        let short_distance =
+            quirks.short_distance && matches!(ty, MessageType::Distance) && header.length == 4;
+        if header.length as usize > N || (header.length != ty.expected_length() && !short_distance)
+        {
             Err(LdError::InvalidDataLength {
                 got: header.length,
-                expected: header.ty.expected_length(),
-                ty: header.ty,
+                expected: ty.expected_length(),
+                ty,
             })
         } else {
             Ok(())
         }
     }
 
-    pub fn read<R: Read>(mut reader: R, header: &FrameHeader) -> Result<Self, LdError<R::Error>> {
-        Self::validate(header)?;
-
-        let mut data = [0u8; N];
-        reader.read_exact(&mut data[0..header.length as usize])?;
-
-        Ok(FrameData {
-            _align: 0,
-            data,
-            len: header.length,
-        })
-    }
-
-    pub async fn read_async<R: AsyncRead>(
-        mut reader: R,
-        header: &FrameHeader,
-    ) -> Result<Self, LdError<R::Error>> {
-        Self::validate(header)?;
-
+    pub fn from_slice(bytes: &[u8]) -> Self {
         let mut data = [0u8; N];
-        reader
-            .read_exact(&mut data[0..header.length as usize])
-            .await?;
+        data[0..bytes.len()].copy_from_slice(bytes);
 
-        Ok(FrameData {
+        FrameData {
             _align: 0,
             data,
-            len: header.length,
-        })
+            len: bytes.len() as u16,
+        }
     }
 }
 
@@ -262,10 +327,16 @@ impl<const N: usize> AsRef<[u8]> for FrameData<N> {
 
 impl Frame {
     /// Decode the body of the message according to the message type
-    fn body<E: Error>(&self) -> Result<MessageBody, LdError<E>> {
+    fn body<E: Error>(&self, quirks: &Quirks) -> Result<MessageBody, LdError<E>> {
+        let Some(ty) = self.header.ty else {
+            return Ok(MessageBody::Unknown {
+                ty: self.header.raw_ty,
+                data: Payload::from_slice(self.data.as_ref()),
+            });
+        };
         let numbers = cast_slice::<_, u32>(self.data.as_ref());
 
-        match (self.header.ty, self.data.len()) {
+        match (ty, self.data.len()) {
             (MessageType::Phase, 12) => {
                 let numbers: [u32; 3] = numbers.try_into().unwrap();
                 Ok(MessageBody::Phase(cast(numbers)))
@@ -282,41 +353,308 @@ impl Frame {
                 };
                 Ok(MessageBody::Distance(Some(distance)))
             }
-            (MessageType::Distance, 4) => Ok(MessageBody::Distance(None)),
+            (MessageType::Distance, 4) if quirks.short_distance => Ok(MessageBody::Distance(None)),
             _ => Err(LdError::InvalidDataLength {
                 got: self.data.len(),
-                expected: self.header.ty.expected_length(),
-                ty: self.header.ty,
+                expected: ty.expected_length(),
+                ty,
             }),
         }
     }
 }
 
 /// The decoded message from the sensor
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum MessageBody {
     Phase([f32; 3]),
     Respiratory(f32),
     Heartbeat(f32),
     Distance(Option<f32>),
+    /// A frame of a type this crate doesn't decode, with its raw payload
+    ///
+    /// Unknown frames with more than 16 bytes of payload can't be buffered
+    /// and are reported as [`LdError::FrameTooLong`] instead.
+    Unknown {
+        ty: u16,
+        data: Payload,
+    },
+}
+
+/// The raw payload of a frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Payload {
+    bytes: [u8; 16],
+    len: u8,
+}
+
+impl Payload {
+    fn from_slice(data: &[u8]) -> Self {
+        let mut bytes = [0; 16];
+        bytes[..data.len()].copy_from_slice(data);
+        Payload {
+            bytes,
+            len: data.len() as u8,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+impl AsRef<[u8]> for Payload {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Payload {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=[u8]:02x}", self.as_slice())
+    }
+}
+
+/// The bytes of a frame exactly as they were received from the sensor, see [`MessageStream::next_raw`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawFrame {
+    bytes: [u8; buffer::MAX_FRAME],
+    len: u8,
+}
+
+impl RawFrame {
+    fn from_slice(frame: &[u8]) -> Self {
+        let mut bytes = [0; buffer::MAX_FRAME];
+        bytes[..frame.len()].copy_from_slice(frame);
+        RawFrame {
+            bytes,
+            len: frame.len() as u8,
+        }
+    }
+
+    /// All bytes of the frame, from the start of frame byte to the payload checksum
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+
+    pub fn id(&self) -> u16 {
+        u16::from_be_bytes([self.bytes[1], self.bytes[2]])
+    }
+
+    pub fn ty(&self) -> u16 {
+        u16::from_be_bytes([self.bytes[5], self.bytes[6]])
+    }
+
+    /// The payload of the frame, without header and checksums
+    pub fn payload(&self) -> &[u8] {
+        &self.bytes[8..self.len as usize - 1]
+    }
+}
+
+impl AsRef<[u8]> for RawFrame {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+/// Serialized as bytes, formats without a byte type (like JSON) use a sequence of numbers
+#[cfg(feature = "serde")]
+impl serde::Serialize for Payload {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.as_slice())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Payload {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, SeqAccess, Visitor};
+
+        struct PayloadVisitor;
+
+        impl<'de> Visitor<'de> for PayloadVisitor {
+            type Value = Payload;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("at most 16 bytes")
+            }
+
+            fn visit_bytes<E: Error>(self, bytes: &[u8]) -> Result<Payload, E> {
+                if bytes.len() > 16 {
+                    return Err(E::invalid_length(bytes.len(), &self));
+                }
+                Ok(Payload::from_slice(bytes))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Payload, A::Error> {
+                let mut payload = Payload::from_slice(&[]);
+                while let Some(byte) = seq.next_element()? {
+                    if payload.len as usize == payload.bytes.len() {
+                        return Err(A::Error::invalid_length(payload.len as usize + 1, &self));
+                    }
+                    payload.bytes[payload.len as usize] = byte;
+                    payload.len += 1;
+                }
+                Ok(payload)
+            }
+        }
+
+        deserializer.deserialize_bytes(PayloadVisitor)
+    }
 }
 
 /// A wrapper around [`Read`](embedded-io::Read) for reading messages from the sensor
-pub struct MessageStream<R> {
+pub struct MessageStream<R, C = fn() -> u64> {
     reader: R,
+    buffer: FrameBuffer,
+    clock: Option<C>,
+    rates: RateMeter,
+    quirks: Quirks,
+    resync: bool,
+    #[cfg(feature = "latency")]
+    timer: latency::FrameTimer,
 }
 
 impl<R: Read> MessageStream<R> {
     pub fn new(reader: R) -> Self {
-        Self { reader }
+        Self {
+            reader,
+            buffer: FrameBuffer::new(),
+            clock: None,
+            rates: RateMeter::default(),
+            quirks: Quirks::default(),
+            resync: false,
+            #[cfg(feature = "latency")]
+            timer: latency::FrameTimer::default(),
+        }
+    }
+}
+
+impl<R: Read, C: Clock> MessageStream<R, C> {
+    /// Create a message stream that timestamps received frames using `clock`
+    ///
+    /// This enables measuring the reporting rate of the sensor using [`rates`](Self::rates).
+    pub fn with_clock(reader: R, clock: C) -> Self {
+        Self {
+            reader,
+            buffer: FrameBuffer::new(),
+            clock: Some(clock),
+            rates: RateMeter::default(),
+            quirks: Quirks::default(),
+            resync: false,
+            #[cfg(feature = "latency")]
+            timer: latency::FrameTimer::default(),
+        }
+    }
+
+    /// Set the firmware workarounds applied while decoding, see [`Quirks`]
+    pub fn with_quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Resynchronize to the next valid frame when invalid data is received
+    ///
+    /// Without resync, every invalid start of frame or header is returned as an error and parsing continues
+    /// after the bytes that were read. With resync enabled, the stream instead scans forward for the next start
+    /// of frame byte that is followed by a valid header, so a single lost or corrupted byte on the wire
+    /// only costs the frame it was part of. Body checksum errors are still returned.
+    pub fn with_resync(mut self, resync: bool) -> Self {
+        self.resync = resync;
+        self
     }
 
     fn read(&mut self) -> Result<Frame, LdError<R::Error>> {
-        Frame::read(&mut self.reader)
+        let mut retries = 0;
+        loop {
+            let remaining = match self.buffer.remaining(&self.quirks) {
+                Ok(remaining) => remaining,
+                Err(_) if self.resync => {
+                    self.buffer.resync();
+                    continue;
+                }
+                Err(e) => {
+                    self.buffer.clear();
+                    return Err(e);
+                }
+            };
+            if remaining.is_empty() {
+                let frame = self.take()?;
+                if let Some(clock) = self.clock.as_mut() {
+                    let now = clock.now_ms();
+                    if let Some(ty) = frame.header.ty {
+                        self.rates.record(ty, now);
+                    }
+                    #[cfg(feature = "latency")]
+                    self.timer.decoded(now, self.buffer.latency_mut());
+                }
+                return Ok(frame);
+            }
+
+            match self.reader.read(remaining) {
+                Ok(0) => return Err(LdError::Eof),
+                Ok(read) => {
+                    #[cfg(feature = "latency")]
+                    if let (false, Some(clock)) = (self.buffer.is_partial(), self.clock.as_mut()) {
+                        self.timer.started(clock.now_ms());
+                    }
+                    self.buffer.advance(read);
+                    retries = 0;
+                }
+                Err(e) if retries < MAX_RETRIES && retryable(&e, self.buffer.is_partial()) => {
+                    retries += 1;
+                }
+                Err(e) => return Err(LdError::Read(e)),
+            }
+        }
+    }
+
+    fn take(&mut self) -> Result<Frame, LdError<R::Error>> {
+        self.buffer.take(&self.quirks).inspect_err(|_| {
+            #[cfg(feature = "latency")]
+            self.timer.dropped();
+            if self.resync {
+                self.buffer.resync();
+            } else {
+                self.buffer.clear();
+            }
+        })
+    }
+
+    /// Read the next message along with the bytes of the frame it was decoded from
+    ///
+    /// Useful for logging the exact bytes of frames that aren't decoded by this crate,
+    /// like [`MessageBody::Unknown`].
+    pub fn next_raw(&mut self) -> Result<(RawFrame, MessageBody), LdError<R::Error>> {
+        let frame = self.read()?;
+        Ok((frame.raw, frame.body(&self.quirks)?))
+    }
+
+    /// The measured frames per second for each message type
+    ///
+    /// Always empty for streams created without a [`Clock`].
+    pub fn rates(&mut self) -> Rates {
+        match self.clock.as_mut() {
+            Some(clock) => self.rates.rates(clock.now_ms()),
+            None => Rates::default(),
+        }
+    }
+
+    /// Counters of received frames and errors, for diagnosing the link to the sensor
+    pub fn stats(&self) -> Stats {
+        self.buffer.stats()
+    }
+
+    /// Record the latency of a stage after decoding in the [`Stats`], see [`latency`]
+    #[cfg(feature = "latency")]
+    pub fn record_latency(&mut self, stage: latency::Stage, ms: u32) {
+        self.buffer.latency_mut().record(stage, ms);
     }
 }
 
-impl<R: Read> Iterator for MessageStream<R> {
+impl<R: Read, C: Clock> Iterator for MessageStream<R, C> {
     type Item = Result<MessageBody, LdError<R::Error>>;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -325,52 +663,268 @@ impl<R: Read> Iterator for MessageStream<R> {
             Err(e) => return Some(Err(e)),
         };
 
-        Some(frame.body::<R::Error>())
+        Some(frame.body::<R::Error>(&self.quirks))
     }
 }
 
 /// A wrapper around [`AsyncRead`](embedded-io-async::AsyncRead) for reading messages from the sensor
-pub struct AsyncMessageStream<R> {
+pub struct AsyncMessageStream<R, C = fn() -> u64> {
     reader: R,
+    buffer: FrameBuffer,
+    clock: Option<C>,
+    rates: RateMeter,
+    quirks: Quirks,
+    resync: bool,
+    #[cfg(feature = "latency")]
+    timer: latency::FrameTimer,
 }
 
 impl<R: AsyncRead> AsyncMessageStream<R> {
     pub fn new(reader: R) -> Self {
-        Self { reader }
+        Self {
+            reader,
+            buffer: FrameBuffer::new(),
+            clock: None,
+            rates: RateMeter::default(),
+            quirks: Quirks::default(),
+            resync: false,
+            #[cfg(feature = "latency")]
+            timer: latency::FrameTimer::default(),
+        }
+    }
+}
+
+impl<R: AsyncRead, C: Clock> AsyncMessageStream<R, C> {
+    /// Create a message stream that timestamps received frames using `clock`
+    ///
+    /// This enables measuring the reporting rate of the sensor using [`rates`](Self::rates).
+    pub fn with_clock(reader: R, clock: C) -> Self {
+        Self {
+            reader,
+            buffer: FrameBuffer::new(),
+            clock: Some(clock),
+            rates: RateMeter::default(),
+            quirks: Quirks::default(),
+            resync: false,
+            #[cfg(feature = "latency")]
+            timer: latency::FrameTimer::default(),
+        }
+    }
+
+    /// Set the firmware workarounds applied while decoding, see [`Quirks`]
+    pub fn with_quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Resynchronize to the next valid frame when invalid data is received
+    ///
+    /// Without resync, every invalid start of frame or header is returned as an error and parsing continues
+    /// after the bytes that were read. With resync enabled, the stream instead scans forward for the next start
+    /// of frame byte that is followed by a valid header, so a single lost or corrupted byte on the wire
+    /// only costs the frame it was part of. Body checksum errors are still returned.
+    pub fn with_resync(mut self, resync: bool) -> Self {
+        self.resync = resync;
+        self
     }
 
     async fn read(&mut self) -> Result<Frame, LdError<R::Error>> {
-        Frame::read_async(&mut self.reader).await
+        let mut retries = 0;
+        loop {
+            let remaining = match self.buffer.remaining(&self.quirks) {
+                Ok(remaining) => remaining,
+                Err(_) if self.resync => {
+                    self.buffer.resync();
+                    continue;
+                }
+                Err(e) => {
+                    self.buffer.clear();
+                    return Err(e);
+                }
+            };
+            if remaining.is_empty() {
+                let frame = self.take()?;
+                if let Some(clock) = self.clock.as_mut() {
+                    let now = clock.now_ms();
+                    if let Some(ty) = frame.header.ty {
+                        self.rates.record(ty, now);
+                    }
+                    #[cfg(feature = "latency")]
+                    self.timer.decoded(now, self.buffer.latency_mut());
+                }
+                return Ok(frame);
+            }
+
+            match self.reader.read(remaining).await {
+                Ok(0) => return Err(LdError::Eof),
+                Ok(read) => {
+                    #[cfg(feature = "latency")]
+                    if let (false, Some(clock)) = (self.buffer.is_partial(), self.clock.as_mut()) {
+                        self.timer.started(clock.now_ms());
+                    }
+                    self.buffer.advance(read);
+                    retries = 0;
+                }
+                Err(e) if retries < MAX_RETRIES && retryable(&e, self.buffer.is_partial()) => {
+                    retries += 1;
+                }
+                Err(e) => return Err(LdError::Read(e)),
+            }
+        }
+    }
+
+    fn take(&mut self) -> Result<Frame, LdError<R::Error>> {
+        self.buffer.take(&self.quirks).inspect_err(|_| {
+            #[cfg(feature = "latency")]
+            self.timer.dropped();
+            if self.resync {
+                self.buffer.resync();
+            } else {
+                self.buffer.clear();
+            }
+        })
     }
 
     /// Read the next message from the sensor
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe as long as the `read` of the underlying reader is.
+    /// The bytes of a partially received frame are kept in the stream, so dropping the future
+    /// (e.g. when it loses a `select!` or hits a timeout) and calling `next` again continues
+    /// with the same frame. The same applies when `next` returns a read error halfway through a frame.
     pub async fn next(&mut self) -> Result<MessageBody, LdError<R::Error>> {
         let frame = self.read().await?;
-        frame.body()
+        frame.body(&self.quirks)
+    }
+
+    /// Read the next message along with the bytes of the frame it was decoded from
+    ///
+    /// Like [`next`](Self::next), this is cancel safe. See [`MessageStream::next_raw`].
+    pub async fn next_raw(&mut self) -> Result<(RawFrame, MessageBody), LdError<R::Error>> {
+        let frame = self.read().await?;
+        Ok((frame.raw, frame.body(&self.quirks)?))
+    }
+
+    /// Read the next message from the sensor, or stop when `shutdown` completes first
+    ///
+    /// If both are ready at the same time, the shutdown takes priority. Since [`next`](Self::next)
+    /// is cancel safe, a partially received frame isn't lost when the shutdown wins,
+    /// and reading can continue later.
+    pub async fn next_or<F: Future>(
+        &mut self,
+        shutdown: F,
+    ) -> NextOr<Result<MessageBody, LdError<R::Error>>, F::Output> {
+        let mut next = pin!(self.next());
+        let mut shutdown = pin!(shutdown);
+        poll_fn(|cx| {
+            if let Poll::Ready(output) = shutdown.as_mut().poll(cx) {
+                return Poll::Ready(NextOr::Shutdown(output));
+            }
+            next.as_mut().poll(cx).map(NextOr::Message)
+        })
+        .await
+    }
+
+    /// The measured frames per second for each message type
+    ///
+    /// Always empty for streams created without a [`Clock`].
+    pub fn rates(&mut self) -> Rates {
+        match self.clock.as_mut() {
+            Some(clock) => self.rates.rates(clock.now_ms()),
+            None => Rates::default(),
+        }
+    }
+
+    /// Counters of received frames and errors, for diagnosing the link to the sensor
+    pub fn stats(&self) -> Stats {
+        self.buffer.stats()
+    }
+
+    /// Record the latency of a stage after decoding in the [`Stats`], see [`latency`]
+    #[cfg(feature = "latency")]
+    pub fn record_latency(&mut self, stage: latency::Stage, ms: u32) {
+        self.buffer.latency_mut().record(stage, ms);
     }
 }
 
+/// Result of [`AsyncMessageStream::next_or`]
+#[derive(Clone, Debug)]
+pub enum NextOr<T, S> {
+    /// A message was received
+    Message(T),
+    /// The shutdown future completed with the contained output
+    Shutdown(S),
+}
+
 /// A helper struct to store the received data
 #[derive(Default, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Data {
     pub respiratory: f32,
     pub distance: f32,
     pub heartbeat: f32,
+    /// Confidence in the heartbeat rate between 0 and 1, only set by the `ConfidenceEstimator`
+    pub heartbeat_confidence: f32,
+    /// Confidence in the respiratory rate between 0 and 1, only set by the `ConfidenceEstimator`
+    pub respiratory_confidence: f32,
 }
 
 impl Data {
     pub fn update(&mut self, message: MessageBody) {
+        self.apply(message);
+    }
+
+    /// Update the data with a message, returning how the message affected the data
+    ///
+    /// Zero readings don't overwrite the last value, a report of no target in range is returned
+    /// as [`DataEvent::NoTarget`] so callers can react to a person leaving.
+    pub fn apply(&mut self, message: MessageBody) -> DataEvent {
         match message {
             MessageBody::Respiratory(rate) if rate > 0.0 => {
                 self.respiratory = rate;
+                DataEvent::Updated(Field::Respiratory)
             }
             MessageBody::Distance(Some(distance)) if distance > 0.0 => {
                 self.distance = distance;
+                DataEvent::Updated(Field::Distance)
             }
+            MessageBody::Distance(_) => DataEvent::NoTarget,
             MessageBody::Heartbeat(rate) if rate > 0.0 => {
                 self.heartbeat = rate;
+                DataEvent::Updated(Field::Heartbeat)
             }
-            _ => {}
+            _ => DataEvent::Ignored,
+        }
+    }
+}
+
+/// How a message affected [`Data`], returned by [`Data::apply`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataEvent {
+    /// The field was updated with a new reading
+    Updated(Field),
+    /// The sensor reported that there is no target in range
+    NoTarget,
+    /// The message didn't change the data, like a zero rate or a phase report
+    Ignored,
+}
+
+/// A field of [`Data`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Respiratory,
+    Distance,
+    Heartbeat,
+}
+
+impl Field {
+    pub fn get(self, data: &Data) -> f32 {
+        match self {
+            Field::Respiratory => data.respiratory,
+            Field::Distance => data.distance,
+            Field::Heartbeat => data.heartbeat,
         }
     }
 }
@@ -381,4 +935,4 @@ fn checksum(data: &[u8]) -> u8 {
         result ^= byte;
     }
     !result
-}
\ No newline at end of file
+}
@@ -0,0 +1,268 @@
+//! Logging the readings to rotating CSV or JSON lines files
+//!
+//! [`DataLogger`] writes a timestamped [`Data`] snapshot per sample interval, so long recordings
+//! don't depend on a broker or database being available. Files are rotated once they reach a size or age,
+//! and flushed according to a [`Flush`] policy, trading durability against wear on SD cards.
+//!
+//! ```rust,no_run
+//! use hlk_ld6002::logger::{DataLogger, Format, LoggerConfig};
+//! use hlk_ld6002::{Data, MessageStream};
+//! use std::time::{SystemTime, UNIX_EPOCH};
+//!
+//! let mut logger = DataLogger::new(LoggerConfig::new("/var/log/radar", Format::Csv)).unwrap();
+//! let mut data = Data::default();
+//! for message in MessageStream::open("/dev/ttyUSB0", 1_382_400).unwrap().flatten() {
+//!     data.update(message);
+//!     let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+//!     logger.log(now, &data).unwrap();
+//! }
+//! ```
+
+use crate::Data;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::string::String;
+
+/// File format of a [`DataLogger`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Comma separated values with a header line, readings of 0 are left empty
+    Csv,
+    /// One JSON object per line, readings of 0 are `null`
+    JsonLines,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::JsonLines => "jsonl",
+        }
+    }
+}
+
+/// When a [`DataLogger`] flushes the written samples to the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flush {
+    /// After every sample, nothing is lost when the process is killed
+    EverySample,
+    /// After this many samples
+    Samples(u32),
+    /// When at least this many milliseconds passed since the last flush
+    Interval(u64),
+}
+
+/// Where and how a [`DataLogger`] writes
+#[derive(Debug, Clone)]
+pub struct LoggerConfig {
+    /// Directory the files are created in, created if it doesn't exist
+    pub directory: PathBuf,
+    /// Start of the file names, followed by the timestamp of the first sample
+    pub prefix: String,
+    pub format: Format,
+    /// Minimum milliseconds between samples, samples in between are skipped
+    pub interval: u64,
+    pub flush: Flush,
+    /// Also ask the OS to write flushed samples to the disk, so they survive a power loss
+    pub sync: bool,
+    /// Start a new file once the current one reaches this many bytes
+    pub max_size: Option<u64>,
+    /// Start a new file once the current one covers this many milliseconds
+    pub max_age: Option<u64>,
+}
+
+impl LoggerConfig {
+    /// A sample per second to daily files in `directory`, flushed every 10 seconds
+    pub fn new(directory: impl Into<PathBuf>, format: Format) -> Self {
+        LoggerConfig {
+            directory: directory.into(),
+            prefix: "ld6002".into(),
+            format,
+            interval: 1_000,
+            flush: Flush::Interval(10_000),
+            sync: false,
+            max_size: None,
+            max_age: Some(24 * 60 * 60 * 1000),
+        }
+    }
+}
+
+/// The file currently written to
+struct LogFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    /// Timestamp of the first sample in the file
+    started: u64,
+    size: u64,
+}
+
+/// Writes timestamped [`Data`] snapshots to rotating files
+pub struct DataLogger {
+    config: LoggerConfig,
+    file: Option<LogFile>,
+    last_sample: Option<u64>,
+    last_flush: u64,
+    unflushed: u32,
+}
+
+impl DataLogger {
+    /// Create a logger, the first file is only created once the first sample is logged
+    pub fn new(config: LoggerConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.directory)?;
+        Ok(DataLogger {
+            config,
+            file: None,
+            last_sample: None,
+            last_flush: 0,
+            unflushed: 0,
+        })
+    }
+
+    /// Log the readings at `timestamp` milliseconds, returning whether a sample was written
+    ///
+    /// The timestamp is written to the file as is, use milliseconds since the unix epoch to be able to
+    /// line the samples up with other recordings later. Nothing is written if less than the sample interval
+    /// passed since the last sample.
+    pub fn log(&mut self, timestamp: u64, data: &Data) -> io::Result<bool> {
+        if self
+            .last_sample
+            .is_some_and(|last| timestamp.saturating_sub(last) < self.config.interval)
+        {
+            return Ok(false);
+        }
+        self.last_sample = Some(timestamp);
+
+        if self
+            .file
+            .as_ref()
+            .is_some_and(|file| self.is_full(file, timestamp))
+        {
+            self.close()?;
+        }
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => self.file.insert(Self::create(&self.config, timestamp)?),
+        };
+
+        let line = match self.config.format {
+            Format::Csv => csv_line(timestamp, data),
+            Format::JsonLines => json_line(timestamp, data),
+        };
+        file.writer.write_all(line.as_bytes())?;
+        file.size += line.len() as u64;
+        self.unflushed += 1;
+
+        let due = match self.config.flush {
+            Flush::EverySample => true,
+            Flush::Samples(samples) => self.unflushed >= samples,
+            Flush::Interval(interval) => timestamp.saturating_sub(self.last_flush) >= interval,
+        };
+        if due {
+            self.flush_at(timestamp)?;
+        }
+        Ok(true)
+    }
+
+    fn is_full(&self, file: &LogFile, timestamp: u64) -> bool {
+        self.config.max_size.is_some_and(|max| file.size >= max)
+            || self
+                .config
+                .max_age
+                .is_some_and(|max| timestamp.saturating_sub(file.started) >= max)
+    }
+
+    fn create(config: &LoggerConfig, timestamp: u64) -> io::Result<LogFile> {
+        let name = std::format!(
+            "{}-{timestamp}.{}",
+            config.prefix,
+            config.format.extension()
+        );
+        let path = config.directory.join(name);
+        let mut writer = BufWriter::new(File::create(&path)?);
+        let mut size = 0;
+        if config.format == Format::Csv {
+            let header = "timestamp,heartbeat,respiratory,distance,heartbeat_confidence,respiratory_confidence\n";
+            writer.write_all(header.as_bytes())?;
+            size = header.len() as u64;
+        }
+        Ok(LogFile {
+            path,
+            writer,
+            started: timestamp,
+            size,
+        })
+    }
+
+    fn flush_at(&mut self, timestamp: u64) -> io::Result<()> {
+        self.flush()?;
+        self.last_flush = timestamp;
+        Ok(())
+    }
+
+    /// Write the buffered samples to the file, regardless of the flush policy
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.writer.flush()?;
+            if self.config.sync {
+                file.writer.get_ref().sync_data()?;
+            }
+        }
+        self.unflushed = 0;
+        Ok(())
+    }
+
+    /// Flush and close the current file, the next sample starts a new one
+    pub fn close(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.file = None;
+        Ok(())
+    }
+
+    /// The file currently written to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.file.as_ref().map(|file| file.path.as_path())
+    }
+}
+
+impl Drop for DataLogger {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+fn reading(value: f32) -> Option<f32> {
+    (value > 0.0).then_some(value)
+}
+
+fn csv_line(timestamp: u64, data: &Data) -> String {
+    let field = |value: f32| {
+        reading(value)
+            .map(|value| std::format!("{value}"))
+            .unwrap_or_default()
+    };
+    std::format!(
+        "{timestamp},{},{},{},{},{}\n",
+        field(data.heartbeat),
+        field(data.respiratory),
+        field(data.distance),
+        field(data.heartbeat_confidence),
+        field(data.respiratory_confidence),
+    )
+}
+
+fn json_line(timestamp: u64, data: &Data) -> String {
+    let field = |value: f32| {
+        reading(value)
+            .map(|value| std::format!("{value}"))
+            .unwrap_or_else(|| "null".into())
+    };
+    std::format!(
+        "{{\"timestamp\":{timestamp},\"heartbeat\":{},\"respiratory\":{},\"distance\":{},\"heartbeat_confidence\":{},\"respiratory_confidence\":{}}}\n",
+        field(data.heartbeat),
+        field(data.respiratory),
+        field(data.distance),
+        field(data.heartbeat_confidence),
+        field(data.respiratory_confidence),
+    )
+}
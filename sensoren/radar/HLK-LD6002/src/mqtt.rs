@@ -0,0 +1,173 @@
+//! Publishing the readings over MQTT from a microcontroller, using [`minimq`]
+//!
+//! [`MqttPublisher`] wraps a [`Minimq`] client and publishes [`Data`] snapshots as JSON to
+//! `<prefix>/state`, so nodes without a Linux host can report to the same broker as the
+//! `hlk_ld6002_mqtt` bridge. Minimq reconnects to the broker by itself as long as it's polled,
+//! after every (re)connect the publisher marks the node as `online` on `<prefix>/availability`.
+//! Set a will of `offline` on the same topic to mark the node offline when the connection is lost:
+//!
+//! ```rust,ignore
+//! let will = Will::new("radar/bedroom/availability", b"offline", &[])?.retained();
+//! let config = ConfigBuilder::new(broker, &mut buffer).client_id("bedroom")?.will(will)?;
+//! let mut publisher = MqttPublisher::new(Minimq::new(stack, clock, config), "radar/bedroom")?;
+//! loop {
+//!     publisher.poll()?;
+//!     // once per second
+//!     publisher.publish(&data)?;
+//! }
+//! ```
+//!
+//! The payload uses the same field names as the one of the bridge, readings of 0 are published as `null`.
+//! There is no presence in the payload, as the node doesn't run a detector by itself.
+
+use crate::Data;
+use core::fmt::{self, Write};
+use minimq::embedded_nal::TcpClientStack;
+use minimq::embedded_time::Clock;
+use minimq::{Broker, Minimq, PubError, Publication};
+
+/// Maximum length of the topics, including the prefix
+pub const MAX_TOPIC: usize = 64;
+
+/// A topic below the configured prefix
+#[derive(Debug, Clone, Copy)]
+pub struct Topic {
+    bytes: [u8; MAX_TOPIC],
+    len: usize,
+}
+
+impl Topic {
+    /// `<prefix>/<name>`, `None` if it's longer than [`MAX_TOPIC`]
+    pub fn new(prefix: &str, name: &str) -> Option<Self> {
+        let mut bytes = [0; MAX_TOPIC];
+        let mut out = Cursor {
+            buffer: &mut bytes,
+            len: 0,
+        };
+        write!(out, "{}/{name}", prefix.trim_end_matches('/')).ok()?;
+        let len = out.len;
+        Some(Topic { bytes, len })
+    }
+
+    pub fn as_str(&self) -> &str {
+        // only ever filled from `&str`
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+}
+
+/// Writes into a byte buffer, failing once it's full
+struct Cursor<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl Write for Cursor<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.buffer
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Serialize the readings as JSON into `buffer`, returning the length of the payload
+///
+/// Fails if the buffer is too small, 100 bytes are enough for any rate and distance the sensor reports.
+pub fn write_json(data: &Data, buffer: &mut [u8]) -> Result<usize, fmt::Error> {
+    let mut out = Cursor { buffer, len: 0 };
+    let fields = [
+        ("heartbeat", data.heartbeat),
+        ("respiratory", data.respiratory),
+        ("distance", data.distance),
+    ];
+    out.write_char('{')?;
+    for (i, (name, value)) in fields.into_iter().enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        if value > 0.0 {
+            write!(out, "\"{name}\":{value:.2}")?;
+        } else {
+            write!(out, "\"{name}\":null")?;
+        }
+    }
+    out.write_char('}')?;
+    Ok(out.len)
+}
+
+/// Publishes [`Data`] snapshots through a [`Minimq`] client
+pub struct MqttPublisher<'buf, S: TcpClientStack, C: Clock, B: Broker> {
+    mqtt: Minimq<'buf, S, C, B>,
+    state: Topic,
+    availability: Topic,
+    announced: bool,
+}
+
+impl<'buf, S: TcpClientStack, C: Clock, B: Broker> MqttPublisher<'buf, S, C, B> {
+    /// Publish below `prefix`, `None` if the topics would be longer than [`MAX_TOPIC`]
+    pub fn new(mqtt: Minimq<'buf, S, C, B>, prefix: &str) -> Option<Self> {
+        Some(MqttPublisher {
+            mqtt,
+            state: Topic::new(prefix, "state")?,
+            availability: Topic::new(prefix, "availability")?,
+            announced: false,
+        })
+    }
+
+    pub fn state_topic(&self) -> &str {
+        self.state.as_str()
+    }
+
+    pub fn availability_topic(&self) -> &str {
+        self.availability.as_str()
+    }
+
+    /// Keep the connection alive and reconnect when needed, needs to be called regularly
+    ///
+    /// Incoming messages are ignored. A lost session isn't an error, the node is simply
+    /// announced again once the connection is back.
+    pub fn poll(&mut self) -> Result<(), minimq::Error<S::Error>> {
+        match self.mqtt.poll(|_, _, _, _| ()) {
+            Ok(_) | Err(minimq::Error::SessionReset) => {}
+            Err(e) => return Err(e),
+        }
+        if !self.mqtt.client().is_connected() {
+            self.announced = false;
+        } else if !self.announced {
+            let online = Publication::new(self.availability.as_str(), "online").retain();
+            match self.mqtt.client().publish(online) {
+                Ok(()) => self.announced = true,
+                // try again on the next poll
+                Err(PubError::Error(minimq::Error::NotReady)) => {}
+                Err(PubError::Error(e)) => return Err(e),
+                Err(PubError::Serialization(())) => return Err(minimq::Error::WriteFail),
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the client is connected to the broker
+    pub fn is_connected(&mut self) -> bool {
+        self.mqtt.client().is_connected()
+    }
+
+    /// Publish the readings, returning `false` if not connected and the readings were dropped
+    pub fn publish(&mut self, data: &Data) -> Result<bool, PubError<S::Error, fmt::Error>> {
+        if !self.mqtt.client().is_connected() {
+            return Ok(false);
+        }
+        let data = *data;
+        let payload = move |buffer: &mut [u8]| write_json(&data, buffer);
+        self.mqtt
+            .client()
+            .publish(Publication::new(self.state.as_str(), payload))?;
+        Ok(true)
+    }
+
+    pub fn into_inner(self) -> Minimq<'buf, S, C, B> {
+        self.mqtt
+    }
+}
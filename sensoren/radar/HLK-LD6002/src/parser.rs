@@ -0,0 +1,171 @@
+//! Parsing frames from bytes received by other means than a reader
+//!
+//! [`FrameParser`] doesn't do any IO itself, it's fed with whatever bytes are available,
+//! e.g. from a DMA ring buffer or a UART interrupt, and keeps partial frames between calls.
+//! Bytes can either be [`push`](FrameParser::push)ed in chunks, or [`feed`](FrameParser::feed) one at a time
+//! to follow the progress of the parser byte by byte, as needed by protocol decoders for logic analyzers.
+//!
+//! Bridges forwarding the serial data over BLE split it into notifications that don't line up with
+//! the frames, a notification can hold the end of one frame and the start of the next, or several frames
+//! at once. [`push_notification`](FrameParser::push_notification) handles a whole notification at once,
+//! which fits the callbacks BLE stacks deliver notifications to.
+
+use crate::buffer::FrameBuffer;
+use crate::{LdError, MessageBody, Quirks, Stats};
+use core::convert::Infallible;
+
+/// A state machine decoding messages from pushed bytes
+///
+/// Being `const` constructible, it can live in a `static` shared with an interrupt handler.
+#[derive(Debug, Clone)]
+pub struct FrameParser {
+    buffer: FrameBuffer,
+    quirks: Quirks,
+    resync: bool,
+}
+
+impl Default for FrameParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameParser {
+    pub const fn new() -> Self {
+        FrameParser {
+            buffer: FrameBuffer::new(),
+            quirks: Quirks::DEFAULT,
+            resync: false,
+        }
+    }
+
+    /// Set the firmware workarounds applied while decoding, see [`Quirks`]
+    pub const fn with_quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Resynchronize to the next valid frame when invalid data is received,
+    /// see [`MessageStream::with_resync`](crate::MessageStream::with_resync)
+    pub const fn with_resync(mut self, resync: bool) -> Self {
+        self.resync = resync;
+        self
+    }
+
+    /// Feed received bytes into the parser, returning the messages completed by them
+    ///
+    /// The bytes are consumed while iterating, the iterator needs to be run to the end
+    /// for all of them to be parsed. A trailing partial frame is kept for the next call.
+    pub fn push<'a>(&'a mut self, bytes: &'a [u8]) -> Messages<'a> {
+        Messages {
+            parser: self,
+            bytes,
+        }
+    }
+
+    /// Feed a complete BLE notification (or any other chunk) into the parser, passing every decoded message
+    /// or error to `on_message`
+    ///
+    /// Unlike with [`push`](Self::push), all bytes are consumed when this returns, so none get lost
+    /// by dropping the iterator early. Returns the number of messages and errors passed on.
+    pub fn push_notification(
+        &mut self,
+        notification: &[u8],
+        on_message: impl FnMut(Result<MessageBody, LdError<Infallible>>),
+    ) -> usize {
+        self.push(notification).map(on_message).count()
+    }
+
+    /// Feed a single byte into the parser, returning what it completed
+    ///
+    /// Unlike [`push`](Self::push), this also reports the header of a frame as soon as it's received.
+    pub fn feed(&mut self, byte: u8) -> ParserEvent {
+        match self.push(&[byte]).next() {
+            Some(Ok(message)) => ParserEvent::Message(message),
+            Some(Err(error)) => ParserEvent::Error(error),
+            None if self.buffer.is_header_complete() => {
+                match self.buffer.header::<Infallible>(&self.quirks) {
+                    Ok(header) => ParserEvent::Header {
+                        id: header.id,
+                        ty: header.raw_ty,
+                        length: header.length,
+                    },
+                    Err(error) => ParserEvent::Error(error),
+                }
+            }
+            None => ParserEvent::Pending,
+        }
+    }
+
+    /// Counters of received frames and errors, for diagnosing the link to the sensor
+    pub fn stats(&self) -> Stats {
+        self.buffer.stats()
+    }
+
+    /// Discard a partially received frame, e.g. after a receive error
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+/// The result of feeding a byte to [`FrameParser::feed`]
+#[derive(Debug)]
+pub enum ParserEvent {
+    /// The byte was buffered, the frame isn't complete yet
+    Pending,
+    /// The byte completed a valid frame header
+    Header { id: u16, ty: u16, length: u16 },
+    /// The byte completed a frame
+    Message(MessageBody),
+    /// The byte made the frame invalid, the parser continues with the next byte
+    Error(LdError<Infallible>),
+}
+
+/// Messages decoded from the bytes passed to [`FrameParser::push`]
+pub struct Messages<'a> {
+    parser: &'a mut FrameParser,
+    bytes: &'a [u8],
+}
+
+impl Iterator for Messages<'_> {
+    type Item = Result<MessageBody, LdError<Infallible>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let parser = &mut *self.parser;
+        loop {
+            let remaining = match parser.buffer.remaining(&parser.quirks) {
+                Ok(remaining) => remaining,
+                Err(_) if parser.resync => {
+                    parser.buffer.resync();
+                    continue;
+                }
+                Err(e) => {
+                    parser.buffer.clear();
+                    return Some(Err(e));
+                }
+            };
+            if remaining.is_empty() {
+                let frame = match parser.buffer.take(&parser.quirks) {
+                    Ok(frame) => frame,
+                    Err(e) => {
+                        if parser.resync {
+                            parser.buffer.resync();
+                        } else {
+                            parser.buffer.clear();
+                        }
+                        return Some(Err(e));
+                    }
+                };
+                return Some(frame.body(&parser.quirks));
+            }
+            if self.bytes.is_empty() {
+                return None;
+            }
+
+            let read = remaining.len().min(self.bytes.len());
+            remaining[..read].copy_from_slice(&self.bytes[..read]);
+            parser.buffer.advance(read);
+            self.bytes = &self.bytes[read..];
+        }
+    }
+}
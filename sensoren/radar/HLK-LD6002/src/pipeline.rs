@@ -0,0 +1,111 @@
+//! Composing filters and detectors without a heap
+//!
+//! A [`Pipeline`] runs a fixed number of [`Stage`]s over every [`Data`] snapshot, the stages themselves
+//! can be kept in a [`Pool`]. Both are sized by const generics, so exceeding their capacity is a compile
+//! error instead of a runtime panic.
+//!
+//! Stages work on snapshots rather than on the messages of the sensor. [`Stage`] is implemented for
+//! closures and [`DataWindow`](crate::window::DataWindow)s, while the smoothing of
+//! [`FilteredData`](crate::filter::FilteredData) and the detectors work on the messages themselves, so they
+//! run before the pipeline: [`FilteredData::data`](crate::filter::FilteredData::data) makes a good snapshot to
+//! run it on, and a detector that follows a single reading, like a
+//! [`StuckDetector`](crate::stuck::StuckDetector) on the heartbeat, is plugged in with a closure.
+//!
+//! ```rust
+//! use hlk_ld6002::Data;
+//! use hlk_ld6002::pipeline::{Pipeline, Pool};
+//! use hlk_ld6002::window::DataWindow;
+//!
+//! let mut windows: Pool<DataWindow<16>, 2> = Pool::default();
+//! let (short, long) = windows.split::<0, 1>();
+//! let mut clamp = |_now: u64, data: &mut Data| data.heartbeat = data.heartbeat.min(200.0);
+//!
+//! let mut pipeline = Pipeline::new([&mut clamp, short, long]);
+//! pipeline.update(0, &mut Data::default());
+//! ```
+
+use crate::Data;
+
+/// A step in a [`Pipeline`], like a filter or detector
+pub trait Stage {
+    /// Process a snapshot of the data taken at `now` milliseconds
+    ///
+    /// Stages can modify the data to pass the result on to the next stage.
+    fn update(&mut self, now: u64, data: &mut Data);
+}
+
+impl<F: FnMut(u64, &mut Data)> Stage for F {
+    fn update(&mut self, now: u64, data: &mut Data) {
+        self(now, data)
+    }
+}
+
+impl<const N: usize> Stage for crate::window::DataWindow<N> {
+    fn update(&mut self, now: u64, data: &mut Data) {
+        self.record(now, data)
+    }
+}
+
+/// A fixed sequence of `N` stages
+pub struct Pipeline<'a, const N: usize> {
+    stages: [&'a mut dyn Stage; N],
+}
+
+impl<'a, const N: usize> Pipeline<'a, N> {
+    pub fn new(stages: [&'a mut dyn Stage; N]) -> Self {
+        Pipeline { stages }
+    }
+
+    /// Run all stages in order over a snapshot taken at `now` milliseconds
+    pub fn update(&mut self, now: u64, data: &mut Data) {
+        for stage in self.stages.iter_mut() {
+            stage.update(now, data);
+        }
+    }
+}
+
+/// Statically sized storage for `N` stages of the same type
+///
+/// Slots are accessed by const index, using an index outside of the pool fails to compile.
+#[derive(Debug, Clone)]
+pub struct Pool<T, const N: usize> {
+    slots: [T; N],
+}
+
+impl<T, const N: usize> Pool<T, N> {
+    pub const fn new(slots: [T; N]) -> Self {
+        Pool { slots }
+    }
+
+    /// Access the slot at index `I`
+    pub fn get<const I: usize>(&mut self) -> &mut T {
+        const { assert!(I < N, "pool index out of range") };
+        &mut self.slots[I]
+    }
+
+    /// Borrow two different slots at the same time
+    pub fn split<const A: usize, const B: usize>(&mut self) -> (&mut T, &mut T) {
+        const {
+            assert!(A < N && B < N, "pool index out of range");
+            assert!(A != B, "can't borrow the same slot twice");
+        };
+        let (low, high) = self.slots.split_at_mut(A.max(B));
+        if A < B {
+            (&mut low[A], &mut high[0])
+        } else {
+            (&mut high[0], &mut low[B])
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut()
+    }
+}
+
+impl<T: Default, const N: usize> Default for Pool<T, N> {
+    fn default() -> Self {
+        Pool {
+            slots: core::array::from_fn(|_| T::default()),
+        }
+    }
+}
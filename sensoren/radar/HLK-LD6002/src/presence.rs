@@ -0,0 +1,162 @@
+//! Debouncing the distance reports into presence
+//!
+//! A single distance report isn't reliable evidence, the sensor occasionally loses a person that sits
+//! still or briefly picks up reflections in an empty room. [`PresenceDetector`] only changes state
+//! after the new state has been observed for a hold time, and uses a larger distance for keeping
+//! a person present than for detecting them, so someone at the edge of the range doesn't flap.
+//!
+//! Rooms where people stay motionless for a long time or where steam scatters the radar, like bathrooms,
+//! need longer hold times, see [`PresenceConfig::bathroom`]. There, heartbeat and respiration readings
+//! can also keep a person present while the distance reports drop out.
+
+use crate::MessageBody;
+
+/// Whether someone is in front of the sensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Presence {
+    /// Not enough reports received yet, or the sensor stopped reporting
+    Unknown,
+    Present,
+    Absent,
+}
+
+/// Hold times and distances used by a [`PresenceDetector`]
+#[derive(Debug, Clone, Copy)]
+pub struct PresenceConfig {
+    /// Milliseconds a target needs to be reported before switching to present
+    pub enter_hold: u64,
+    /// Milliseconds without a target before switching to absent
+    pub leave_hold: u64,
+    /// Maximum distance in meters of a target to become present
+    pub max_distance: f32,
+    /// Additional distance in meters a present target can move away before it's lost
+    pub hysteresis: f32,
+    /// Milliseconds without any report after which the presence is unknown
+    pub timeout: u64,
+    /// Whether non-zero heartbeat and respiration readings keep a present target present
+    ///
+    /// The sensor only reports vitals for a target it's locked on, so they confirm a person that
+    /// stopped showing up in the distance reports, e.g. someone standing still in a steamy shower.
+    /// Vitals alone never make an absent room present.
+    pub vitals: bool,
+}
+
+impl PresenceConfig {
+    /// Profile for bathrooms, keeping a motionless person in the shower present
+    ///
+    /// Enters quickly so the light turns on when walking in, but only leaves after 5 minutes
+    /// without a target and takes vitals readings as confirmation that someone is still there.
+    /// A longer timeout rides out steam briefly disrupting the reports.
+    pub fn bathroom() -> Self {
+        PresenceConfig {
+            enter_hold: 1_000,
+            leave_hold: 300_000,
+            max_distance: 2.5,
+            hysteresis: 0.5,
+            timeout: 30_000,
+            vitals: true,
+        }
+    }
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        PresenceConfig {
+            enter_hold: 2_000,
+            leave_hold: 30_000,
+            max_distance: 2.0,
+            hysteresis: 0.2,
+            timeout: 10_000,
+            vitals: false,
+        }
+    }
+}
+
+/// Debounces distance reports into [`Presence`]
+#[derive(Debug, Clone)]
+pub struct PresenceDetector {
+    config: PresenceConfig,
+    state: Presence,
+    /// Since when the reports disagree with the current state
+    changing_since: Option<u64>,
+    last_report: Option<u64>,
+}
+
+impl PresenceDetector {
+    pub fn new(config: PresenceConfig) -> Self {
+        PresenceDetector {
+            config,
+            state: Presence::Unknown,
+            changing_since: None,
+            last_report: None,
+        }
+    }
+
+    /// Feed a message received at `now` milliseconds
+    ///
+    /// Distance reports decide the presence, phase reports only show that the sensor is still reporting.
+    /// With [`vitals`](PresenceConfig::vitals) enabled, heartbeat and respiration readings keep a present target present.
+    pub fn update(&mut self, now: u64, message: &MessageBody) -> Presence {
+        match message {
+            MessageBody::Distance(distance) => {
+                self.last_report = Some(now);
+                let range = match self.state {
+                    Presence::Present => self.config.max_distance + self.config.hysteresis,
+                    _ => self.config.max_distance,
+                };
+                let seen = distance.is_some_and(|distance| distance > 0.0 && distance <= range);
+                self.observe(now, seen);
+            }
+            MessageBody::Phase(_) => self.last_report = Some(now),
+            MessageBody::Heartbeat(rate) | MessageBody::Respiratory(rate) if self.config.vitals => {
+                self.last_report = Some(now);
+                if *rate > 0.0 && self.state == Presence::Present {
+                    self.changing_since = None;
+                }
+            }
+            _ => {}
+        }
+        self.state(now)
+    }
+
+    fn observe(&mut self, now: u64, seen: bool) {
+        let (target, hold) = if seen {
+            (Presence::Present, self.config.enter_hold)
+        } else {
+            (Presence::Absent, self.config.leave_hold)
+        };
+        if self.state == target {
+            self.changing_since = None;
+            return;
+        }
+
+        let since = *self.changing_since.get_or_insert(now);
+        if now.saturating_sub(since) >= hold {
+            self.state = target;
+            self.changing_since = None;
+        }
+    }
+
+    /// The presence as of `now` milliseconds
+    pub fn state(&self, now: u64) -> Presence {
+        match self.last_report {
+            Some(last) if now.saturating_sub(last) <= self.config.timeout => self.state,
+            _ => Presence::Unknown,
+        }
+    }
+
+    /// Forget the current state, e.g. after restarting the sensor
+    pub fn reset(&mut self) {
+        self.state = Presence::Unknown;
+        self.changing_since = None;
+        self.last_report = None;
+    }
+}
+
+impl Default for PresenceDetector {
+    fn default() -> Self {
+        Self::new(PresenceConfig::default())
+    }
+}
@@ -0,0 +1,40 @@
+//! Workarounds for known firmware bugs
+//!
+//! Every workaround can be toggled individually, [`Quirks::default`] enables the ones needed
+//! for the firmware versions seen in the wild so far.
+
+/// The set of enabled firmware workarounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// Accept `Distance` frames with a 4 byte body
+    ///
+    /// When no target is in range, the sensor occasionally sends a `Distance` frame containing only the
+    /// 4 byte flag instead of the documented 8 bytes. With this enabled these are decoded as `Distance(None)`,
+    /// otherwise they are rejected with [`LdError::InvalidDataLength`](crate::LdError::InvalidDataLength).
+    pub short_distance: bool,
+    /// Don't verify the header checksum
+    ///
+    /// The header checksums sent by the sensor don't always match the TinyFrame checksum
+    /// of the header, so by default only the body checksum is verified.
+    pub ignore_header_checksum: bool,
+}
+
+impl Quirks {
+    /// The workarounds enabled by [`Quirks::default`], usable in const contexts
+    pub const DEFAULT: Quirks = Quirks {
+        short_distance: true,
+        ignore_header_checksum: true,
+    };
+
+    /// Strict parsing without any workarounds
+    pub const NONE: Quirks = Quirks {
+        short_distance: false,
+        ignore_header_checksum: false,
+    };
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::DEFAULT
+    }
+}
@@ -0,0 +1,97 @@
+use crate::MessageType;
+
+/// Number of frame arrivals kept per message type
+const WINDOW: usize = 8;
+
+/// Arrival times of the last few frames of a single message type
+#[derive(Debug, Clone, Default)]
+struct Window {
+    stamps: [u32; WINDOW],
+    len: usize,
+    next: usize,
+}
+
+impl Window {
+    fn push(&mut self, now: u32) {
+        self.stamps[self.next] = now;
+        self.next = (self.next + 1) % WINDOW;
+        self.len = (self.len + 1).min(WINDOW);
+    }
+
+    fn rate(&self, now: u32) -> Option<f32> {
+        if self.len < 2 {
+            return None;
+        }
+        let oldest = self.stamps[(self.next + WINDOW - self.len) % WINDOW];
+        let newest = self.stamps[(self.next + WINDOW - 1) % WINDOW];
+        let intervals = self.len as u32 - 1;
+        let mut span = newest.wrapping_sub(oldest);
+
+        // once the sensor has been quiet for longer than a normal interval, count the gap
+        // so a stalled report shows up as a dropping rate instead of the last good value
+        if now.wrapping_sub(newest) > span / intervals {
+            span = now.wrapping_sub(oldest);
+        }
+
+        if span == 0 {
+            None
+        } else {
+            Some(intervals as f32 * 1000.0 / span as f32)
+        }
+    }
+}
+
+/// Measures the rate at which frames of each message type are received
+#[derive(Debug, Clone, Default)]
+pub struct RateMeter {
+    windows: [Window; 4],
+}
+
+impl RateMeter {
+    /// Record the arrival of a frame at `now` milliseconds
+    pub fn record(&mut self, ty: MessageType, now: u64) {
+        self.windows[slot(ty)].push(now as u32);
+    }
+
+    /// The measured rates as of `now` milliseconds
+    pub fn rates(&self, now: u64) -> Rates {
+        let now = now as u32;
+        Rates {
+            rates: [
+                self.windows[0].rate(now),
+                self.windows[1].rate(now),
+                self.windows[2].rate(now),
+                self.windows[3].rate(now),
+            ],
+        }
+    }
+}
+
+/// Measured frames per second for each message type
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rates {
+    rates: [Option<f32>; 4],
+}
+
+impl Rates {
+    /// Frames per second for the message type, `None` if not enough frames have been seen yet
+    pub fn get(&self, ty: MessageType) -> Option<f32> {
+        self.rates[slot(ty)]
+    }
+
+    /// Whether the message type is being received slower than `expected` frames per second
+    ///
+    /// Message types that haven't been received at all are also considered an underrun.
+    pub fn is_underrun(&self, ty: MessageType, expected: f32) -> bool {
+        self.get(ty).is_none_or(|rate| rate < expected)
+    }
+}
+
+fn slot(ty: MessageType) -> usize {
+    match ty {
+        MessageType::Phase => 0,
+        MessageType::Respiratory => 1,
+        MessageType::Heartbeat => 2,
+        MessageType::Distance => 3,
+    }
+}
@@ -0,0 +1,257 @@
+//! Recording raw serial traffic and replaying it later
+//!
+//! [`Recorder`] wraps the reader of the serial port and writes every chunk of received bytes
+//! to a capture, [`Replay`] reads a capture back as a reader, optionally with the original pacing.
+//! This allows reproducing problems seen in the field deterministically against the parser.
+//!
+//! A capture is a sequence of chunks, each consisting of the time since the first chunk in milliseconds
+//! as little endian `u32`, the number of bytes as little endian `u16`, followed by the bytes themselves.
+//! The timestamps cover about 49 days, chunks recorded after that all get the largest timestamp.
+
+use crate::Clock;
+use core::future::{Future, Ready};
+use embedded_io::{ErrorKind, ErrorType, Read, Write};
+use embedded_io_async::Read as AsyncRead;
+
+/// Length of the timestamp and length in front of every chunk
+const CHUNK_HEADER: usize = 6;
+
+/// Error returned by a [`Recorder`]
+#[derive(Debug)]
+pub enum RecordError<R, W> {
+    /// Error reading from the wrapped reader
+    Read(R),
+    /// Error writing the capture
+    Write(W),
+}
+
+impl<R: embedded_io::Error, W: embedded_io::Error> embedded_io::Error for RecordError<R, W> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            RecordError::Read(e) => e.kind(),
+            RecordError::Write(e) => e.kind(),
+        }
+    }
+}
+
+/// A reader that writes all bytes read from the wrapped reader to a capture
+pub struct Recorder<R, W, C> {
+    inner: R,
+    capture: W,
+    clock: C,
+    start: Option<u64>,
+}
+
+impl<R, W: Write, C: Clock> Recorder<R, W, C> {
+    /// Record the bytes read from `inner` into `capture`, timestamped using `clock`
+    pub fn new(inner: R, capture: W, clock: C) -> Self {
+        Recorder {
+            inner,
+            capture,
+            clock,
+            start: None,
+        }
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        (self.inner, self.capture)
+    }
+
+    fn record(&mut self, bytes: &[u8]) -> Result<(), W::Error> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let now = self.clock.now_ms();
+        let elapsed = now.saturating_sub(*self.start.get_or_insert(now));
+        let elapsed = u32::try_from(elapsed).unwrap_or(u32::MAX);
+
+        let mut header = [0; CHUNK_HEADER];
+        header[..4].copy_from_slice(&elapsed.to_le_bytes());
+        header[4..].copy_from_slice(&(bytes.len() as u16).to_le_bytes());
+        self.capture.write_all(&header)?;
+        self.capture.write_all(bytes)
+    }
+}
+
+impl<R: ErrorType, W: ErrorType, C> ErrorType for Recorder<R, W, C> {
+    type Error = RecordError<R::Error, W::Error>;
+}
+
+impl<R: Read, W: Write, C: Clock> Read for Recorder<R, W, C> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        // chunks are limited by the u16 length
+        let len = buf.len().min(u16::MAX as usize);
+        let read = self
+            .inner
+            .read(&mut buf[..len])
+            .map_err(RecordError::Read)?;
+        self.record(&buf[..read]).map_err(RecordError::Write)?;
+        Ok(read)
+    }
+}
+
+impl<R: AsyncRead, W: Write, C: Clock> AsyncRead for Recorder<R, W, C> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = buf.len().min(u16::MAX as usize);
+        let read = self
+            .inner
+            .read(&mut buf[..len])
+            .await
+            .map_err(RecordError::Read)?;
+        self.record(&buf[..read]).map_err(RecordError::Write)?;
+        Ok(read)
+    }
+}
+
+/// A reader returning the bytes of a capture in the chunks they were received in
+///
+/// The end of the capture is returned as end of file, a chunk cut off by stopping the recording
+/// is returned as far as it was recorded.
+pub struct Replay<R, D = fn(u64)> {
+    capture: R,
+    delay: Option<D>,
+    remaining: usize,
+    time: u32,
+}
+
+impl<R: Read> Replay<R> {
+    /// Replay a capture as fast as it's read
+    pub fn new(capture: R) -> Self {
+        Replay {
+            capture,
+            delay: None,
+            remaining: 0,
+            time: 0,
+        }
+    }
+}
+
+impl<R: Read, D: FnMut(u64)> Replay<R, D> {
+    /// Replay a capture with the original pacing
+    ///
+    /// Before every chunk, `delay` is called with the milliseconds between the previous chunk and this one
+    /// being received, e.g. `|ms| std::thread::sleep(Duration::from_millis(ms))`.
+    pub fn with_pacing(capture: R, delay: D) -> Self {
+        Replay {
+            capture,
+            delay: Some(delay),
+            remaining: 0,
+            time: 0,
+        }
+    }
+
+    /// Read the header of the next chunk, returning `false` at the end of the capture
+    fn next_chunk(&mut self) -> Result<bool, R::Error> {
+        let mut header = [0; CHUNK_HEADER];
+        let mut filled = 0;
+        while filled < CHUNK_HEADER {
+            match self.capture.read(&mut header[filled..])? {
+                0 => return Ok(false),
+                read => filled += read,
+            }
+        }
+        let elapsed = self.start_chunk(header);
+        if let Some(delay) = self.delay.as_mut() {
+            delay(elapsed);
+        }
+        Ok(true)
+    }
+}
+
+impl<R: AsyncRead> Replay<R, fn(u64) -> Ready<()>> {
+    /// Replay a capture from an async reader as fast as it's read
+    pub fn new_async(capture: R) -> Self {
+        Replay {
+            capture,
+            delay: None,
+            remaining: 0,
+            time: 0,
+        }
+    }
+}
+
+impl<R: AsyncRead, D: FnMut(u64) -> F, F: Future<Output = ()>> Replay<R, D> {
+    /// Replay a capture from an async reader with the original pacing
+    ///
+    /// Before every chunk, the future returned by `delay` is awaited, see [`with_pacing`](Replay::with_pacing),
+    /// e.g. `|ms| tokio::time::sleep(Duration::from_millis(ms))`.
+    pub fn with_async_pacing(capture: R, delay: D) -> Self {
+        Replay {
+            capture,
+            delay: Some(delay),
+            remaining: 0,
+            time: 0,
+        }
+    }
+
+    /// Read the header of the next chunk, returning `false` at the end of the capture
+    async fn next_chunk_async(&mut self) -> Result<bool, R::Error> {
+        let mut header = [0; CHUNK_HEADER];
+        let mut filled = 0;
+        while filled < CHUNK_HEADER {
+            match self.capture.read(&mut header[filled..]).await? {
+                0 => return Ok(false),
+                read => filled += read,
+            }
+        }
+        let elapsed = self.start_chunk(header);
+        if let Some(delay) = self.delay.as_mut() {
+            delay(elapsed).await;
+        }
+        Ok(true)
+    }
+}
+
+impl<R, D> Replay<R, D> {
+    /// Time since the start of the capture at which the current chunk was received, in milliseconds
+    pub fn now_ms(&self) -> u64 {
+        self.time as u64
+    }
+
+    /// Start the chunk with the given header, returning the milliseconds since the previous chunk
+    fn start_chunk(&mut self, header: [u8; CHUNK_HEADER]) -> u64 {
+        let time = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let elapsed = time.saturating_sub(self.time) as u64;
+        self.time = time;
+        self.remaining = u16::from_le_bytes([header[4], header[5]]) as usize;
+        elapsed
+    }
+}
+
+impl<R: ErrorType, D> ErrorType for Replay<R, D> {
+    type Error = R::Error;
+}
+
+impl<R: Read, D: FnMut(u64)> Read for Replay<R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.remaining == 0 {
+            if !self.next_chunk()? {
+                return Ok(0);
+            }
+        }
+        let len = buf.len().min(self.remaining);
+        let read = self.capture.read(&mut buf[..len])?;
+        self.remaining = if read == 0 { 0 } else { self.remaining - read };
+        Ok(read)
+    }
+}
+
+impl<R: AsyncRead, D: FnMut(u64) -> F, F: Future<Output = ()>> AsyncRead for Replay<R, D> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.remaining == 0 {
+            if !self.next_chunk_async().await? {
+                return Ok(0);
+            }
+        }
+        let len = buf.len().min(self.remaining);
+        let read = self.capture.read(&mut buf[..len]).await?;
+        self.remaining = if read == 0 { 0 } else { self.remaining - read };
+        Ok(read)
+    }
+}
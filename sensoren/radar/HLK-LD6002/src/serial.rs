@@ -0,0 +1,282 @@
+//! Opening serial ports on hosts, without writing the adapter glue
+//!
+//! [`MessageStream::open`] opens a port with the settings the sensor needs, [`MessageStream::open_auto`]
+//! finds the port the sensor is connected to by probing all serial ports for valid frames,
+//! which helps when the numbering of USB serial adapters changes between reboots.
+//!
+//! ```rust,no_run
+//! use hlk_ld6002::{Data, MessageStream};
+//!
+//! let messages = MessageStream::open_auto().expect("no sensor found");
+//! let mut data = Data::default();
+//! for message in messages.flatten() {
+//!     data.update(message);
+//! }
+//! ```
+//!
+//! With the `tokio` feature, `TokioMessageStream` does the same for tokio, timestamps the frames and
+//! reopens the port when reading keeps failing, e.g. after a USB adapter was unplugged and plugged back in.
+
+use crate::MessageStream;
+use embedded_io_adapters::std::FromStd;
+use serialport::{ClearBuffer, DataBits, Parity, SerialPort, StopBits};
+use std::boxed::Box;
+use std::io;
+use std::string::String;
+use std::time::{Duration, Instant};
+#[cfg(feature = "tokio")]
+use {
+    crate::stream::unfold,
+    crate::{AsyncMessageStream, Clock, LdError, MessageBody, Rates, Stats},
+    embedded_io_adapters::tokio_1::FromTokio,
+    futures_core::Stream,
+    tokio_serial::{SerialPortBuilderExt, SerialStream},
+};
+
+/// Baud rate the sensor uses out of the box
+///
+/// This isn't one of the standard rates, which some platforms only support through special ioctls.
+/// Opening through `tokio-serial` takes care of that on Linux, macOS and Windows, but not every USB
+/// serial adapter supports it, see the readme.
+pub const BAUD_RATE: u32 = 1_382_400;
+
+/// Read timeout of the ports, short enough for probing without waiting long on silent ports
+const TIMEOUT: Duration = Duration::from_millis(50);
+/// How long a port is given to produce valid frames while probing
+const PROBE_TIME: Duration = Duration::from_secs(1);
+/// Number of valid frames needed to recognize the sensor, a single one could be a coincidence
+const PROBE_FRAMES: u32 = 3;
+
+/// The reader of a serial port opened by [`MessageStream::open`]
+pub type SerialReader = FromStd<Box<dyn SerialPort>>;
+
+impl MessageStream<SerialReader> {
+    /// Open the port at `path`, e.g. `/dev/ttyUSB0` or `COM3`, with 8 data bits, no parity and 1 stop bit
+    ///
+    /// Data received before opening is dropped, as it likely starts halfway through a frame.
+    pub fn open(path: &str, baud: u32) -> Result<Self, serialport::Error> {
+        Ok(MessageStream::new(FromStd::new(open_port(path, baud)?)))
+    }
+
+    /// Open the first serial port a sensor is connected to, see [`detect`]
+    pub fn open_auto() -> Result<Self, serialport::Error> {
+        let path = detect()?.ok_or_else(|| {
+            serialport::Error::new(serialport::ErrorKind::NoDevice, "no sensor found")
+        })?;
+        Self::open(&path, BAUD_RATE)
+    }
+}
+
+fn open_port(path: &str, baud: u32) -> Result<Box<dyn SerialPort>, serialport::Error> {
+    let port = serialport::new(path, baud)
+        .data_bits(DataBits::Eight)
+        .parity(Parity::None)
+        .stop_bits(StopBits::One)
+        .timeout(TIMEOUT)
+        .open()?;
+    port.clear(ClearBuffer::All)?;
+    Ok(port)
+}
+
+/// Find the serial port a sensor is connected to
+///
+/// Probes every serial port at [`BAUD_RATE`] for up to a second, returning the first that produces valid frames.
+/// Ports that can't be opened, e.g. because they are in use, are skipped. Probing only reads from the ports,
+/// but other devices might still not like being opened at this baud rate, so prefer a fixed path
+/// (like `/dev/serial/by-id/...`) where possible.
+pub fn detect() -> Result<Option<String>, serialport::Error> {
+    Ok(serialport::available_ports()?
+        .into_iter()
+        .map(|port| port.port_name)
+        .find(|path| probe(path)))
+}
+
+/// Whether the sensor is connected to the port at `path`
+pub fn probe(path: &str) -> bool {
+    let Ok(port) = open_port(path, BAUD_RATE) else {
+        return false;
+    };
+    let deadline = Instant::now() + PROBE_TIME;
+    let mut frames = 0;
+    // a port streaming data that never forms a frame would otherwise keep the stream resyncing forever
+    let messages = MessageStream::new(FromStd::new(Deadline { port, deadline }));
+    for message in messages.with_resync(true) {
+        if message.is_ok() {
+            frames += 1;
+            if frames >= PROBE_FRAMES {
+                return true;
+            }
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+    }
+    false
+}
+
+/// A reader that ends once the deadline passed
+struct Deadline<R> {
+    port: R,
+    deadline: Instant,
+}
+
+impl<R: io::Read> io::Read for Deadline<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if Instant::now() >= self.deadline {
+            return Ok(0);
+        }
+        self.port.read(buf)
+    }
+}
+
+/// Number of consecutive read errors after which the port is reopened
+#[cfg(feature = "tokio")]
+const REOPEN_AFTER: u32 = 3;
+/// Longest wait between attempts to reopen the port
+#[cfg(feature = "tokio")]
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Milliseconds since the port was first opened
+#[cfg(feature = "tokio")]
+struct Elapsed(Instant);
+
+#[cfg(feature = "tokio")]
+impl Clock for Elapsed {
+    fn now_ms(&mut self) -> u64 {
+        self.0.elapsed().as_millis() as u64
+    }
+}
+
+/// An [`AsyncMessageStream`] on a tokio serial port that reopens the port after persistent errors
+///
+/// Read errors and the port closing are still returned, so they can be logged, but after
+/// a few of them in a row the port is closed and opened again. If reopening fails, the error is
+/// returned and the next read tries again, waiting longer between attempts up to 30 seconds.
+/// Invalid data is skipped by resynchronizing to the next frame.
+///
+/// ```rust,no_run
+/// use futures::StreamExt;
+/// use hlk_ld6002::serial::{TokioMessageStream, BAUD_RATE};
+///
+/// # async fn run() -> Result<(), tokio_serial::Error> {
+/// let messages = TokioMessageStream::open("/dev/ttyUSB0", BAUD_RATE)?;
+/// let mut messages = Box::pin(messages.into_stream());
+/// while let Some(message) = messages.next().await {
+///     println!("{message:?}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "tokio")]
+pub struct TokioMessageStream {
+    path: String,
+    baud: u32,
+    messages: Option<AsyncMessageStream<FromTokio<SerialStream>, Elapsed>>,
+    start: Instant,
+    failures: u32,
+    backoff: Duration,
+    reopens: u32,
+}
+
+#[cfg(feature = "tokio")]
+impl TokioMessageStream {
+    /// Open the port at `path`, e.g. `/dev/ttyUSB0` or `COM3`, with 8 data bits, no parity and 1 stop bit
+    ///
+    /// Fails if the port can't be opened initially, later failures are handled by reopening the port.
+    pub fn open(path: &str, baud: u32) -> Result<Self, tokio_serial::Error> {
+        let start = Instant::now();
+        let messages = Self::open_port(path, baud, start)?;
+        Ok(TokioMessageStream {
+            path: path.into(),
+            baud,
+            messages: Some(messages),
+            start,
+            failures: 0,
+            backoff: Duration::ZERO,
+            reopens: 0,
+        })
+    }
+
+    fn open_port(
+        path: &str,
+        baud: u32,
+        start: Instant,
+    ) -> Result<AsyncMessageStream<FromTokio<SerialStream>, Elapsed>, tokio_serial::Error> {
+        let port = tokio_serial::new(path, baud)
+            .data_bits(DataBits::Eight)
+            .parity(Parity::None)
+            .stop_bits(StopBits::One)
+            .open_native_async()?;
+        // drop whatever was received before opening, it likely starts halfway through a frame
+        port.clear(ClearBuffer::All)?;
+        Ok(AsyncMessageStream::with_clock(FromTokio::new(port), Elapsed(start)).with_resync(true))
+    }
+
+    /// Read the next message, reopening the port if needed
+    ///
+    /// This is cancel safe as long as the port doesn't need to be reopened, like [`AsyncMessageStream::next`].
+    pub async fn next(&mut self) -> Result<MessageBody, LdError<io::Error>> {
+        let messages = match self.messages.as_mut() {
+            Some(messages) => messages,
+            None => self.reopen().await?,
+        };
+        let message = messages.next().await;
+        match &message {
+            Err(LdError::Read(_) | LdError::Eof) => {
+                self.failures += 1;
+                if self.failures >= REOPEN_AFTER {
+                    self.messages = None;
+                }
+            }
+            _ => self.failures = 0,
+        }
+        message
+    }
+
+    async fn reopen(
+        &mut self,
+    ) -> Result<&mut AsyncMessageStream<FromTokio<SerialStream>, Elapsed>, LdError<io::Error>> {
+        tokio::time::sleep(self.backoff).await;
+        match Self::open_port(&self.path, self.baud, self.start) {
+            Ok(messages) => {
+                self.failures = 0;
+                self.backoff = Duration::ZERO;
+                self.reopens = self.reopens.wrapping_add(1);
+                Ok(self.messages.insert(messages))
+            }
+            Err(e) => {
+                self.backoff = (self.backoff * 2).clamp(Duration::from_secs(1), MAX_BACKOFF);
+                Err(LdError::Read(e.into()))
+            }
+        }
+    }
+
+    /// Turn the stream into a [`Stream`] of messages, which never ends
+    pub fn into_stream(self) -> impl Stream<Item = Result<MessageBody, LdError<io::Error>>> {
+        unfold(self, |mut stream: Self| async move {
+            let message = stream.next().await;
+            (message, stream)
+        })
+    }
+
+    /// How often the port was reopened
+    pub fn reopens(&self) -> u32 {
+        self.reopens
+    }
+
+    /// The reporting rates since the port was last (re)opened, see [`AsyncMessageStream::rates`]
+    pub fn rates(&mut self) -> Rates {
+        self.messages
+            .as_mut()
+            .map(|messages| messages.rates())
+            .unwrap_or_default()
+    }
+
+    /// The link statistics since the port was last (re)opened, see [`AsyncMessageStream::stats`]
+    pub fn stats(&self) -> Stats {
+        self.messages
+            .as_ref()
+            .map(|messages| messages.stats())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,141 @@
+//! Summarizing a night of readings
+//!
+//! [`SleepSession`] collects the readings of a night and summarizes them into the time in bed,
+//! the range of the rates and how restless the sleep was. The night is split into epochs of 30 seconds,
+//! as in actigraphy, an epoch in bed counts as movement when the distance changed more than a threshold.
+//!
+//! The session keeps every sample, so it needs the `alloc` feature and is meant for hosts.
+
+use crate::window::Aggregate;
+use crate::Data;
+use alloc::vec::Vec;
+
+/// An epoch of a [`SleepSession`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Epoch {
+    /// Start of the epoch in milliseconds
+    pub start: u64,
+    /// Whether a target was reported during the epoch
+    pub in_bed: bool,
+    /// Whether the target moved during the epoch
+    pub moving: bool,
+}
+
+/// Summary of a [`SleepSession`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SleepSummary {
+    /// Milliseconds of epochs with a target
+    pub time_in_bed: u64,
+    pub heartbeat: Option<Aggregate>,
+    pub respiratory: Option<Aggregate>,
+    /// Number of epochs in bed with movement
+    pub movement_epochs: usize,
+    /// Share of the epochs in bed with movement, between 0 and 1
+    pub restlessness: f32,
+}
+
+/// Collects the readings of a night
+///
+/// Samples should be recorded with fields set to 0 while they aren't reported, e.g. from
+/// [`TimedData::fresh`](crate::TimedData::fresh), as [`Data`] keeps the last distance after the
+/// subject left.
+#[derive(Debug, Clone)]
+pub struct SleepSession {
+    samples: Vec<(u64, Data)>,
+    epoch: u64,
+    movement: f32,
+}
+
+impl SleepSession {
+    /// Session with 30 second epochs, counting distance changes of 5 cm as movement
+    pub fn new() -> Self {
+        Self::with_epochs(30_000, 0.05)
+    }
+
+    /// Session with epochs of `epoch` milliseconds, counting distance changes over `movement` meters as movement
+    pub fn with_epochs(epoch: u64, movement: f32) -> Self {
+        SleepSession {
+            samples: Vec::new(),
+            epoch,
+            movement,
+        }
+    }
+
+    /// Add a snapshot of the readings taken at `now` milliseconds
+    ///
+    /// Snapshots need to be recorded in order.
+    pub fn record(&mut self, now: u64, data: Data) {
+        self.samples.push((now, data));
+    }
+
+    /// The epochs from the first to the last sample
+    pub fn epochs(&self) -> Vec<Epoch> {
+        let Some(&(first, _)) = self.samples.first() else {
+            return Vec::new();
+        };
+        self.samples
+            .chunk_by(|(a, _), (b, _)| (a - first) / self.epoch == (b - first) / self.epoch)
+            .map(|samples| {
+                let start = first + (samples[0].0 - first) / self.epoch * self.epoch;
+                let distances = samples.iter().map(|(_, data)| data.distance);
+                let (min, max) = distances.filter(|distance| *distance > 0.0).fold(
+                    (f32::INFINITY, f32::NEG_INFINITY),
+                    |(min, max), distance| (min.min(distance), max.max(distance)),
+                );
+                let in_bed = min <= max;
+                Epoch {
+                    start,
+                    in_bed,
+                    moving: in_bed && max - min > self.movement,
+                }
+            })
+            .collect()
+    }
+
+    /// Summarize the session, `None` if nothing was recorded
+    pub fn summary(&self) -> Option<SleepSummary> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let epochs = self.epochs();
+        let in_bed = epochs.iter().filter(|epoch| epoch.in_bed).count();
+        let movement_epochs = epochs.iter().filter(|epoch| epoch.moving).count();
+        Some(SleepSummary {
+            time_in_bed: in_bed as u64 * self.epoch,
+            heartbeat: self.aggregate(|data| data.heartbeat),
+            respiratory: self.aggregate(|data| data.respiratory),
+            movement_epochs,
+            restlessness: if in_bed > 0 {
+                movement_epochs as f32 / in_bed as f32
+            } else {
+                0.0
+            },
+        })
+    }
+
+    fn aggregate(&self, field: impl Fn(&Data) -> f32) -> Option<Aggregate> {
+        let (min, max, sum, count) = self
+            .samples
+            .iter()
+            .map(|(_, data)| field(data))
+            .filter(|value| *value > 0.0)
+            .fold(
+                (f32::INFINITY, f32::NEG_INFINITY, 0.0, 0),
+                |(min, max, sum, count), value| {
+                    (min.min(value), max.max(value), sum + value, count + 1)
+                },
+            );
+        (count > 0).then(|| Aggregate {
+            min,
+            max,
+            avg: sum / count as f32,
+            count,
+        })
+    }
+}
+
+impl Default for SleepSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,31 @@
+/// Counters describing the quality of the link to the sensor
+///
+/// A healthy link only increases `frames`, growing error counters point to bad wiring,
+/// a too long cable or a serial adapter that can't keep up with the baud rate.
+/// The counters wrap around on overflow, so differences between two snapshots stay correct.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    /// Frames received with valid checksums
+    pub frames: u32,
+    /// Frames rejected because of an invalid header or body checksum
+    pub checksum_failures: u32,
+    /// Frames with a type that isn't known to this crate, decoded as [`MessageBody::Unknown`](crate::MessageBody::Unknown)
+    pub unknown_types: u32,
+    /// Number of times the stream lost track of the frames and had to resynchronize to the next start of frame
+    ///
+    /// Counted once per loss of sync, no matter how many bytes were skipped until the next valid frame,
+    /// the skipped bytes are counted in `bytes_discarded`.
+    pub resyncs: u32,
+    /// Bytes thrown away because they weren't part of a valid frame
+    pub bytes_discarded: u32,
+    /// Frames with a valid header that were too long to be buffered, see [`LdError::FrameTooLong`](crate::LdError::FrameTooLong)
+    ///
+    /// With resync enabled these frames are skipped without returning an error, so a growing count is the only
+    /// sign that the sensor sends a message type this crate can't buffer yet.
+    pub oversized_frames: u32,
+    /// The longest payload of the oversized frames, the buffer capacity needed to receive them
+    pub longest_oversized: u16,
+    /// Latency histograms, only measured by streams with a [`Clock`](crate::Clock)
+    #[cfg(feature = "latency")]
+    pub latency: crate::latency::Latency,
+}
@@ -0,0 +1,84 @@
+//! [`Stream`] support for [`AsyncMessageStream`]
+//!
+//! Only `futures-core` is needed and nothing is allocated, so the streams can be used
+//! with stream combinators on microcontrollers as well, e.g. in embassy tasks.
+
+use crate::{AsyncMessageStream, Clock, LdError, MessageBody};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{ready, Context, Poll};
+use embedded_io_async::Read as AsyncRead;
+use futures_core::Stream;
+
+impl<R: AsyncRead, C: Clock> AsyncMessageStream<R, C> {
+    /// Turn the message stream into a [`Stream`] of messages
+    ///
+    /// Like the [`Iterator`] of [`MessageStream`](crate::MessageStream), the stream never ends,
+    /// errors (including [`LdError::Eof`]) are yielded as items. The returned stream isn't `Unpin`,
+    /// it needs to be pinned (e.g. with [`pin!`](core::pin::pin)) to use combinators that require it.
+    pub fn into_stream(self) -> impl Stream<Item = Result<MessageBody, LdError<R::Error>>> {
+        unfold(self, |mut stream: Self| async move {
+            let message = stream.next().await;
+            (message, stream)
+        })
+    }
+
+    /// A [`Stream`] of messages borrowing the message stream
+    ///
+    /// Unlike [`into_stream`](Self::into_stream), the message stream can be used again once the returned
+    /// stream is dropped, e.g. to read the [`rates`](Self::rates) or to keep reading after a `select`.
+    /// Since reading a message is cancel safe, dropping the stream halfway through a frame doesn't lose data.
+    pub fn stream<'a>(
+        &'a mut self,
+    ) -> impl Stream<Item = Result<MessageBody, LdError<R::Error>>> + 'a {
+        unfold(self, |stream: &'a mut Self| async move {
+            let message = stream.next().await;
+            (message, stream)
+        })
+    }
+}
+
+/// A stream calling `next` with the state to get the next item and the state back
+pub(crate) fn unfold<S, T, F, Fut>(stream: S, next: F) -> impl Stream<Item = T>
+where
+    F: FnMut(S) -> Fut,
+    Fut: Future<Output = (T, S)>,
+{
+    Unfold {
+        stream: Some(stream),
+        future: None,
+        next,
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// Moves the message stream into the future reading the next message and takes it back afterwards,
+    /// so the stream doesn't need to borrow from itself
+    struct Unfold<S, F, Fut> {
+        stream: Option<S>,
+        next: F,
+        #[pin]
+        future: Option<Fut>,
+    }
+}
+
+impl<S, T, F, Fut> Stream for Unfold<S, F, Fut>
+where
+    F: FnMut(S) -> Fut,
+    Fut: Future<Output = (T, S)>,
+{
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut this = self.project();
+        if let Some(stream) = this.stream.take() {
+            this.future.set(Some((this.next)(stream)));
+        }
+
+        let future = this.future.as_mut().as_pin_mut();
+        let (item, stream) = ready!(future.expect("stream is either idle or reading").poll(cx));
+        this.future.set(None);
+        *this.stream = Some(stream);
+        Poll::Ready(Some(item))
+    }
+}
@@ -0,0 +1,108 @@
+//! Detecting readings that stopped changing
+//!
+//! A known failure mode of the sensor is the heartbeat rate freezing at a constant value for minutes,
+//! while the other reports keep updating. A real heart rate always jitters a bit between reports,
+//! so a value that stays exactly the same for long enough can be flagged as stale.
+//!
+//! [`Restart`] optionally turns a stale value into a command for the sensor. No restart command
+//! is documented for the LD6002, so the command to send is up to the application, e.g. a
+//! [`Command::Raw`] for a firmware with a known restart frame, or [`Command::Reset`] for the HLK-LD6002C
+//! (which re-initializes its fall detection parameters).
+
+use crate::command::Command;
+
+/// Whether a value is still being updated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Liveness {
+    Live,
+    /// The value hasn't changed for longer than the configured duration
+    Stale,
+}
+
+/// Detects a value that is stuck at the same reading
+#[derive(Debug, Clone)]
+pub struct StuckDetector {
+    tolerance: f32,
+    max_duration: u64,
+    value: f32,
+    since: Option<u64>,
+}
+
+impl StuckDetector {
+    /// Flag values as stale once they stay within `tolerance` of each other for `max_duration` milliseconds
+    pub fn new(tolerance: f32, max_duration: u64) -> Self {
+        StuckDetector {
+            tolerance,
+            max_duration,
+            value: 0.0,
+            since: None,
+        }
+    }
+
+    /// Detector tuned for the frozen heartbeat failure: no change at all for 2 minutes
+    pub fn heartbeat() -> Self {
+        Self::new(0.0, 120_000)
+    }
+
+    /// Feed a new reading received at `now` milliseconds
+    pub fn update(&mut self, now: u64, value: f32) -> Liveness {
+        match self.since {
+            Some(_) if (value - self.value).abs() <= self.tolerance => {}
+            _ => {
+                self.value = value;
+                self.since = Some(now);
+            }
+        }
+        self.liveness(now)
+    }
+
+    /// Whether the value is stale as of `now` milliseconds
+    pub fn liveness(&self, now: u64) -> Liveness {
+        match self.since {
+            Some(since) if now.saturating_sub(since) > self.max_duration => Liveness::Stale,
+            _ => Liveness::Live,
+        }
+    }
+
+    /// Forget the current value, e.g. after restarting the sensor
+    pub fn reset(&mut self) {
+        self.since = None;
+    }
+}
+
+/// Decides when to send a command to the sensor for a stale value
+///
+/// While the value is stale, the command is returned at most once per `cooldown` milliseconds,
+/// so a sensor that doesn't recover isn't flooded with commands.
+/// Send it with [`write_command`](crate::command::write_command) and [`reset`](StuckDetector::reset)
+/// the detector afterwards.
+#[derive(Debug, Clone)]
+pub struct Restart<'a> {
+    command: Command<'a>,
+    cooldown: u64,
+    last: Option<u64>,
+}
+
+impl<'a> Restart<'a> {
+    /// Send `command` for stale values, at most once per `cooldown` milliseconds
+    pub fn new(command: Command<'a>, cooldown: u64) -> Self {
+        Restart {
+            command,
+            cooldown,
+            last: None,
+        }
+    }
+
+    /// The command to send as of `now` milliseconds, given the current liveness of the value
+    pub fn update(&mut self, now: u64, liveness: Liveness) -> Option<Command<'a>> {
+        let cooled_down = self
+            .last
+            .is_none_or(|last| now.saturating_sub(last) >= self.cooldown);
+        (liveness == Liveness::Stale && cooled_down).then(|| {
+            self.last = Some(now);
+            self.command
+        })
+    }
+}
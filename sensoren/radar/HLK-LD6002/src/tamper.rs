@@ -0,0 +1,80 @@
+//! Detecting a blocked or repositioned sensor
+//!
+//! An object placed directly on or in front of the sensor shows up as a target a few centimeters away
+//! that never leaves. A sensor that was turned towards a wall or covered with something reflective
+//! doesn't see any movement at all, so the phase becomes completely flat. Both are different from
+//! an empty room, where the sensor reports no target and the phase keeps picking up noise.
+
+use crate::stuck::{Liveness, StuckDetector};
+use crate::MessageBody;
+
+/// Whether the sensor appears to be tampered with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Tamper {
+    Normal,
+    /// A target stayed right in front of the sensor for longer than the configured duration
+    SensorBlocked,
+    /// The phase stopped changing, the sensor was likely moved or covered
+    SensorTampered,
+}
+
+/// Detects a blocked or repositioned sensor from the reported distance and phase
+#[derive(Debug, Clone)]
+pub struct TamperDetector {
+    near: f32,
+    max_duration: u64,
+    near_since: Option<u64>,
+    phase: StuckDetector,
+}
+
+impl Default for TamperDetector {
+    /// A target within 10 cm or a phase changing less than 0.001 for one minute
+    fn default() -> Self {
+        Self::new(0.1, 0.001, 60_000)
+    }
+}
+
+impl TamperDetector {
+    /// Flag the sensor as blocked once a target stays within `near` meters for `max_duration` milliseconds,
+    /// or as tampered with once the phase stays within `phase_tolerance` for the same duration
+    pub fn new(near: f32, phase_tolerance: f32, max_duration: u64) -> Self {
+        TamperDetector {
+            near,
+            max_duration,
+            near_since: None,
+            phase: StuckDetector::new(phase_tolerance, max_duration),
+        }
+    }
+
+    /// Feed a message received at `now` milliseconds
+    pub fn update(&mut self, now: u64, message: &MessageBody) -> Tamper {
+        match message {
+            MessageBody::Distance(Some(distance)) if *distance > 0.0 && *distance <= self.near => {
+                self.near_since.get_or_insert(now);
+            }
+            MessageBody::Distance(_) => self.near_since = None,
+            MessageBody::Phase(phase) => {
+                self.phase.update(now, phase[0]);
+            }
+            _ => {}
+        }
+        self.state(now)
+    }
+
+    /// Whether the sensor appears to be tampered with as of `now` milliseconds
+    pub fn state(&self, now: u64) -> Tamper {
+        match self.near_since {
+            Some(since) if now.saturating_sub(since) > self.max_duration => Tamper::SensorBlocked,
+            _ if self.phase.liveness(now) == Liveness::Stale => Tamper::SensorTampered,
+            _ => Tamper::Normal,
+        }
+    }
+
+    /// Forget the observed readings, e.g. after the sensor was remounted
+    pub fn reset(&mut self) {
+        self.near_since = None;
+        self.phase.reset();
+    }
+}
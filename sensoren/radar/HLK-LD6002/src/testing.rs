@@ -0,0 +1,311 @@
+//! Helpers for testing code that reads from the sensor
+//!
+//! [`FaultyTransport`] wraps any reader and injects the kind of misbehavior seen with real serial adapters,
+//! so the handling of these faults can be tested without flaky hardware.
+//! [`SimulatedSensor`] produces an endless stream of frames for given vital signs, for tests and demos
+//! without a sensor at all.
+
+use crate::encode::{encode, MAX_FRAME};
+use crate::MessageBody;
+use core::convert::Infallible;
+use core::f32::consts::PI;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use embedded_io::{ErrorKind, ErrorType, Read};
+use embedded_io_async::Read as AsyncRead;
+
+/// The faults injected by a [`FaultyTransport`]
+///
+/// All faults are driven by a seeded pseudo-random generator, so runs are reproducible.
+#[derive(Debug, Clone, Copy)]
+pub struct Faults {
+    /// Chance for every read to fail with an [`ErrorKind::Other`] error, between 0 and 1
+    pub error_rate: f32,
+    /// Maximum number of bytes returned by a single read, reads return a random amount up to this
+    pub max_chunk: usize,
+    /// Every n-th read fails with [`ErrorKind::TimedOut`], as std serial ports do when no data arrives in time
+    ///
+    /// `0` disables the timeouts.
+    pub timeout_every: u32,
+    /// Every n-th async read is delayed by returning `Pending` once before the data is returned
+    ///
+    /// `0` disables the delays.
+    pub delay_every: u32,
+    /// Seed for the pseudo-random generator
+    pub seed: u32,
+}
+
+impl Default for Faults {
+    fn default() -> Self {
+        Faults {
+            error_rate: 0.0,
+            max_chunk: usize::MAX,
+            timeout_every: 0,
+            delay_every: 0,
+            seed: 0x1d6002,
+        }
+    }
+}
+
+/// Error returned by a [`FaultyTransport`]
+#[derive(Debug)]
+pub enum FaultyError<E> {
+    /// An injected fault
+    Injected(ErrorKind),
+    /// An error from the wrapped reader
+    Inner(E),
+}
+
+impl<E: embedded_io::Error> embedded_io::Error for FaultyError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            FaultyError::Injected(kind) => *kind,
+            FaultyError::Inner(e) => e.kind(),
+        }
+    }
+}
+
+/// A reader that injects errors, short reads, timeouts and delays into a wrapped reader
+pub struct FaultyTransport<R> {
+    inner: R,
+    faults: Faults,
+    state: u32,
+    reads: u32,
+}
+
+impl<R> FaultyTransport<R> {
+    pub fn new(inner: R, faults: Faults) -> Self {
+        FaultyTransport {
+            inner,
+            faults,
+            state: faults.seed.max(1),
+            reads: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// xorshift32
+    fn random(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state
+    }
+
+    /// Decide on the fault for the next read, returning the number of bytes to read
+    fn next_read<E>(&mut self, len: usize) -> Result<usize, FaultyError<E>> {
+        self.reads = self.reads.wrapping_add(1);
+        if self.faults.timeout_every > 0 && self.reads.is_multiple_of(self.faults.timeout_every) {
+            return Err(FaultyError::Injected(ErrorKind::TimedOut));
+        }
+        if (self.random() as f32 / u32::MAX as f32) < self.faults.error_rate {
+            return Err(FaultyError::Injected(ErrorKind::Other));
+        }
+        let max = len.min(self.faults.max_chunk).max(1);
+        Ok(1 + self.random() as usize % max)
+    }
+
+    fn delayed(&self) -> bool {
+        self.faults.delay_every > 0 && self.reads.is_multiple_of(self.faults.delay_every)
+    }
+}
+
+impl<R: ErrorType> ErrorType for FaultyTransport<R> {
+    type Error = FaultyError<R::Error>;
+}
+
+impl<R: Read> Read for FaultyTransport<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let len = self.next_read(buf.len())?;
+        self.inner.read(&mut buf[..len]).map_err(FaultyError::Inner)
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for FaultyTransport<R> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let len = self.next_read(buf.len())?;
+        if self.delayed() {
+            YieldOnce(false).await;
+        }
+        self.inner
+            .read(&mut buf[..len])
+            .await
+            .map_err(FaultyError::Inner)
+    }
+}
+
+/// Future that returns `Pending` once before completing
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// The vital signs and signal quality simulated by a [`SimulatedSensor`]
+#[derive(Debug, Clone, Copy)]
+pub struct Vitals {
+    /// Heartbeat rate in beats per minute
+    pub heartbeat: f32,
+    /// Respiratory rate in breaths per minute
+    pub respiratory: f32,
+    /// Distance of the target in meters, `0` simulates an empty room
+    pub distance: f32,
+    /// Amplitude of the random noise added to all values, relative to the value
+    pub noise: f32,
+    /// Every n-th frame gets a corrupted payload, failing its checksum
+    ///
+    /// `0` disables the corruption.
+    pub corrupt_every: u32,
+    /// Seed for the pseudo-random generator
+    pub seed: u32,
+}
+
+impl Default for Vitals {
+    fn default() -> Self {
+        Vitals {
+            heartbeat: 70.0,
+            respiratory: 15.0,
+            distance: 0.8,
+            noise: 0.02,
+            corrupt_every: 0,
+            seed: 0x1d6002,
+        }
+    }
+}
+
+/// A reader producing the frames of a sensor observing a person with the given [`Vitals`]
+///
+/// The simulated sensor sends a phase report every 50 ms and the rates and distance once per second,
+/// the phase follows breathing and heartbeat as sines. The simulation runs on virtual time,
+/// advancing with every frame read, so it never blocks and can be read as fast as needed.
+pub struct SimulatedSensor {
+    vitals: Vitals,
+    frame: [u8; MAX_FRAME],
+    len: usize,
+    pos: usize,
+    frames: u32,
+    now: u64,
+    state: u32,
+}
+
+impl SimulatedSensor {
+    /// Interval between the phase reports in milliseconds
+    pub const PHASE_INTERVAL: u64 = 50;
+
+    pub fn new(vitals: Vitals) -> Self {
+        SimulatedSensor {
+            vitals,
+            frame: [0; MAX_FRAME],
+            len: 0,
+            pos: 0,
+            frames: 0,
+            now: 0,
+            state: vitals.seed.max(1),
+        }
+    }
+
+    /// Change the simulated vitals, taking effect from the next frame
+    pub fn set_vitals(&mut self, vitals: Vitals) {
+        self.vitals = vitals;
+    }
+
+    /// The virtual time of the last generated frame in milliseconds
+    pub fn now_ms(&self) -> u64 {
+        self.now
+    }
+
+    /// xorshift32
+    fn random(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state
+    }
+
+    /// `value` with relative noise applied
+    fn noisy(&mut self, value: f32) -> f32 {
+        let noise = self.random() as f32 / u32::MAX as f32 * 2.0 - 1.0;
+        value * (1.0 + noise * self.vitals.noise)
+    }
+
+    fn phase(&mut self) -> MessageBody {
+        let vitals = self.vitals;
+        if vitals.distance == 0.0 {
+            return MessageBody::Phase([0.0; 3]);
+        }
+        let t = self.now as f32 / 1000.0;
+        let breath = self.noisy(libm::sinf(2.0 * PI * vitals.respiratory / 60.0 * t));
+        let heart = self.noisy(0.1 * libm::sinf(2.0 * PI * vitals.heartbeat / 60.0 * t));
+        MessageBody::Phase([breath + heart, breath, heart])
+    }
+
+    fn next_frame(&mut self) {
+        // every second, the phase reports are followed by the rates and distance
+        let per_second = (1000 / Self::PHASE_INTERVAL) as u32;
+        let present = (self.vitals.distance != 0.0) as u32 as f32;
+        let message = match (self.frames % (per_second + 3)).checked_sub(per_second) {
+            None => {
+                self.now += Self::PHASE_INTERVAL;
+                self.phase()
+            }
+            Some(0) => MessageBody::Respiratory(self.noisy(self.vitals.respiratory * present)),
+            Some(1) => MessageBody::Heartbeat(self.noisy(self.vitals.heartbeat * present)),
+            Some(_) => MessageBody::Distance(Some(self.noisy(self.vitals.distance))),
+        };
+
+        self.frames = self.frames.wrapping_add(1);
+        self.len = encode(self.frames as u16, &message, &mut self.frame);
+        self.pos = 0;
+
+        let corrupt_every = self.vitals.corrupt_every;
+        if corrupt_every > 0 && self.frames.is_multiple_of(corrupt_every) {
+            let payload = 8 + self.random() as usize % (self.len - 9);
+            self.frame[payload] ^= 0xff;
+        }
+    }
+}
+
+impl ErrorType for SimulatedSensor {
+    type Error = Infallible;
+}
+
+impl Read for SimulatedSensor {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pos == self.len {
+            self.next_frame();
+        }
+        let len = buf.len().min(self.len - self.pos);
+        buf[..len].copy_from_slice(&self.frame[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+impl AsyncRead for SimulatedSensor {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Read::read(self, buf)
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{Data, DataEvent, Field, MessageBody};
+
+/// [`Data`] that remembers when each field was last updated
+///
+/// [`Data`] keeps the last reading forever, so the heartbeat of a person that left the room is still shown.
+/// This tracks the age of every field, so stale readings can be told apart from live ones.
+/// Timestamps are in milliseconds, e.g. from the same [`Clock`](crate::Clock) used for the stream.
+#[derive(Default, Debug, Copy, Clone)]
+pub struct TimedData {
+    data: Data,
+    respiratory_at: Option<u64>,
+    distance_at: Option<u64>,
+    heartbeat_at: Option<u64>,
+}
+
+impl TimedData {
+    /// Update the data with a message received at `now` milliseconds
+    ///
+    /// Like [`Data::apply`], zero readings are ignored and don't refresh the age of the field.
+    pub fn update(&mut self, now: u64, message: MessageBody) -> DataEvent {
+        let event = self.data.apply(message);
+        if let DataEvent::Updated(field) = event {
+            *self.updated_at(field) = Some(now);
+        }
+        event
+    }
+
+    fn updated_at(&mut self, field: Field) -> &mut Option<u64> {
+        match field {
+            Field::Respiratory => &mut self.respiratory_at,
+            Field::Distance => &mut self.distance_at,
+            Field::Heartbeat => &mut self.heartbeat_at,
+        }
+    }
+
+    /// The last readings, regardless of their age
+    pub fn data(&self) -> Data {
+        self.data
+    }
+
+    /// Milliseconds since `field` was last updated, `None` if it was never received
+    pub fn age(&self, field: Field, now: u64) -> Option<u64> {
+        let updated = match field {
+            Field::Respiratory => self.respiratory_at,
+            Field::Distance => self.distance_at,
+            Field::Heartbeat => self.heartbeat_at,
+        };
+        updated.map(|updated| now.saturating_sub(updated))
+    }
+
+    /// Whether `field` wasn't updated in the last `max_age` milliseconds, or never received
+    pub fn is_stale(&self, field: Field, now: u64, max_age: u64) -> bool {
+        self.age(field, now).is_none_or(|age| age > max_age)
+    }
+
+    /// The last readings with the fields older than `max_age` milliseconds and their confidence set to 0
+    pub fn fresh(&self, now: u64, max_age: u64) -> Data {
+        let fresh = |field: Field| {
+            if self.is_stale(field, now, max_age) {
+                0.0
+            } else {
+                field.get(&self.data)
+            }
+        };
+        let confidence = |field: Field, confidence: f32| {
+            if self.is_stale(field, now, max_age) {
+                0.0
+            } else {
+                confidence
+            }
+        };
+        Data {
+            respiratory: fresh(Field::Respiratory),
+            distance: fresh(Field::Distance),
+            heartbeat: fresh(Field::Heartbeat),
+            heartbeat_confidence: confidence(Field::Heartbeat, self.data.heartbeat_confidence),
+            respiratory_confidence: confidence(
+                Field::Respiratory,
+                self.data.respiratory_confidence,
+            ),
+        }
+    }
+}
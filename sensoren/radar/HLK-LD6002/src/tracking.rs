@@ -0,0 +1,184 @@
+//! Tracking the rates with a Kalman filter
+//!
+//! The rates reported by the sensor are noisy, and sometimes missing for a while. [`VitalsTracker`]
+//! treats every rate as a slowly drifting value and fuses the reported rates with a breathing rate
+//! estimated from the phase (see [`goertzel`](crate::goertzel)) in a scalar Kalman filter per rate.
+//! Besides a smoother estimate, this gives an uncertainty that grows while no reports arrive.
+//!
+//! The phase doesn't carry a usable heartbeat on its own, so only the respiratory rate uses it.
+
+use crate::goertzel::BreathRateEstimator;
+use crate::MessageBody;
+
+/// A tracked rate with its uncertainty
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// Rate per minute
+    pub rate: f32,
+    /// Standard deviation of the rate per minute, about 95 % of the time the true rate
+    /// is within two standard deviations
+    pub std_dev: f32,
+}
+
+/// Kalman filter for a single rate, modelled as a random walk
+#[derive(Debug, Clone)]
+pub struct Kalman {
+    process_noise: f32,
+    rate: f32,
+    variance: f32,
+    updated_at: Option<u64>,
+}
+
+impl Kalman {
+    /// Filter for a rate whose variance grows by `process_noise` per second without measurements
+    pub fn new(process_noise: f32) -> Self {
+        Kalman {
+            process_noise,
+            rate: 0.0,
+            variance: 0.0,
+            updated_at: None,
+        }
+    }
+
+    /// Add a `measurement` taken at `now` milliseconds with the given variance
+    pub fn update(&mut self, now: u64, measurement: f32, variance: f32) -> Estimate {
+        match self.updated_at {
+            Some(_) => {
+                let predicted = self.predicted_variance(now);
+                let gain = predicted / (predicted + variance);
+                self.rate += gain * (measurement - self.rate);
+                self.variance = (1.0 - gain) * predicted;
+            }
+            None => {
+                self.rate = measurement;
+                self.variance = variance;
+            }
+        }
+        self.updated_at = Some(now);
+        Estimate {
+            rate: self.rate,
+            std_dev: libm::sqrtf(self.variance),
+        }
+    }
+
+    /// The estimate as of `now` milliseconds, `None` before the first measurement
+    pub fn estimate(&self, now: u64) -> Option<Estimate> {
+        self.updated_at?;
+        Some(Estimate {
+            rate: self.rate,
+            std_dev: libm::sqrtf(self.predicted_variance(now)),
+        })
+    }
+
+    fn predicted_variance(&self, now: u64) -> f32 {
+        let elapsed = self
+            .updated_at
+            .map_or(0, |updated| now.saturating_sub(updated));
+        self.variance + self.process_noise * elapsed as f32 / 1000.0
+    }
+
+    pub fn reset(&mut self) {
+        self.updated_at = None;
+    }
+}
+
+/// Noise of the model and the measurements used by a [`VitalsTracker`], as variances in (1/min)²
+#[derive(Debug, Clone, Copy)]
+pub struct TrackingConfig {
+    /// Growth of the respiratory rate variance per second
+    pub respiratory_drift: f32,
+    /// Growth of the heartbeat rate variance per second
+    pub heartbeat_drift: f32,
+    /// Variance of the reported respiratory rates
+    pub respiratory_noise: f32,
+    /// Variance of the reported heartbeat rates
+    pub heartbeat_noise: f32,
+    /// Variance of the respiratory rate estimated from the phase
+    pub phase_noise: f32,
+}
+
+impl Default for TrackingConfig {
+    fn default() -> Self {
+        TrackingConfig {
+            respiratory_drift: 0.05,
+            heartbeat_drift: 0.5,
+            respiratory_noise: 4.0,
+            heartbeat_noise: 16.0,
+            phase_noise: 2.0,
+        }
+    }
+}
+
+/// Tracks the respiratory and heartbeat rates from the reports of the sensor
+///
+/// `N` is the number of phase samples used for estimating the breathing rate, see [`BreathRateEstimator`].
+#[derive(Debug, Clone)]
+pub struct VitalsTracker<const N: usize> {
+    config: TrackingConfig,
+    respiratory: Kalman,
+    heartbeat: Kalman,
+    breath: BreathRateEstimator<N>,
+    /// Phase samples between two breathing rate estimates
+    estimate_every: u32,
+    phase_samples: u32,
+}
+
+impl<const N: usize> VitalsTracker<N> {
+    /// Tracker for phase reports arriving at `sample_rate` Hz
+    pub fn new(sample_rate: f32, config: TrackingConfig) -> Self {
+        VitalsTracker {
+            config,
+            respiratory: Kalman::new(config.respiratory_drift),
+            heartbeat: Kalman::new(config.heartbeat_drift),
+            breath: BreathRateEstimator::new(sample_rate),
+            // estimating is expensive, once per second is plenty for a breathing rate
+            estimate_every: (sample_rate as u32).max(1),
+            phase_samples: 0,
+        }
+    }
+
+    /// Add a message received at `now` milliseconds
+    ///
+    /// Zero rates, reported when there is no target, are ignored.
+    pub fn update(&mut self, now: u64, message: &MessageBody) {
+        match *message {
+            MessageBody::Respiratory(rate) if rate > 0.0 => {
+                self.respiratory
+                    .update(now, rate, self.config.respiratory_noise);
+            }
+            MessageBody::Heartbeat(rate) if rate > 0.0 => {
+                self.heartbeat
+                    .update(now, rate, self.config.heartbeat_noise);
+            }
+            MessageBody::Phase(_) => {
+                self.breath.update(message);
+                self.phase_samples += 1;
+                if self.phase_samples >= self.estimate_every {
+                    self.phase_samples = 0;
+                    if let Some(rate) = self.breath.estimate() {
+                        self.respiratory.update(now, rate, self.config.phase_noise);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The respiratory rate as of `now` milliseconds
+    pub fn respiratory(&self, now: u64) -> Option<Estimate> {
+        self.respiratory.estimate(now)
+    }
+
+    /// The heartbeat rate as of `now` milliseconds
+    pub fn heartbeat(&self, now: u64) -> Option<Estimate> {
+        self.heartbeat.estimate(now)
+    }
+
+    /// Forget the tracked rates, e.g. when the subject changed
+    pub fn reset(&mut self) {
+        self.respiratory.reset();
+        self.heartbeat.reset();
+        self.breath.reset();
+        self.phase_samples = 0;
+    }
+}
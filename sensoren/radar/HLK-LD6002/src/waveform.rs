@@ -0,0 +1,74 @@
+//! Breathing and heartbeat waveforms for charting
+//!
+//! [`Waveforms`] splits the phase reports with a [`PhaseFilter`] and keeps the last `N` samples of
+//! each band in a [`Window`], so applications can draw the movement of the chest in real time
+//! instead of only showing the rates.
+
+use crate::bandpass::{FilteredPhase, PhaseFilter};
+use crate::window::Window;
+use crate::MessageBody;
+
+/// The last `N` samples of the breathing and heartbeat waveforms
+#[derive(Debug, Clone)]
+pub struct Waveforms<const N: usize> {
+    filter: PhaseFilter,
+    breathing: Window<N>,
+    heart: Window<N>,
+}
+
+impl<const N: usize> Waveforms<N> {
+    /// Waveforms from phase reports arriving at `sample_rate` Hz
+    pub fn new(sample_rate: f32) -> Self {
+        Self::with_filter(PhaseFilter::new(sample_rate))
+    }
+
+    /// Waveforms using a custom filter, e.g. with other bands
+    pub fn with_filter(filter: PhaseFilter) -> Self {
+        Waveforms {
+            filter,
+            breathing: Window::default(),
+            heart: Window::default(),
+        }
+    }
+
+    /// Add a message received at `now` milliseconds, returns the new samples for phase reports
+    pub fn update(&mut self, now: u64, message: &MessageBody) -> Option<FilteredPhase> {
+        let filtered = self.filter.update(message)?;
+        self.breathing.push(now, filtered.breathing);
+        self.heart.push(now, filtered.heart);
+        Some(filtered)
+    }
+
+    /// The breathing waveform, as timestamped samples
+    pub fn breathing(&self) -> &Window<N> {
+        &self.breathing
+    }
+
+    /// The heartbeat waveform, as timestamped samples
+    pub fn heart(&self) -> &Window<N> {
+        &self.heart
+    }
+
+    /// Peak to peak amplitude of the breathing in the last `span` milliseconds before `now`
+    pub fn breathing_amplitude(&self, now: u64, span: u64) -> Option<f32> {
+        amplitude(&self.breathing, now, span)
+    }
+
+    /// Peak to peak amplitude of the heartbeat in the last `span` milliseconds before `now`
+    pub fn heart_amplitude(&self, now: u64, span: u64) -> Option<f32> {
+        amplitude(&self.heart, now, span)
+    }
+
+    /// Forget the waveforms and filter state, e.g. after a gap in the phase reports
+    pub fn reset(&mut self) {
+        self.filter.reset();
+        self.breathing = Window::default();
+        self.heart = Window::default();
+    }
+}
+
+fn amplitude<const N: usize>(window: &Window<N>, now: u64, span: u64) -> Option<f32> {
+    window
+        .aggregate(now, span)
+        .map(|aggregate| aggregate.max - aggregate.min)
+}
@@ -0,0 +1,114 @@
+//! Aggregates over the recent history of readings
+//!
+//! Keeps the last `N` timestamped values per field, allowing queries like
+//! "the average respiratory rate over the last 10 minutes".
+
+use crate::Data;
+pub use crate::Field;
+
+/// Summary of the values in a time range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aggregate {
+    pub min: f32,
+    pub max: f32,
+    pub avg: f32,
+    pub count: usize,
+}
+
+/// Ring buffer of the last `N` timestamped values
+///
+/// `N` needs to be at least 1, creating a `Window<0>` fails to compile.
+#[derive(Debug, Clone)]
+pub struct Window<const N: usize> {
+    samples: [(u64, f32); N],
+    len: usize,
+    next: usize,
+}
+
+impl<const N: usize> Default for Window<N> {
+    fn default() -> Self {
+        const { assert!(N > 0, "window needs to hold at least one value") };
+        Window {
+            samples: [(0, 0.0); N],
+            len: 0,
+            next: 0,
+        }
+    }
+}
+
+impl<const N: usize> Window<N> {
+    /// Add a value received at `now` milliseconds, overwriting the oldest value when full
+    pub fn push(&mut self, now: u64, value: f32) {
+        self.samples[self.next] = (now, value);
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// The values from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = (u64, f32)> + '_ {
+        let start = (self.next + N - self.len) % N;
+        (0..self.len).map(move |i| self.samples[(start + i) % N])
+    }
+
+    /// Aggregate the values received in the last `span` milliseconds before `now`
+    ///
+    /// Returns `None` if there are no values in the range.
+    pub fn aggregate(&self, now: u64, span: u64) -> Option<Aggregate> {
+        let since = now.saturating_sub(span);
+        let (min, max, sum, count) = self.iter().filter(|(time, _)| *time >= since).fold(
+            (f32::INFINITY, f32::NEG_INFINITY, 0.0, 0),
+            |(min, max, sum, count), (_, value)| {
+                (min.min(value), max.max(value), sum + value, count + 1)
+            },
+        );
+        (count > 0).then(|| Aggregate {
+            min,
+            max,
+            avg: sum / count as f32,
+            count,
+        })
+    }
+}
+
+/// Windows for every field of [`Data`]
+#[derive(Debug, Clone, Default)]
+pub struct DataWindow<const N: usize> {
+    respiratory: Window<N>,
+    distance: Window<N>,
+    heartbeat: Window<N>,
+}
+
+impl<const N: usize> DataWindow<N> {
+    /// Record a snapshot of the data taken at `now` milliseconds
+    ///
+    /// Fields without a reading (`0.0`) are skipped.
+    pub fn record(&mut self, now: u64, data: &Data) {
+        for field in [Field::Respiratory, Field::Distance, Field::Heartbeat] {
+            let value = field.get(data);
+            if value > 0.0 {
+                self.window_mut(field).push(now, value);
+            }
+        }
+    }
+
+    pub fn window(&self, field: Field) -> &Window<N> {
+        match field {
+            Field::Respiratory => &self.respiratory,
+            Field::Distance => &self.distance,
+            Field::Heartbeat => &self.heartbeat,
+        }
+    }
+
+    fn window_mut(&mut self, field: Field) -> &mut Window<N> {
+        match field {
+            Field::Respiratory => &mut self.respiratory,
+            Field::Distance => &mut self.distance,
+            Field::Heartbeat => &mut self.heartbeat,
+        }
+    }
+
+    /// Aggregate a field over the last `span` milliseconds before `now`
+    pub fn aggregate(&self, field: Field, now: u64, span: u64) -> Option<Aggregate> {
+        self.window(field).aggregate(now, span)
+    }
+}
@@ -0,0 +1,87 @@
+//! Detection of breathing pauses in the simulated phase
+
+#![cfg(feature = "detectors")]
+
+use hlk_ld6002::apnea::{ApneaConfig, ApneaDetector, BreathingEvent};
+use hlk_ld6002::testing::{SimulatedSensor, Vitals};
+use hlk_ld6002::{MessageBody, MessageStream};
+
+/// Run the simulation for `seconds`, returning the events with the time they were raised at
+fn run(
+    sensor: &mut SimulatedSensor,
+    detector: &mut ApneaDetector<100>,
+    seconds: u64,
+) -> Vec<(u64, BreathingEvent)> {
+    let mut now = sensor.now_ms();
+    let end = now + seconds * 1000;
+    let mut events = Vec::new();
+    let mut stream = MessageStream::new(sensor);
+    while now < end {
+        let message = stream.next().unwrap().unwrap();
+        if let MessageBody::Phase(_) = message {
+            now += SimulatedSensor::PHASE_INTERVAL;
+        }
+        events.extend(detector.update(now, &message).map(|event| (now, event)));
+    }
+    events
+}
+
+#[test]
+fn breathing_pause() {
+    let vitals = Vitals::default();
+    let mut sensor = SimulatedSensor::new(vitals);
+    let mut detector = ApneaDetector::new(ApneaConfig {
+        min_amplitude: 0.5,
+        ..ApneaConfig::default()
+    });
+
+    assert_eq!(run(&mut sensor, &mut detector, 30), []);
+
+    sensor.set_vitals(Vitals {
+        respiratory: 0.0,
+        ..vitals
+    });
+    let events = run(&mut sensor, &mut detector, 20);
+    assert_eq!(events.len(), 1);
+    let (at, event) = events[0];
+    assert_eq!(event, BreathingEvent::BreathingStopped);
+    assert!((39_000..=42_000).contains(&at), "{at}");
+    assert!(detector.is_stopped());
+
+    sensor.set_vitals(vitals);
+    let events = run(&mut sensor, &mut detector, 10);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].1, BreathingEvent::BreathingResumed);
+}
+
+#[test]
+fn irregular_rate() {
+    let mut detector = ApneaDetector::<100>::default();
+    let mut events = Vec::new();
+    for (i, rate) in [15.0, 16.0, 15.0, 14.0, 25.0, 8.0, 9.0]
+        .into_iter()
+        .enumerate()
+    {
+        events.extend(detector.update(i as u64 * 1000, &MessageBody::Respiratory(rate)));
+    }
+    assert_eq!(events, [BreathingEvent::IrregularBreathing]);
+    assert!(detector.is_irregular());
+}
+
+#[test]
+fn clock_going_backwards() {
+    let mut detector = ApneaDetector::<20>::default();
+    let phase = |value: f32| MessageBody::Phase([value, 0.0, 0.0]);
+    for now in (0..30_000).step_by(250) {
+        detector.update(now, &phase((now as f32 / 1_000.0).sin()));
+    }
+    // flat long enough for the start of the pause to be known
+    for now in (30_000..=36_000).step_by(250) {
+        assert_eq!(detector.update(now, &phase(0.0)), None);
+    }
+    // the clock steps back to before the pause started
+    for now in (10_000..12_000).step_by(250) {
+        assert_eq!(detector.update(now, &phase(0.0)), None);
+    }
+    assert!(!detector.is_stopped());
+}
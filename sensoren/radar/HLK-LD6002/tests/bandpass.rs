@@ -0,0 +1,28 @@
+//! Separation of the breathing and heartbeat bands
+
+#![cfg(feature = "filters")]
+
+use core::f32::consts::PI;
+use hlk_ld6002::bandpass::Biquad;
+
+/// Peak output amplitude for a sine of `frequency` Hz, after the filter settled
+fn response(filter: &mut Biquad, sample_rate: f32, frequency: f32) -> f32 {
+    (0..(sample_rate * 120.0) as usize)
+        .map(|i| {
+            let t = i as f32 / sample_rate;
+            // with an offset, like the drifting phase
+            filter.update(5.0 + (2.0 * PI * frequency * t).sin())
+        })
+        .skip((sample_rate * 60.0) as usize)
+        .fold(0.0f32, |max, y| max.max(y.abs()))
+}
+
+#[test]
+fn band_pass() {
+    let sample_rate = 20.0;
+    let filter = Biquad::band_pass(sample_rate, 0.1, 0.5);
+
+    assert!((response(&mut filter.clone(), sample_rate, 0.22) - 1.0).abs() < 0.05);
+    assert!(response(&mut filter.clone(), sample_rate, 1.5) < 0.3);
+    assert!(response(&mut filter.clone(), sample_rate, 0.01) < 0.3);
+}
@@ -0,0 +1,99 @@
+//! Cancel safety of the async message stream
+
+mod common;
+
+use common::{HEARTBEAT_72, PHASE, RESPIRATORY_15_5};
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use hlk_ld6002::testing::{Faults, FaultyTransport};
+use hlk_ld6002::{AsyncMessageStream, MessageBody, NextOr};
+
+#[test]
+fn cancelled_next_keeps_partial_frame() {
+    let bytes = [HEARTBEAT_72, RESPIRATORY_15_5, PHASE].concat();
+    let transport = FaultyTransport::new(
+        bytes.as_slice(),
+        Faults {
+            max_chunk: 3,
+            delay_every: 2,
+            ..Faults::default()
+        },
+    );
+    let mut stream = AsyncMessageStream::new(transport);
+
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut messages = Vec::new();
+    let mut cancelled = 0;
+    while messages.len() < 3 {
+        // poll every future only once, dropping it when it isn't ready,
+        // like a `select!` that picked another branch
+        let mut next = pin!(stream.next());
+        match next.as_mut().poll(&mut cx) {
+            Poll::Ready(message) => messages.push(message.unwrap()),
+            Poll::Pending => cancelled += 1,
+        }
+    }
+
+    assert!(cancelled > 0);
+    assert_eq!(
+        messages,
+        [
+            MessageBody::Heartbeat(72.0),
+            MessageBody::Respiratory(15.5),
+            MessageBody::Phase([1.0, -0.5, 0.25]),
+        ]
+    );
+}
+
+#[test]
+fn ready_shutdown_wins() {
+    let mut stream = AsyncMessageStream::new(HEARTBEAT_72);
+
+    let mut cx = Context::from_waker(Waker::noop());
+    assert!(matches!(
+        pin!(stream.next_or(core::future::ready(()))).poll(&mut cx),
+        Poll::Ready(NextOr::Shutdown(()))
+    ));
+
+    assert!(matches!(
+        pin!(stream.next()).poll(&mut cx),
+        Poll::Ready(Ok(MessageBody::Heartbeat(72.0)))
+    ));
+}
+
+#[test]
+fn shutdown_keeps_partial_frame() {
+    let transport = FaultyTransport::new(
+        HEARTBEAT_72,
+        Faults {
+            max_chunk: 3,
+            delay_every: 2,
+            ..Faults::default()
+        },
+    );
+    let mut stream = AsyncMessageStream::new(transport);
+
+    // a shutdown arriving on the second poll, after the first bytes of the frame were read
+    let mut polled = false;
+    let shutdown = core::future::poll_fn(|_| match core::mem::replace(&mut polled, true) {
+        false => Poll::Pending,
+        true => Poll::Ready(()),
+    });
+    let mut cx = Context::from_waker(Waker::noop());
+    {
+        let mut next = pin!(stream.next_or(shutdown));
+        assert!(next.as_mut().poll(&mut cx).is_pending());
+        assert!(matches!(
+            next.as_mut().poll(&mut cx),
+            Poll::Ready(NextOr::Shutdown(()))
+        ));
+    }
+
+    let message = loop {
+        if let Poll::Ready(message) = pin!(stream.next()).poll(&mut cx) {
+            break message;
+        }
+    };
+    assert_eq!(message.unwrap(), MessageBody::Heartbeat(72.0));
+}
@@ -0,0 +1,70 @@
+//! Conversion of readings to and from their compact representation
+
+use hlk_ld6002::{CompactData, Data};
+
+fn data(respiratory: f32, heartbeat: f32, distance: f32) -> Data {
+    Data {
+        respiratory,
+        heartbeat,
+        distance,
+        ..Data::default()
+    }
+}
+
+#[test]
+fn round_trip() {
+    let compact = CompactData::from(data(15.4, 71.6, 1.234));
+    assert_eq!(
+        compact,
+        CompactData {
+            respiratory: 15,
+            heartbeat: 72,
+            distance: 123,
+            flags: CompactData::RESPIRATORY | CompactData::HEARTBEAT | CompactData::DISTANCE,
+        }
+    );
+    assert_eq!(CompactData::from_bytes(compact.to_bytes()), compact);
+
+    let data = Data::from(compact);
+    assert_eq!(
+        [data.respiratory, data.heartbeat, data.distance],
+        [15.0, 72.0, 1.23]
+    );
+}
+
+#[test]
+fn bytes() {
+    let compact = CompactData::from(data(12.0, 60.0, 3.0));
+    assert_eq!(compact.to_bytes(), [12, 60, 0x2c, 0x01, 0b111]);
+}
+
+#[test]
+fn missing_readings() {
+    let compact = CompactData::from(data(0.0, 64.0, 0.0));
+    assert_eq!(compact.flags, CompactData::HEARTBEAT);
+    let data = Data::from(compact);
+    assert_eq!(
+        [data.respiratory, data.heartbeat, data.distance],
+        [0.0, 64.0, 0.0]
+    );
+
+    // values without their flag don't count as readings
+    let compact = CompactData {
+        respiratory: 14,
+        heartbeat: 64,
+        distance: 150,
+        flags: CompactData::DISTANCE,
+    };
+    let data = Data::from(compact);
+    assert_eq!(
+        [data.respiratory, data.heartbeat, data.distance],
+        [0.0, 0.0, 1.5]
+    );
+}
+
+#[test]
+fn out_of_range_values_saturate() {
+    let compact = CompactData::from(data(300.0, 0.0, 1_000.0));
+    assert_eq!(compact.respiratory, u8::MAX);
+    assert_eq!(compact.distance, u16::MAX);
+}
@@ -0,0 +1,36 @@
+//! Confidence of the readings while the subject is still or moving
+
+#![cfg(feature = "filters")]
+
+use hlk_ld6002::confidence::ConfidenceEstimator;
+use hlk_ld6002::testing::{SimulatedSensor, Vitals};
+use hlk_ld6002::{Data, MessageBody, MessageStream};
+
+#[test]
+fn still_subject() {
+    let mut stream = MessageStream::new(SimulatedSensor::new(Vitals::default()));
+    let mut confidence = ConfidenceEstimator::default();
+    let mut data = Data::default();
+    for _ in 0..500 {
+        confidence.apply(stream.next().unwrap().unwrap(), &mut data);
+    }
+    assert!(data.heartbeat_confidence > 0.5, "{data:?}");
+    assert!(data.respiratory_confidence > 0.5, "{data:?}");
+}
+
+#[test]
+fn moving_subject() {
+    let mut confidence = ConfidenceEstimator::default();
+    let mut data = Data::default();
+    confidence.apply(MessageBody::Heartbeat(70.0), &mut data);
+    assert_eq!(data.heartbeat_confidence, 1.0);
+
+    for i in 0..100 {
+        let distance = 1.0 + (i % 2) as f32 * 0.3;
+        confidence.apply(MessageBody::Distance(Some(distance)), &mut data);
+        confidence.apply(MessageBody::Phase([(i % 2) as f32 * 5.0; 3]), &mut data);
+    }
+    confidence.apply(MessageBody::Heartbeat(95.0), &mut data);
+    assert_eq!(data.heartbeat, 95.0);
+    assert!(data.heartbeat_confidence < 0.1, "{data:?}");
+}
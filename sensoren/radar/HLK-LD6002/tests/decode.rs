@@ -0,0 +1,253 @@
+//! Decoding of known frames
+
+mod common;
+
+use common::{
+    DISTANCE_0_85, DISTANCE_NO_TARGET, DISTANCE_SHORT, FALL_REPORT, HEARTBEAT_72, PHASE,
+    RESPIRATORY_15_5, SET_FALL_THRESHOLD_0_6, SET_HEIGHT_2_5, SET_HEIGHT_ACK,
+};
+use hlk_ld6002::encode::encode_frame;
+use hlk_ld6002::{LdError, MessageBody, MessageStream, Quirks};
+
+fn decode(bytes: &[u8], quirks: Quirks) -> Result<MessageBody, LdError<core::convert::Infallible>> {
+    MessageStream::new(bytes)
+        .with_quirks(quirks)
+        .next()
+        .expect("no message")
+}
+
+#[test]
+fn heartbeat() {
+    assert_eq!(
+        decode(HEARTBEAT_72, Quirks::NONE).unwrap(),
+        MessageBody::Heartbeat(72.0)
+    );
+}
+
+#[test]
+fn respiratory() {
+    assert_eq!(
+        decode(RESPIRATORY_15_5, Quirks::NONE).unwrap(),
+        MessageBody::Respiratory(15.5)
+    );
+}
+
+#[test]
+fn distance() {
+    assert_eq!(
+        decode(DISTANCE_0_85, Quirks::NONE).unwrap(),
+        MessageBody::Distance(Some(0.85))
+    );
+    assert_eq!(
+        decode(DISTANCE_NO_TARGET, Quirks::NONE).unwrap(),
+        MessageBody::Distance(Some(0.0))
+    );
+}
+
+#[test]
+fn short_distance() {
+    assert_eq!(
+        decode(DISTANCE_SHORT, Quirks::default()).unwrap(),
+        MessageBody::Distance(None)
+    );
+    assert!(matches!(
+        decode(DISTANCE_SHORT, Quirks::NONE),
+        Err(LdError::InvalidDataLength {
+            expected: 8,
+            got: 4,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn phase() {
+    assert_eq!(
+        decode(PHASE, Quirks::NONE).unwrap(),
+        MessageBody::Phase([1.0, -0.5, 0.25])
+    );
+}
+
+#[test]
+fn consecutive_frames() {
+    let bytes = [HEARTBEAT_72, RESPIRATORY_15_5, DISTANCE_0_85, PHASE].concat();
+    let messages: Vec<_> = MessageStream::new(bytes.as_slice())
+        .take(4)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            MessageBody::Heartbeat(72.0),
+            MessageBody::Respiratory(15.5),
+            MessageBody::Distance(Some(0.85)),
+            MessageBody::Phase([1.0, -0.5, 0.25]),
+        ]
+    );
+}
+
+#[test]
+fn invalid_body_checksum() {
+    let mut bytes = HEARTBEAT_72.to_vec();
+    bytes[12] ^= 0xff;
+    assert!(matches!(
+        decode(&bytes, Quirks::default()),
+        Err(LdError::InvalidChecksum { ty: "body", .. })
+    ));
+}
+
+#[test]
+fn invalid_header_checksum() {
+    let mut bytes = HEARTBEAT_72.to_vec();
+    bytes[7] ^= 0xff;
+    assert!(decode(&bytes, Quirks::default()).is_ok());
+    assert!(matches!(
+        decode(&bytes, Quirks::NONE),
+        Err(LdError::InvalidChecksum { ty: "header", .. })
+    ));
+}
+
+#[test]
+fn unknown_type() {
+    let MessageBody::Unknown { ty, data } = decode(FALL_REPORT, Quirks::default()).unwrap() else {
+        panic!("unknown type decoded as known message");
+    };
+    assert_eq!(ty, 0x0e02);
+    assert_eq!(data.as_slice(), [1]);
+}
+
+#[test]
+fn documented_frames() {
+    let bytes = [
+        FALL_REPORT,
+        SET_HEIGHT_2_5,
+        SET_HEIGHT_ACK,
+        SET_FALL_THRESHOLD_0_6,
+    ]
+    .concat();
+    let mut messages = MessageStream::new(bytes.as_slice());
+    let frames: Vec<_> = (0..4)
+        .map(|_| {
+            let (frame, _) = messages.next_raw().unwrap();
+            (frame.id(), frame.ty(), frame.payload().to_vec())
+        })
+        .collect();
+    assert_eq!(
+        frames,
+        [
+            (0, 0x0e02, vec![1]),
+            (0, 0x0e04, 2.5f32.to_le_bytes().to_vec()),
+            (0, 0x0e04, vec![1]),
+            (0, 0x0e08, 0.6f32.to_le_bytes().to_vec()),
+        ]
+    );
+    assert_eq!(messages.stats().checksum_failures, 0);
+}
+
+#[test]
+fn unknown_type_too_long() {
+    let mut bytes = HEARTBEAT_72.to_vec();
+    bytes[6] = 0x99;
+    bytes[4] = 0x20;
+    assert!(matches!(
+        decode(&bytes, Quirks::default()),
+        Err(LdError::FrameTooLong {
+            ty: 0x0a99,
+            length: 0x20
+        })
+    ));
+}
+
+#[test]
+fn invalid_start() {
+    assert!(matches!(
+        decode(&HEARTBEAT_72[1..], Quirks::default()),
+        Err(LdError::InvalidFrameStart(0x00))
+    ));
+}
+
+#[test]
+fn resync_after_lost_byte() {
+    // lose a byte in the middle of the first frame
+    let mut bytes = [HEARTBEAT_72, RESPIRATORY_15_5, PHASE].concat();
+    bytes.remove(9);
+
+    let messages: Vec<_> = MessageStream::new(bytes.as_slice())
+        .with_resync(true)
+        .map_while(|message| match message {
+            Err(LdError::Eof) => None,
+            message => Some(message),
+        })
+        .filter_map(Result::ok)
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            MessageBody::Respiratory(15.5),
+            MessageBody::Phase([1.0, -0.5, 0.25]),
+        ]
+    );
+}
+
+#[test]
+fn stats() {
+    let mut corrupted = HEARTBEAT_72.to_vec();
+    corrupted[12] ^= 0xff;
+    let mut unknown = HEARTBEAT_72.to_vec();
+    unknown[6] = 0x99;
+    let bytes = [&corrupted, RESPIRATORY_15_5, &unknown].concat();
+
+    let mut messages = MessageStream::new(bytes.as_slice());
+    while !matches!(messages.next(), Some(Err(LdError::Eof))) {}
+
+    let stats = messages.stats();
+    assert_eq!(stats.frames, 2);
+    assert_eq!(stats.checksum_failures, 1);
+    assert_eq!(stats.unknown_types, 1);
+    assert_eq!(stats.resyncs, 0);
+    assert_eq!(stats.bytes_discarded, HEARTBEAT_72.len() as u32);
+}
+
+#[test]
+fn resyncs_counted_once() {
+    // garbage before two frames and between them
+    let garbage = [0x55; 10];
+    let bytes = [&garbage, HEARTBEAT_72, &garbage, RESPIRATORY_15_5].concat();
+
+    let mut messages = MessageStream::new(bytes.as_slice()).with_resync(true);
+    while !matches!(messages.next(), Some(Err(LdError::Eof))) {}
+
+    let stats = messages.stats();
+    assert_eq!(stats.frames, 2);
+    assert_eq!(stats.resyncs, 2);
+    assert_eq!(stats.bytes_discarded, 20);
+}
+
+#[test]
+fn oversized_frames() {
+    let mut oversized = [0; 41];
+    encode_frame(0x0200, 0x0a99, &[0; 32], &mut oversized);
+    let bytes = [&oversized, RESPIRATORY_15_5].concat();
+
+    let mut messages = MessageStream::new(bytes.as_slice()).with_resync(true);
+    assert_eq!(
+        messages.next().unwrap().unwrap(),
+        MessageBody::Respiratory(15.5)
+    );
+
+    let stats = messages.stats();
+    assert_eq!(stats.oversized_frames, 1);
+    assert_eq!(stats.longest_oversized, 32);
+}
+
+#[test]
+fn raw_frame() {
+    let mut unknown = HEARTBEAT_72.to_vec();
+    unknown[6] = 0x99;
+
+    let (raw, body) = MessageStream::new(unknown.as_slice()).next_raw().unwrap();
+    assert_eq!(raw.as_slice(), unknown);
+    assert_eq!(raw.ty(), 0x0a99);
+    assert_eq!(raw.payload(), &unknown[8..12]);
+    assert!(matches!(body, MessageBody::Unknown { ty: 0x0a99, .. }));
+}
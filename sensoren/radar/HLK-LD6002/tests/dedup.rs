@@ -0,0 +1,63 @@
+//! Deduplication of frames received over two transports
+
+#![cfg(feature = "filters")]
+
+mod common;
+
+use common::{HEARTBEAT_72, RESPIRATORY_15_5};
+use hlk_ld6002::dedup::Dedup;
+use hlk_ld6002::encode::{encode, MAX_FRAME};
+use hlk_ld6002::{MessageBody, MessageStream, RawFrame};
+
+fn frame(bytes: &[u8]) -> RawFrame {
+    MessageStream::new(bytes).next_raw().unwrap().0
+}
+
+fn encoded(id: u16, message: &MessageBody) -> RawFrame {
+    let mut buf = [0; MAX_FRAME];
+    let len = encode(id, message, &mut buf);
+    frame(&buf[..len])
+}
+
+#[test]
+fn copies_within_window() {
+    let mut dedup = Dedup::<4>::new(100);
+    let heartbeat = frame(HEARTBEAT_72);
+    let respiratory = frame(RESPIRATORY_15_5);
+
+    assert!(!dedup.is_duplicate(0, &heartbeat));
+    assert!(!dedup.is_duplicate(10, &respiratory));
+    assert!(dedup.is_duplicate(40, &heartbeat));
+    assert!(dedup.is_duplicate(110, &respiratory));
+    // a duplicate is only reported once
+    assert!(!dedup.is_duplicate(120, &heartbeat));
+    // copies arriving after the window are new
+    assert!(!dedup.is_duplicate(300, &heartbeat));
+}
+
+#[test]
+fn identical_readings_are_kept() {
+    let mut dedup = Dedup::<4>::new(1_000);
+    // a steady heartbeat reported in consecutive frames
+    let first = encoded(1, &MessageBody::Heartbeat(60.0));
+    let second = encoded(2, &MessageBody::Heartbeat(60.0));
+    assert!(!dedup.is_duplicate(0, &first));
+    assert!(!dedup.is_duplicate(40, &second));
+    // the same frame over the second transport
+    assert!(dedup.is_duplicate(45, &second));
+    assert!(dedup.is_duplicate(50, &first));
+}
+
+#[test]
+fn forgets_oldest() {
+    let mut dedup = Dedup::<2>::new(1_000);
+    let frames: Vec<_> = (0..3)
+        .map(|id| encoded(id, &MessageBody::Respiratory(15.0)))
+        .collect();
+    for frame in &frames {
+        assert!(!dedup.is_duplicate(0, frame));
+    }
+    // only the last 2 frames fit
+    assert!(!dedup.is_duplicate(1, &frames[0]));
+    assert!(dedup.is_duplicate(1, &frames[2]));
+}
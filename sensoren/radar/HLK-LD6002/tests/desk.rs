@@ -0,0 +1,36 @@
+//! Desk occupancy and posture
+
+#![cfg(feature = "detectors")]
+
+use hlk_ld6002::desk::{DeskDetector, Occupancy, Posture};
+use hlk_ld6002::MessageBody;
+
+/// Report `distance` once per second from `from` to `to` seconds, returning the last state
+fn at(desk: &mut DeskDetector, from: u64, to: u64, distance: f32) -> Occupancy {
+    let mut state = Occupancy::Unknown;
+    for second in from..to {
+        state = desk.update(second * 1000, &MessageBody::Distance(Some(distance)));
+    }
+    state
+}
+
+#[test]
+fn sitting_standing_away() {
+    let mut desk = DeskDetector::default();
+
+    assert_eq!(at(&mut desk, 0, 3, 0.6), Occupancy::Unknown);
+    assert_eq!(
+        at(&mut desk, 3, 10, 0.6),
+        Occupancy::Occupied(Posture::Sitting)
+    );
+    assert_eq!(
+        at(&mut desk, 10, 20, 1.2),
+        Occupancy::Occupied(Posture::Standing)
+    );
+    // stepping away for a moment keeps the desk occupied
+    assert_eq!(
+        at(&mut desk, 20, 80, 0.0),
+        Occupancy::Occupied(Posture::Standing)
+    );
+    assert_eq!(at(&mut desk, 80, 200, 0.0), Occupancy::Away);
+}
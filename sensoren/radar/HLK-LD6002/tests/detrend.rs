@@ -0,0 +1,68 @@
+//! Removal of the phase drift
+
+#![cfg(feature = "filters")]
+
+use core::f32::consts::PI;
+use hlk_ld6002::detrend::{remove_linear_trend, Detrend};
+
+/// Breathing at 15/min on top of a drift of `slope` per second, sampled at 20 Hz
+fn drifting_breathing(seconds: f32, slope: f32) -> Vec<f32> {
+    (0..(seconds * 20.0) as usize)
+        .map(|i| {
+            let t = i as f32 / 20.0;
+            5.0 + slope * t + (2.0 * PI * 0.25 * t).sin()
+        })
+        .collect()
+}
+
+fn max_abs(samples: &[f32]) -> f32 {
+    samples
+        .iter()
+        .fold(0.0f32, |max, sample| max.max(sample.abs()))
+}
+
+#[test]
+fn linear_trend() {
+    let mut samples: Vec<f32> = (0..100).map(|i| 3.0 + 0.5 * i as f32).collect();
+    remove_linear_trend(&mut samples);
+    assert!(max_abs(&samples) < 1e-3, "{samples:?}");
+
+    // the breathing stays, the drift and offset are gone
+    let mut samples = drifting_breathing(60.0, 0.2);
+    remove_linear_trend(&mut samples);
+    let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+    assert!(mean.abs() < 1e-3);
+    assert!((max_abs(&samples) - 1.0).abs() < 0.1);
+    assert!((samples[0] - samples[samples.len() - 1]).abs() < 0.5);
+}
+
+#[test]
+fn linear_trend_short() {
+    let mut samples = [4.0];
+    remove_linear_trend(&mut samples);
+    assert_eq!(samples, [0.0]);
+    remove_linear_trend(&mut []);
+}
+
+#[test]
+fn streaming() {
+    let mut detrend = Detrend::new(30.0, 20.0);
+    assert_eq!(detrend.baseline(), None);
+    let output: Vec<f32> = drifting_breathing(600.0, 0.02)
+        .into_iter()
+        .map(|sample| detrend.update(sample))
+        .collect();
+    // after settling, the offset is gone and the baseline lags the drift by the time constant
+    let settled = &output[output.len() - 20 * 60..];
+    let mean = settled.iter().sum::<f32>() / settled.len() as f32;
+    assert!((mean - 0.02 * 30.0).abs() < 0.05, "{mean}");
+    let amplitude = settled
+        .iter()
+        .fold(0.0f32, |max, sample| max.max((sample - mean).abs()));
+    assert!((amplitude - 1.0).abs() < 0.1, "{amplitude}");
+    assert!(detrend.baseline().unwrap() > 10.0);
+
+    detrend.reset();
+    assert_eq!(detrend.baseline(), None);
+    assert_eq!(detrend.update(42.0), 0.0);
+}
@@ -0,0 +1,55 @@
+//! Downsampling of time series with LTTB
+
+#![cfg(feature = "filters")]
+
+use hlk_ld6002::downsample::lttb;
+
+/// A series with a single peak in the middle
+fn series(len: usize) -> Vec<(f32, f32)> {
+    (0..len)
+        .map(|i| (i as f32, if i == len / 2 { 10.0 } else { (i % 3) as f32 }))
+        .collect()
+}
+
+fn downsample(points: &[(f32, f32)], threshold: usize) -> Vec<(f32, f32)> {
+    let mut out = vec![(f32::NAN, f32::NAN); threshold];
+    let len = lttb(points, &mut out);
+    out.truncate(len);
+    out
+}
+
+#[test]
+fn threshold_zero() {
+    assert_eq!(downsample(&series(100), 0), []);
+}
+
+#[test]
+fn threshold_one() {
+    let points = series(100);
+    assert_eq!(downsample(&points, 1), [points[0]]);
+}
+
+#[test]
+fn threshold_two() {
+    let points = series(100);
+    assert_eq!(downsample(&points, 2), [points[0], points[99]]);
+}
+
+#[test]
+fn threshold_at_least_len() {
+    let points = series(10);
+    assert_eq!(downsample(&points, 10), points);
+    assert_eq!(downsample(&points, 20), points);
+    assert_eq!(downsample(&[], 5), []);
+}
+
+#[test]
+fn keeps_ends_and_peak() {
+    let points = series(1000);
+    let out = downsample(&points, 20);
+    assert_eq!(out.len(), 20);
+    assert_eq!(out[0], points[0]);
+    assert_eq!(out[19], points[999]);
+    assert!(out.contains(&points[500]));
+    assert!(out.windows(2).all(|pair| pair[0].0 < pair[1].0));
+}
@@ -0,0 +1,176 @@
+//! Encoding messages into the frames the sensor sends
+
+mod common;
+
+use common::{
+    DISTANCE_0_85, DISTANCE_SHORT, FALL_REPORT, HEARTBEAT_72, PHASE, RESPIRATORY_15_5,
+    SET_FALL_THRESHOLD_0_6, SET_HEIGHT_2_5, SET_HEIGHT_ACK,
+};
+use hlk_ld6002::command::{self, read_ack, write_command, Command};
+use hlk_ld6002::encode::{encode, encode_frame, MAX_FRAME};
+use hlk_ld6002::{LdError, MessageBody, MessageStream, Quirks};
+
+fn encoded(id: u16, message: &MessageBody) -> Vec<u8> {
+    let mut buf = [0; MAX_FRAME];
+    let len = encode(id, message, &mut buf);
+    buf[..len].to_vec()
+}
+
+#[test]
+fn known_frames() {
+    assert_eq!(encoded(1, &MessageBody::Heartbeat(72.0)), HEARTBEAT_72);
+    assert_eq!(
+        encoded(2, &MessageBody::Respiratory(15.5)),
+        RESPIRATORY_15_5
+    );
+    assert_eq!(
+        encoded(3, &MessageBody::Distance(Some(0.85))),
+        DISTANCE_0_85
+    );
+    assert_eq!(encoded(5, &MessageBody::Distance(None)), DISTANCE_SHORT);
+    assert_eq!(encoded(6, &MessageBody::Phase([1.0, -0.5, 0.25])), PHASE);
+
+    let mut buf = [0; MAX_FRAME];
+    let len = encode_frame(0, 0x0e02, &[1], &mut buf);
+    assert_eq!(&buf[..len], FALL_REPORT);
+}
+
+#[test]
+fn roundtrip() {
+    let mut unknown = HEARTBEAT_72.to_vec();
+    unknown[6] = 0x99;
+    let unknown = MessageStream::new(unknown.as_slice())
+        .next()
+        .unwrap()
+        .unwrap();
+
+    for message in [
+        MessageBody::Heartbeat(61.5),
+        MessageBody::Distance(Some(0.0)),
+        MessageBody::Distance(None),
+        unknown,
+    ] {
+        let bytes = encoded(42, &message);
+        // verify the header checksum as well
+        let quirks = Quirks {
+            short_distance: true,
+            ..Quirks::NONE
+        };
+        let decoded: Result<_, LdError<core::convert::Infallible>> =
+            MessageStream::new(bytes.as_slice())
+                .with_quirks(quirks)
+                .next()
+                .unwrap();
+        assert_eq!(decoded.unwrap(), message);
+    }
+}
+
+fn command_frame(id: u16, command: &Command) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_command(&mut bytes, id, command).unwrap();
+    bytes
+}
+
+#[test]
+fn documented_commands() {
+    assert_eq!(command_frame(0, &Command::SetHeight(2.5)), SET_HEIGHT_2_5);
+    assert_eq!(
+        command_frame(0, &Command::SetFallThreshold(0.6)),
+        SET_FALL_THRESHOLD_0_6
+    );
+
+    let area = command_frame(
+        3,
+        &Command::SetAlarmArea {
+            left: 0.5,
+            right: 0.5,
+            front: 0.5,
+            back: 0.5,
+        },
+    );
+    assert_eq!(&area[3..7], &[0x00, 0x10, 0x0e, 0x0c]);
+    assert_eq!(&area[8..12], &0.5f32.to_le_bytes());
+    assert_eq!(
+        &command_frame(1, &Command::SetFallSensitivity(5))[8..13],
+        &[5, 0, 0, 0, !5]
+    );
+    assert_eq!(
+        &command_frame(1, &Command::UserLog(true))[8..12],
+        &[1, 0, 0, 0]
+    );
+    assert_eq!(command_frame(1, &Command::Reset).len(), 9);
+    assert_eq!(
+        command_frame(
+            1,
+            &Command::Raw {
+                ty: 0x1234,
+                data: &[1, 2, 3]
+            }
+        ),
+        {
+            let mut buf = [0; command::MAX_FRAME];
+            let len = encode_frame(1, 0x1234, &[1, 2, 3], &mut buf);
+            buf[..len].to_vec()
+        }
+    );
+}
+
+#[test]
+fn ack_between_reports() {
+    let mut other_id = SET_HEIGHT_ACK.to_vec();
+    other_id[2] = 9;
+    other_id[7] = !(!other_id[7] ^ 9);
+    // an unknown frame with a payload too long to be buffered
+    let mut oversized = [0; 41];
+    let len = encode_frame(0, 0x0e06, &[0; 28], &mut oversized);
+    let bytes = [
+        &HEARTBEAT_72[5..],
+        RESPIRATORY_15_5,
+        &other_id,
+        &oversized[..len],
+        &PHASE[..10],
+        HEARTBEAT_72,
+        SET_HEIGHT_ACK,
+        DISTANCE_0_85,
+    ]
+    .concat();
+
+    let mut messages = MessageStream::new(bytes.as_slice()).with_resync(true);
+    let ack = read_ack(&mut messages, 0, &Command::SetHeight(2.5), 20)
+        .unwrap()
+        .unwrap();
+    assert_eq!((ack.id, ack.ty), (0, 0x0e04));
+    assert_eq!(ack.data(), [1]);
+    assert!(ack.is_success());
+    // the reports after the reply are still read
+    assert_eq!(
+        messages.next().unwrap().unwrap(),
+        MessageBody::Distance(Some(0.85))
+    );
+}
+
+#[test]
+fn no_ack() {
+    let bytes = [HEARTBEAT_72, RESPIRATORY_15_5, HEARTBEAT_72, SET_HEIGHT_ACK].concat();
+    let mut messages = MessageStream::new(bytes.as_slice()).with_resync(true);
+    // the reply to another command doesn't count
+    assert!(
+        read_ack(&mut messages, 0, &Command::SetFallThreshold(0.6), 4)
+            .unwrap()
+            .is_none()
+    );
+
+    let mut messages = MessageStream::new(bytes.as_slice()).with_resync(true);
+    // the reply comes after the limit
+    assert!(read_ack(&mut messages, 0, &Command::SetHeight(1.0), 3)
+        .unwrap()
+        .is_none());
+    assert!(matches!(
+        read_ack(&mut messages, 0, &Command::SetHeight(1.0), 3),
+        Ok(Some(_))
+    ));
+    assert!(matches!(
+        read_ack(&mut messages, 0, &Command::SetHeight(1.0), 3),
+        Err(LdError::Eof)
+    ));
+}
@@ -0,0 +1,67 @@
+//! Events with severities and correlation ids
+
+#![cfg(feature = "detectors")]
+
+use hlk_ld6002::apnea::BreathingEvent;
+use hlk_ld6002::event::{Correlator, EventKind, Severity, Source};
+use hlk_ld6002::fall::PossibleFall;
+use hlk_ld6002::presence::Presence;
+use hlk_ld6002::tamper::Tamper;
+
+#[test]
+fn episodes_share_correlation() {
+    let source = Source { sensor: 2, room: 1 };
+    let mut events = Correlator::new(source);
+
+    let present = events.event(0, Presence::Present);
+    let stopped = events.event(1_000, BreathingEvent::BreathingStopped);
+    let blocked = events.event(2_000, Tamper::SensorBlocked);
+    let resumed = events.event(3_000, BreathingEvent::BreathingResumed);
+    let absent = events.event(4_000, Presence::Absent);
+
+    assert_eq!(stopped.correlation, resumed.correlation);
+    assert_eq!(present.correlation, absent.correlation);
+    assert_ne!(present.correlation, stopped.correlation);
+    assert_ne!(blocked.correlation, stopped.correlation);
+    assert_eq!(resumed.source, source);
+    assert_eq!(resumed.at, 3_000);
+
+    // a new episode gets a new id
+    let stopped_again = events.event(5_000, BreathingEvent::BreathingStopped);
+    assert_ne!(stopped_again.correlation, stopped.correlation);
+}
+
+#[test]
+fn single_events_get_their_own_id() {
+    let mut events = Correlator::new(Source::default());
+    let fall = PossibleFall {
+        at: 0,
+        height: 0.2,
+        confidence: 0.8,
+    };
+    let first = events.event(0, fall);
+    let second = events.event(1_000, fall);
+    assert_ne!(first.correlation, second.correlation);
+    // an end without a start
+    let normal = events.event(2_000, Tamper::Normal);
+    assert_ne!(normal.correlation, second.correlation);
+}
+
+#[test]
+fn severities() {
+    let mut events = Correlator::new(Source::default());
+    assert_eq!(
+        events.event(0, BreathingEvent::BreathingStopped).severity,
+        Severity::Critical
+    );
+    assert_eq!(
+        events.event(0, Tamper::SensorTampered).severity,
+        Severity::Warning
+    );
+    assert_eq!(events.event(0, Presence::Present).severity, Severity::Info);
+    assert_eq!(
+        EventKind::from(BreathingEvent::IrregularBreathing).severity(),
+        Severity::Warning
+    );
+    assert!(Severity::Critical > Severity::Warning);
+}
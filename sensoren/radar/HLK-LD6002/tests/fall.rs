@@ -0,0 +1,54 @@
+//! Fall detection from a ceiling mounted sensor
+
+#![cfg(feature = "detectors")]
+
+use hlk_ld6002::fall::{FallConfig, FallDetector};
+use hlk_ld6002::MessageBody;
+
+/// Feed a second of readings at `height` with the given phase swing, returning the confidence of a fall
+fn second(detector: &mut FallDetector<8>, second: u64, height: f32, swing: f32) -> Option<f32> {
+    let now = second * 1000;
+    let distance = MessageBody::Distance(Some(2.5 - height));
+    let mut fall = detector.update(now, &distance);
+    for i in 0..20 {
+        let phase = MessageBody::Phase([swing * (i % 2) as f32; 3]);
+        fall = fall.or(detector.update(now + i * 50, &phase));
+    }
+    fall.map(|fall| fall.confidence)
+}
+
+#[test]
+fn fall_and_lying_still() {
+    let mut detector = FallDetector::new(FallConfig::new(2.5));
+    for s in 0..10 {
+        assert_eq!(second(&mut detector, s, 1.4, 1.0), None);
+    }
+    let falls: Vec<_> = (10..30)
+        .filter_map(|s| second(&mut detector, s, 0.2, 0.1))
+        .collect();
+    assert_eq!(falls.len(), 1);
+    assert!(falls[0] > 0.7, "{falls:?}");
+}
+
+#[test]
+fn lying_down_slowly() {
+    let mut detector = FallDetector::new(FallConfig::new(2.5));
+    for (s, height) in [1.4, 1.2, 1.0, 0.8, 0.6, 0.4, 0.3, 0.2]
+        .into_iter()
+        .enumerate()
+    {
+        assert_eq!(second(&mut detector, s as u64, height, 0.1), None);
+    }
+    for s in 8..30 {
+        assert_eq!(second(&mut detector, s, 0.2, 0.1), None);
+    }
+}
+
+#[test]
+fn moving_on_the_floor() {
+    let mut detector = FallDetector::new(FallConfig::new(2.5));
+    second(&mut detector, 0, 1.4, 0.0);
+    for s in 1..30 {
+        assert_eq!(second(&mut detector, s, 0.2, 3.0), None);
+    }
+}
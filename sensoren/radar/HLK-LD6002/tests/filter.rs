@@ -0,0 +1,33 @@
+//! Smoothing of the readings
+
+#![cfg(feature = "filters")]
+
+use hlk_ld6002::filter::{Ewma, FilteredData, Median, MovingAverage, Smoother};
+use hlk_ld6002::MessageBody;
+
+#[test]
+fn smoothers() {
+    let mut average = MovingAverage::<3>::default();
+    let averages: Vec<_> = [3.0, 6.0, 9.0, 12.0].map(|v| average.update(v)).into();
+    assert_eq!(averages, [3.0, 4.5, 6.0, 9.0]);
+
+    let mut median = Median::<3>::default();
+    let medians: Vec<_> = [70.0, 120.0, 72.0, 71.0].map(|v| median.update(v)).into();
+    assert_eq!(medians, [70.0, 95.0, 72.0, 72.0]);
+
+    let mut ewma = Ewma::new(0.5);
+    let smoothed: Vec<_> = [10.0, 20.0, 20.0].map(|v| ewma.update(v)).into();
+    assert_eq!(smoothed, [10.0, 15.0, 17.5]);
+}
+
+#[test]
+fn filtered_heartbeat() {
+    let mut data = FilteredData::new((Median::<3>::default(), Ewma::new(0.5)));
+    for rate in [70.0, 72.0, 150.0, 74.0] {
+        data.update(MessageBody::Heartbeat(rate));
+    }
+    // the outlier is dropped by the median (70, 71, 72, 74) before averaging
+    assert_eq!(data.data().heartbeat, 72.625);
+    assert_eq!(data.raw().heartbeat, 74.0);
+    assert_eq!(data.data().respiratory, 0.0);
+}
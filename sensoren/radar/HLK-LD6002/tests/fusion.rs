@@ -0,0 +1,79 @@
+//! Fusion of the readings of overlapping sensors
+
+#![cfg(feature = "filters")]
+
+use hlk_ld6002::fusion::{fuse, Estimate, FusionPolicy};
+use hlk_ld6002::Data;
+
+fn estimate(heartbeat: f32, respiratory: f32, distance: f32, quality: f32) -> Estimate {
+    Estimate {
+        data: Data {
+            heartbeat,
+            respiratory,
+            distance,
+            ..Data::default()
+        },
+        quality,
+    }
+}
+
+#[test]
+fn best() {
+    let estimates = [
+        estimate(60.0, 12.0, 1.0, 0.5),
+        estimate(70.0, 16.0, 2.0, 0.9),
+    ];
+    let data = fuse(&estimates, FusionPolicy::Best);
+    assert_eq!(data.heartbeat, 70.0);
+    assert_eq!(data.respiratory, 16.0);
+    assert_eq!(data.distance, 2.0);
+}
+
+#[test]
+fn weighted() {
+    let estimates = [
+        estimate(60.0, 12.0, 1.0, 1.0),
+        estimate(72.0, 18.0, 2.0, 3.0),
+    ];
+    let data = fuse(&estimates, FusionPolicy::Weighted);
+    assert_eq!(data.heartbeat, 69.0);
+    assert_eq!(data.respiratory, 16.5);
+    // distances are relative to each sensor, so they aren't averaged
+    assert_eq!(data.distance, 2.0);
+}
+
+#[test]
+fn missing_readings() {
+    // the better sensor lost the heartbeat
+    let estimates = [
+        estimate(64.0, 14.0, 1.0, 0.2),
+        estimate(0.0, 16.0, 2.0, 0.8),
+    ];
+    for policy in [FusionPolicy::Best, FusionPolicy::Weighted] {
+        let data = fuse(&estimates, policy);
+        assert_eq!(data.heartbeat, 64.0, "{policy:?}");
+    }
+}
+
+#[test]
+fn ignored_sensors() {
+    let estimates = [
+        estimate(60.0, 12.0, 1.0, 0.0),
+        estimate(70.0, 16.0, 2.0, 1.0),
+    ];
+    assert_eq!(fuse(&estimates, FusionPolicy::Weighted).heartbeat, 70.0);
+}
+
+#[test]
+fn no_input() {
+    for policy in [FusionPolicy::Best, FusionPolicy::Weighted] {
+        for estimates in [&[][..], &[estimate(60.0, 12.0, 1.0, 0.0)]] {
+            let data = fuse(estimates, policy);
+            assert_eq!(
+                [data.heartbeat, data.respiratory, data.distance],
+                [0.0; 3],
+                "{policy:?}"
+            );
+        }
+    }
+}
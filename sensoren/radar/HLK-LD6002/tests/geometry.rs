@@ -0,0 +1,71 @@
+//! Conversion of the slant distance using the mounting position
+
+#![cfg(feature = "filters")]
+
+use hlk_ld6002::geometry::{Implausible, Mounting};
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-4,
+        "{actual} isn't close to {expected}"
+    );
+}
+
+#[test]
+fn horizontal_distance() {
+    // sensor 1.5 m above the chest
+    let mounting = Mounting::new(2.5, 1.0);
+    assert_close(mounting.horizontal_distance(2.5).unwrap(), 2.0);
+    assert_close(mounting.horizontal_distance(1.5).unwrap(), 0.0);
+}
+
+#[test]
+fn distance_out_of_range() {
+    let mounting = Mounting::new(2.5, 1.0);
+    // shorter than the vertical offset
+    assert_eq!(mounting.horizontal_distance(1.4), None);
+    assert_eq!(mounting.horizontal_distance(0.0), None);
+    assert_eq!(mounting.horizontal_distance(f32::NAN), None);
+
+    // a target above the sensor works the same way
+    let mounting = Mounting::new(0.5, 1.0);
+    assert_eq!(mounting.horizontal_distance(0.4), None);
+    assert_close(mounting.horizontal_distance(1.3).unwrap(), 1.2);
+}
+
+#[test]
+fn horizontal_offset() {
+    let mounting = Mounting::new(2.5, 1.0);
+    assert_close(mounting.horizontal_offset(2.5, 3.0).unwrap(), 2.0);
+    assert_eq!(
+        mounting.horizontal_offset(1.0, 3.0),
+        Err(Implausible::TooClose)
+    );
+    assert_eq!(
+        mounting.horizontal_offset(5.0, 3.0),
+        Err(Implausible::TooFar)
+    );
+}
+
+#[test]
+fn target_height_at_an_angle() {
+    // ceiling sensor looking at a bed 1.2 m to the side, 1.6 m below the sensor
+    let mounting = Mounting::new(2.5, 1.0);
+    assert_close(mounting.target_height(2.0, 1.2).unwrap(), 0.9);
+    // someone standing next to the bed
+    assert_close(mounting.target_height(1.3, 1.2).unwrap(), 2.0);
+    // straight below the sensor
+    assert_close(mounting.target_height(1.5, 0.0).unwrap(), 1.0);
+}
+
+#[test]
+fn target_height_out_of_room() {
+    let mounting = Mounting::new(2.5, 1.0);
+    // shorter than the known horizontal offset
+    assert_eq!(mounting.target_height(1.0, 1.2), Err(Implausible::TooClose));
+    // below the floor
+    assert_eq!(
+        mounting.target_height(3.0, 1.2),
+        Err(Implausible::OutOfRoom)
+    );
+}
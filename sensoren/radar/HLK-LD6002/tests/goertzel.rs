@@ -0,0 +1,46 @@
+//! Breathing rate estimation from the simulated phase
+
+#![cfg(feature = "filters")]
+
+use hlk_ld6002::goertzel::BreathRateEstimator;
+use hlk_ld6002::testing::{SimulatedSensor, Vitals};
+use hlk_ld6002::MessageStream;
+
+#[test]
+fn simulated_breathing_rate() {
+    let sensor = SimulatedSensor::new(Vitals {
+        respiratory: 18.0,
+        ..Vitals::default()
+    });
+    let mut estimator = BreathRateEstimator::<512>::new(20.0);
+    for message in MessageStream::new(sensor).take(600) {
+        estimator.update(&message.unwrap());
+    }
+    assert_eq!(estimator.estimate(), Some(18.0));
+}
+
+#[test]
+fn invalid_ranges() {
+    let mut estimator = BreathRateEstimator::<64>::new(20.0);
+    for i in 0..64 {
+        estimator.push((i as f32 / 5.0).sin());
+    }
+    assert!(estimator.estimate_in(6.0, 30.0, 0.5).is_some());
+    // the upper end of the range is scanned as well
+    assert_eq!(estimator.estimate_in(30.0, 30.0, 0.1), Some(30.0));
+
+    for (low, high, step) in [
+        (6.0, 30.0, 0.0),
+        (6.0, 30.0, -1.0),
+        (6.0, 30.0, f32::NAN),
+        (f32::NEG_INFINITY, 30.0, 1.0),
+        (6.0, f32::INFINITY, 1.0),
+        (30.0, 6.0, 1.0),
+    ] {
+        assert_eq!(
+            estimator.estimate_in(low, high, step),
+            None,
+            "{low} {high} {step}"
+        );
+    }
+}
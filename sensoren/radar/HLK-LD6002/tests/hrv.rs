@@ -0,0 +1,70 @@
+//! Heart rate variability proxies from the simulated sensor
+
+#![cfg(feature = "filters")]
+
+use hlk_ld6002::hrv::{HrvEstimator, Intervals};
+use hlk_ld6002::testing::{SimulatedSensor, Vitals};
+use hlk_ld6002::{MessageBody, MessageStream};
+
+#[test]
+fn interval_metrics() {
+    let mut intervals = Intervals::<8>::default();
+    for interval in [800.0, 820.0, 200.0, 800.0] {
+        intervals.push(interval);
+    }
+    // the 200 ms interval is out of range and ignored
+    let metrics = intervals.metrics().unwrap();
+    assert_eq!(metrics.count, 3);
+    assert!((metrics.rmssd - 20.0).abs() < 0.01, "{metrics:?}");
+    assert!((metrics.sdnn - 9.43).abs() < 0.01, "{metrics:?}");
+}
+
+/// Feed a minute of a perfectly regular simulated heartbeat starting at `start` milliseconds
+fn simulated_beats(start: u64) -> HrvEstimator<32> {
+    let sensor = SimulatedSensor::new(Vitals {
+        heartbeat: 60.0,
+        respiratory: 0.0,
+        noise: 0.0,
+        ..Vitals::default()
+    });
+    let mut hrv = HrvEstimator::<32>::new(20.0);
+    let mut now = start;
+    for message in MessageStream::new(sensor).take(23 * 60) {
+        let message = message.unwrap();
+        if let MessageBody::Phase(_) = message {
+            now += SimulatedSensor::PHASE_INTERVAL;
+        }
+        hrv.update(now, &message);
+    }
+    hrv
+}
+
+#[test]
+fn regular_beats() {
+    let hrv = simulated_beats(0);
+    // a perfectly regular simulated heartbeat has close to no variability
+    let phase = hrv.from_phase().unwrap();
+    assert_eq!(phase.count, 32);
+    assert!(phase.sdnn < 10.0 && phase.rmssd < 10.0, "{phase:?}");
+    let rates = hrv.from_rates().unwrap();
+    assert_eq!(rates.sdnn, 0.0);
+}
+
+#[test]
+fn regular_beats_after_days() {
+    // timestamps this large can't be represented in milliseconds by an f32
+    let phase = simulated_beats(10 * 86_400_000).from_phase().unwrap();
+    let reference = simulated_beats(0).from_phase().unwrap();
+    assert_eq!(phase.count, 32);
+    assert!((phase.sdnn - reference.sdnn).abs() < 0.1, "{phase:?}");
+    assert!((phase.rmssd - reference.rmssd).abs() < 0.1, "{phase:?}");
+}
+
+#[test]
+fn clock_going_backwards() {
+    let mut hrv = HrvEstimator::<8>::new(20.0);
+    for (now, phase) in [(1_000, -1.0), (900, 1.0), (950, -1.0), (800, 1.0)] {
+        hrv.update(now, &MessageBody::Phase([phase, 0.0, 0.0]));
+    }
+    assert!(hrv.from_phase().is_none());
+}
@@ -0,0 +1,124 @@
+//! Inactivity alerts during the active periods of the day
+
+#![cfg(feature = "detectors")]
+
+use hlk_ld6002::inactivity::{
+    ActivePeriod, HouseMode, InactivityEvent, InactivityWatchdog, WelfareConfig, WelfareMonitor,
+};
+use hlk_ld6002::MessageBody;
+
+const MINUTE: u64 = 60_000;
+const HOUR: u64 = 60 * MINUTE;
+
+#[test]
+fn periods() {
+    let day = ActivePeriod::new((8, 0), (22, 0));
+    assert!(day.contains(8 * 60) && !day.contains(22 * 60) && !day.contains(3 * 60));
+    let night_shift = ActivePeriod::new((22, 0), (6, 0));
+    assert!(
+        night_shift.contains(23 * 60) && night_shift.contains(60) && !night_shift.contains(12 * 60)
+    );
+}
+
+#[test]
+fn alerts_only_during_active_periods() {
+    let mut watchdog = InactivityWatchdog::new([ActivePeriod::new((8, 0), (22, 0))], 3 * HOUR);
+    // the clock starts at midnight
+    let minute = |now: u64| (now / MINUTE % (24 * 60)) as u16;
+
+    // last seen going to bed at 23:00
+    let bed = 23 * HOUR;
+    watchdog.check(0, 0);
+    assert_eq!(watchdog.update(bed, &MessageBody::Heartbeat(60.0)), None);
+    // 3 hours without signs of life are over at 2:00, but the alert waits for the active period at 8:00
+    let mut events = Vec::new();
+    for now in (bed..bed + 12 * HOUR).step_by(MINUTE as usize) {
+        events.extend(
+            watchdog
+                .check(now, minute(now))
+                .map(|event| (minute(now), event)),
+        );
+    }
+    assert_eq!(events, [(8 * 60, InactivityEvent::Inactive { since: bed })]);
+    assert!(watchdog.is_alerted());
+
+    // a zero rate is no sign of life
+    assert_eq!(
+        watchdog.update(bed + 12 * HOUR, &MessageBody::Respiratory(0.0)),
+        None
+    );
+    assert_eq!(
+        watchdog.update(bed + 12 * HOUR, &MessageBody::Distance(Some(1.2))),
+        Some(InactivityEvent::Active)
+    );
+}
+
+fn distance(distance: f32) -> MessageBody {
+    MessageBody::Distance(Some(distance))
+}
+
+#[test]
+fn welfare_check_without_movement() {
+    let mut monitor = WelfareMonitor::new(WelfareConfig {
+        max_still: 2 * HOUR,
+        ..WelfareConfig::default()
+    });
+
+    assert_eq!(monitor.update(0, &distance(1.0)), None);
+    // small changes, like breathing, aren't movement
+    for minute in 1..=150 {
+        monitor.update(minute * MINUTE, &distance(1.05));
+        if minute < 120 {
+            assert_eq!(monitor.check(minute * MINUTE), None);
+        }
+    }
+    assert_eq!(
+        monitor.check(150 * MINUTE),
+        Some(InactivityEvent::Inactive { since: 0 })
+    );
+    assert_eq!(monitor.check(151 * MINUTE), None);
+    assert!(monitor.is_alerted());
+
+    assert_eq!(
+        monitor.update(152 * MINUTE, &distance(2.0)),
+        Some(InactivityEvent::Active)
+    );
+    assert_eq!(monitor.last_movement(), Some(152 * MINUTE));
+}
+
+#[test]
+fn welfare_house_mode() {
+    let config = WelfareConfig {
+        max_still: HOUR,
+        occupied_for: 3 * HOUR,
+        ..WelfareConfig::default()
+    };
+
+    // away, no checks
+    let mut monitor = WelfareMonitor::new(config);
+    monitor.set_mode(0, HouseMode::Away);
+    monitor.check(0);
+    assert_eq!(monitor.check(5 * HOUR), None);
+    // coming home starts over
+    monitor.set_mode(5 * HOUR, HouseMode::Home);
+    assert_eq!(monitor.check(5 * HOUR + 30 * MINUTE), None);
+    assert_eq!(
+        monitor.check(6 * HOUR),
+        Some(InactivityEvent::Inactive { since: 5 * HOUR })
+    );
+
+    // unknown, occupied only while a target was reported recently
+    let mut monitor = WelfareMonitor::new(config);
+    assert!(!monitor.is_occupied(0));
+    monitor.update(0, &distance(1.0));
+    assert!(monitor.is_occupied(2 * HOUR));
+    assert!(!monitor.is_occupied(3 * HOUR));
+    assert_eq!(
+        monitor.check(2 * HOUR),
+        Some(InactivityEvent::Inactive { since: 0 })
+    );
+
+    let mut monitor = WelfareMonitor::new(config);
+    monitor.update(0, &distance(1.0));
+    assert_eq!(monitor.check(4 * HOUR), None);
+}
@@ -0,0 +1,72 @@
+//! Intrusion alerts while away
+
+#![cfg(feature = "detectors")]
+
+use hlk_ld6002::event::{Correlator, Severity, Source};
+use hlk_ld6002::inactivity::HouseMode;
+use hlk_ld6002::intrusion::{Intrusion, IntrusionDetector};
+use hlk_ld6002::MessageBody;
+
+const TARGET: MessageBody = MessageBody::Distance(Some(2.0));
+const NO_TARGET: MessageBody = MessageBody::Distance(None);
+
+#[test]
+fn presence_while_away() {
+    let mut detector = IntrusionDetector::new(2_000);
+    assert_eq!(detector.set_mode(HouseMode::Away), None);
+
+    assert_eq!(detector.update(10_000, &TARGET), None);
+    assert_eq!(detector.update(11_000, &TARGET), None);
+    assert_eq!(
+        detector.update(12_000, &TARGET),
+        Some(Intrusion::Detected { since: 10_000 })
+    );
+    // only raised once
+    assert_eq!(detector.update(13_000, &TARGET), None);
+    assert_eq!(detector.update(14_000, &MessageBody::Heartbeat(80.0)), None);
+    assert_eq!(
+        detector.update(15_000, &NO_TARGET),
+        Some(Intrusion::Cleared)
+    );
+    assert_eq!(detector.update(16_000, &NO_TARGET), None);
+    assert!(!detector.is_alerted());
+}
+
+#[test]
+fn spurious_frames() {
+    let mut detector = IntrusionDetector::new(2_000);
+    detector.set_mode(HouseMode::Vacation);
+    for start in (0..20_000).step_by(1_500) {
+        assert_eq!(detector.update(start, &TARGET), None);
+        assert_eq!(detector.update(start + 1_000, &NO_TARGET), None);
+    }
+}
+
+#[test]
+fn disarmed() {
+    let mut detector = IntrusionDetector::default();
+    for mode in [HouseMode::Home, HouseMode::Unknown] {
+        detector.set_mode(mode);
+        assert!(!detector.is_armed());
+        for time in (0..10_000).step_by(500) {
+            assert_eq!(detector.update(time, &TARGET), None);
+        }
+    }
+
+    // coming home clears the alert
+    detector.set_mode(HouseMode::Away);
+    detector.update(20_000, &TARGET);
+    assert!(detector.update(22_000, &TARGET).is_some());
+    assert_eq!(detector.set_mode(HouseMode::Home), Some(Intrusion::Cleared));
+    assert_eq!(detector.update(30_000, &TARGET), None);
+}
+
+#[test]
+fn intrusion_events() {
+    let mut events = Correlator::new(Source::default());
+    let detected = events.event(0, Intrusion::Detected { since: 0 });
+    let cleared = events.event(1_000, Intrusion::Cleared);
+    assert_eq!(detected.severity, Severity::Critical);
+    assert_eq!(cleared.severity, Severity::Info);
+    assert_eq!(detected.correlation, cleared.correlation);
+}
@@ -0,0 +1,73 @@
+//! Latency measurements of the streams
+
+#![cfg(feature = "latency")]
+
+mod common;
+
+use common::HEARTBEAT_72;
+use embedded_io_adapters::std::FromStd;
+use hlk_ld6002::latency::{Histogram, Stage, Summary};
+use hlk_ld6002::MessageStream;
+use std::cell::Cell;
+
+#[test]
+fn percentiles() {
+    let mut histogram = Histogram::default();
+    assert_eq!(histogram.summary(), None);
+    for ms in [0, 3, 3, 5, 10, 12, 20, 40, 100, 700] {
+        histogram.record(ms);
+    }
+    assert_eq!(
+        histogram.summary(),
+        Some(Summary {
+            count: 10,
+            p50: 16,
+            p90: 128,
+            p99: 700,
+            max: 700,
+        })
+    );
+}
+
+#[test]
+fn decode_latency() {
+    let time = Cell::new(0);
+    let bytes = HEARTBEAT_72.repeat(3);
+    let reader = FromStd::new(SlowReader {
+        bytes: &bytes,
+        time: &time,
+    });
+    let mut stream = MessageStream::with_clock(reader, || time.get());
+    for _ in 0..3 {
+        stream.next().unwrap().unwrap();
+        stream.record_latency(Stage::Publish, 5);
+    }
+
+    let latency = stream.stats().latency;
+    // from the first to the last byte of every frame
+    let frame = HEARTBEAT_72.len() as u32 - 1;
+    assert_eq!(latency.decode.count(), 3);
+    assert_eq!(latency.decode.summary().unwrap().p99, frame);
+    assert_eq!(latency.publish.summary().unwrap().max, 5);
+    assert_eq!(latency.filter.count(), 0);
+}
+
+/// Returns a single byte per read, each read taking a millisecond
+struct SlowReader<'a> {
+    bytes: &'a [u8],
+    time: &'a Cell<u64>,
+}
+
+impl std::io::Read for SlowReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.time.set(self.time.get() + 1);
+        match self.bytes.split_first() {
+            Some((byte, rest)) if !buf.is_empty() => {
+                buf[0] = *byte;
+                self.bytes = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
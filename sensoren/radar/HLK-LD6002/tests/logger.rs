@@ -0,0 +1,105 @@
+//! Logging to rotating files
+
+#![cfg(feature = "std")]
+
+use hlk_ld6002::logger::{DataLogger, Flush, Format, LoggerConfig};
+use hlk_ld6002::Data;
+use std::fs;
+use std::path::PathBuf;
+
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("hlk_ld6002_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    directory
+}
+
+fn data(heartbeat: f32) -> Data {
+    Data {
+        heartbeat,
+        respiratory: 14.5,
+        distance: 0.0,
+        ..Data::default()
+    }
+}
+
+#[test]
+fn csv_with_interval() {
+    let directory = directory("csv");
+    let mut logger = DataLogger::new(LoggerConfig {
+        flush: Flush::EverySample,
+        ..LoggerConfig::new(&directory, Format::Csv)
+    })
+    .unwrap();
+
+    assert!(logger.log(1_000, &data(60.0)).unwrap());
+    // within the sample interval
+    assert!(!logger.log(1_500, &data(61.0)).unwrap());
+    assert!(logger.log(2_000, &data(62.0)).unwrap());
+
+    let path = logger.path().unwrap().to_owned();
+    assert_eq!(path, directory.join("ld6002-1000.csv"));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "timestamp,heartbeat,respiratory,distance,heartbeat_confidence,respiratory_confidence\n\
+         1000,60,14.5,,,\n\
+         2000,62,14.5,,,\n"
+    );
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn json_lines() {
+    let directory = directory("jsonl");
+    let mut logger = DataLogger::new(LoggerConfig::new(&directory, Format::JsonLines)).unwrap();
+    logger.log(5_000, &data(60.0)).unwrap();
+    let path = logger.path().unwrap().to_owned();
+    drop(logger);
+
+    let content = fs::read_to_string(path).unwrap();
+    let line: serde_json::Value = serde_json::from_str(content.trim()).unwrap();
+    assert_eq!(line["timestamp"], 5_000);
+    assert_eq!(line["heartbeat"], 60.0);
+    assert!(line["distance"].is_null());
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn rotation_and_flush_policy() {
+    let directory = directory("rotation");
+    let mut logger = DataLogger::new(LoggerConfig {
+        flush: Flush::Samples(2),
+        max_age: Some(10_000),
+        ..LoggerConfig::new(&directory, Format::JsonLines)
+    })
+    .unwrap();
+
+    logger.log(0, &data(60.0)).unwrap();
+    let first = logger.path().unwrap().to_owned();
+    // buffered until the second sample
+    assert_eq!(fs::read_to_string(&first).unwrap(), "");
+    logger.log(1_000, &data(60.0)).unwrap();
+    assert_eq!(fs::read_to_string(&first).unwrap().lines().count(), 2);
+
+    logger.log(10_000, &data(60.0)).unwrap();
+    let second = logger.path().unwrap().to_owned();
+    assert_ne!(first, second);
+    logger.close().unwrap();
+    assert_eq!(fs::read_to_string(&second).unwrap().lines().count(), 1);
+
+    // rotation by size
+    let mut logger = DataLogger::new(LoggerConfig {
+        prefix: "size".into(),
+        max_size: Some(100),
+        max_age: None,
+        ..LoggerConfig::new(&directory, Format::Csv)
+    })
+    .unwrap();
+    let mut paths = Vec::new();
+    for t in 0..4 {
+        logger.log(t * 1_000, &data(60.0)).unwrap();
+        paths.push(logger.path().unwrap().to_owned());
+    }
+    paths.dedup();
+    assert!(paths.len() > 1);
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,60 @@
+//! Payloads and topics of the MQTT publisher
+
+#![cfg(feature = "mqtt")]
+
+use hlk_ld6002::mqtt::{write_json, Topic, MAX_TOPIC};
+use hlk_ld6002::Data;
+use serde_json::Value;
+
+#[test]
+fn json_payload() {
+    let data = Data {
+        heartbeat: 61.5,
+        respiratory: 14.0,
+        ..Data::default()
+    };
+    let mut buffer = [0; 100];
+    let len = write_json(&data, &mut buffer).unwrap();
+    let payload = core::str::from_utf8(&buffer[..len]).unwrap();
+    assert_eq!(
+        payload,
+        r#"{"heartbeat":61.50,"respiratory":14.00,"distance":null}"#
+    );
+    let parsed: Value = serde_json::from_str(payload).unwrap();
+    assert_eq!(parsed["heartbeat"], 61.5);
+}
+
+#[test]
+fn payload_fits_in_100_bytes() {
+    let data = Data {
+        heartbeat: f32::MAX,
+        respiratory: f32::MAX,
+        distance: f32::MAX,
+        ..Data::default()
+    };
+    // the largest f32 has 39 digits, too much for any buffer a node would reserve
+    assert!(write_json(&data, &mut [0; 100]).is_err());
+
+    let data = Data {
+        heartbeat: 250.0,
+        respiratory: 60.0,
+        distance: 10.0,
+        ..Data::default()
+    };
+    assert!(write_json(&data, &mut [0; 100]).is_ok());
+    assert!(write_json(&data, &mut [0; 10]).is_err());
+}
+
+#[test]
+fn topics() {
+    assert_eq!(
+        Topic::new("radar/bedroom", "state").unwrap().as_str(),
+        "radar/bedroom/state"
+    );
+    assert_eq!(
+        Topic::new("radar/bedroom/", "state").unwrap().as_str(),
+        "radar/bedroom/state"
+    );
+    let long = "x".repeat(MAX_TOPIC);
+    assert!(Topic::new(&long, "state").is_none());
+}
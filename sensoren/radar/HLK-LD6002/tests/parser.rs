@@ -0,0 +1,128 @@
+//! Parsing pushed bytes without a reader
+
+mod common;
+
+use common::{HEARTBEAT_72, PHASE, RESPIRATORY_15_5};
+use embedded_io::Read;
+use hlk_ld6002::testing::{SimulatedSensor, Vitals};
+use hlk_ld6002::{FrameParser, LdError, MessageBody, MessageStream, ParserEvent};
+
+#[test]
+fn frames_split_across_pushes() {
+    let bytes = [HEARTBEAT_72, RESPIRATORY_15_5, PHASE].concat();
+    let mut parser = FrameParser::new();
+
+    // chunks that don't line up with the frame boundaries, like a DMA half transfer
+    let messages: Vec<_> = bytes
+        .chunks(5)
+        .flat_map(|chunk| parser.push(chunk).map(Result::unwrap).collect::<Vec<_>>())
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            MessageBody::Heartbeat(72.0),
+            MessageBody::Respiratory(15.5),
+            MessageBody::Phase([1.0, -0.5, 0.25]),
+        ]
+    );
+}
+
+#[test]
+fn error_doesnt_stop_parsing() {
+    let mut bytes = HEARTBEAT_72.to_vec();
+    bytes[12] ^= 0xff;
+    bytes.extend_from_slice(RESPIRATORY_15_5);
+
+    let mut parser = FrameParser::new();
+    let mut messages = parser.push(&bytes);
+    assert!(matches!(
+        messages.next(),
+        Some(Err(LdError::InvalidChecksum { ty: "body", .. }))
+    ));
+    assert_eq!(
+        messages.next().unwrap().unwrap(),
+        MessageBody::Respiratory(15.5)
+    );
+    assert!(messages.next().is_none());
+}
+
+#[test]
+fn feed_reports_progress() {
+    let mut parser = FrameParser::new();
+    let events: Vec<_> = HEARTBEAT_72.iter().map(|byte| parser.feed(*byte)).collect();
+
+    assert!(events[..7]
+        .iter()
+        .all(|event| matches!(event, ParserEvent::Pending)));
+    assert!(matches!(
+        events[7],
+        ParserEvent::Header {
+            id: 1,
+            ty: 0x0a15,
+            length: 4
+        }
+    ));
+    assert!(events[8..12]
+        .iter()
+        .all(|event| matches!(event, ParserEvent::Pending)));
+    assert!(matches!(
+        &events[12],
+        ParserEvent::Message(MessageBody::Heartbeat(72.0))
+    ));
+}
+
+/// A few seconds of the simulated sensor as raw bytes
+fn simulated_bytes() -> Vec<u8> {
+    let mut sensor = SimulatedSensor::new(Vitals::default());
+    let mut bytes = vec![0; 2000];
+    let mut filled = 0;
+    while filled < bytes.len() {
+        filled += sensor.read(&mut bytes[filled..]).unwrap();
+    }
+    bytes
+}
+
+/// Decode `bytes` as BLE notifications of the given sizes
+fn notifications(bytes: &[u8], sizes: impl Iterator<Item = usize>) -> Vec<MessageBody> {
+    let mut parser = FrameParser::new();
+    let mut messages = Vec::new();
+    let mut rest = bytes;
+    for size in sizes {
+        if rest.is_empty() {
+            break;
+        }
+        let (notification, next) = rest.split_at(size.min(rest.len()));
+        parser.push_notification(notification, |message| messages.push(message.unwrap()));
+        rest = next;
+    }
+    messages
+}
+
+#[test]
+fn ble_notifications() {
+    let bytes = simulated_bytes();
+    let expected: Vec<_> = MessageStream::new(bytes.as_slice())
+        .map_while(Result::ok)
+        .collect();
+
+    // the default ATT MTU leaves 20 bytes per notification, splitting most frames
+    assert_eq!(notifications(&bytes, std::iter::repeat(20)), expected);
+    // a negotiated MTU of 247 carries several frames per notification
+    assert_eq!(notifications(&bytes, std::iter::repeat(244)), expected);
+    // bridges flushing on a timer send whatever was received so far
+    let sizes = (1..).map(|i: usize| (i * 7919) % 61 + 1);
+    assert_eq!(notifications(&bytes, sizes), expected);
+}
+
+#[test]
+fn lost_notification() {
+    let bytes = [HEARTBEAT_72, RESPIRATORY_15_5, PHASE].concat();
+    let mut parser = FrameParser::new().with_resync(true);
+    let mut messages = Vec::new();
+
+    // the second notification with the end of the heartbeat frame got lost
+    for notification in [&bytes[..5], &bytes[20..]] {
+        parser.push_notification(notification, |message| messages.push(message));
+    }
+    assert!(matches!(messages.last(), Some(Ok(MessageBody::Phase(_)))));
+}
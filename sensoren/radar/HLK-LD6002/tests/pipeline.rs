@@ -0,0 +1,87 @@
+//! Running stages over snapshots of the readings
+
+#![cfg(feature = "filters")]
+
+use hlk_ld6002::filter::{FilteredData, MovingAverage};
+use hlk_ld6002::pipeline::{Pipeline, Pool};
+use hlk_ld6002::window::{DataWindow, Field};
+use hlk_ld6002::{Data, MessageBody};
+
+#[test]
+fn stages_run_in_order() {
+    let mut windows: Pool<DataWindow<16>, 2> = Pool::default();
+    let mut seen = Vec::new();
+    {
+        let (clamped, raw) = windows.split::<1, 0>();
+        let mut clamp = |_now: u64, data: &mut Data| data.heartbeat = data.heartbeat.min(150.0);
+        let mut record = |now: u64, data: &mut Data| seen.push((now, data.heartbeat));
+        let mut pipeline = Pipeline::new([raw, &mut clamp, clamped, &mut record]);
+
+        for (now, heartbeat) in [(0, 70.0), (1_000, 180.0), (2_000, 90.0)] {
+            let mut data = Data {
+                heartbeat,
+                ..Data::default()
+            };
+            pipeline.update(now, &mut data);
+        }
+    }
+
+    assert_eq!(seen, [(0, 70.0), (1_000, 150.0), (2_000, 90.0)]);
+    let max = |window: &mut DataWindow<16>| {
+        window
+            .aggregate(Field::Heartbeat, 2_000, 10_000)
+            .unwrap()
+            .max
+    };
+    assert_eq!(max(windows.get::<0>()), 180.0);
+    assert_eq!(max(windows.get::<1>()), 150.0);
+}
+
+#[test]
+fn pool() {
+    let mut pool = Pool::new([1, 2, 3]);
+    *pool.get::<2>() += 10;
+    let (a, b) = pool.split::<2, 0>();
+    core::mem::swap(a, b);
+    assert_eq!(
+        pool.iter_mut().map(|slot| *slot).collect::<Vec<_>>(),
+        [13, 2, 1]
+    );
+}
+
+#[test]
+fn filtered_snapshots() {
+    let mut filtered = FilteredData::new(MovingAverage::<2>::default());
+    let mut window = DataWindow::<16>::default();
+    let mut pipeline = Pipeline::new([&mut window]);
+    for (now, heartbeat) in [(0, 60.0), (1_000, 80.0)] {
+        filtered.update(MessageBody::Heartbeat(heartbeat));
+        pipeline.update(now, &mut filtered.data());
+    }
+    let average = window.aggregate(Field::Heartbeat, 1_000, 10_000).unwrap();
+    assert_eq!((average.min, average.max), (60.0, 70.0));
+}
+
+#[cfg(feature = "detectors")]
+#[test]
+fn detector_stage() {
+    use hlk_ld6002::stuck::{Liveness, StuckDetector};
+
+    let mut stuck = StuckDetector::new(0.0, 5_000);
+    let mut liveness = Liveness::Live;
+    {
+        let mut heartbeat =
+            |now: u64, data: &mut Data| liveness = stuck.update(now, data.heartbeat);
+        let mut pipeline = Pipeline::new([&mut heartbeat]);
+        for now in (0..=6_000).step_by(1_000) {
+            pipeline.update(
+                now,
+                &mut Data {
+                    heartbeat: 72.0,
+                    ..Data::default()
+                },
+            );
+        }
+    }
+    assert_eq!(liveness, Liveness::Stale);
+}
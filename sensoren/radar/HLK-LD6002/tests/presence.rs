@@ -0,0 +1,86 @@
+//! Debouncing of the presence
+
+#![cfg(feature = "detectors")]
+
+use hlk_ld6002::presence::{Presence, PresenceConfig, PresenceDetector};
+use hlk_ld6002::MessageBody;
+
+#[test]
+fn hold_times_and_hysteresis() {
+    let mut presence = PresenceDetector::new(PresenceConfig {
+        enter_hold: 2_000,
+        leave_hold: 5_000,
+        max_distance: 2.0,
+        hysteresis: 0.5,
+        timeout: 10_000,
+        vitals: false,
+    });
+    let mut update =
+        |now: u64, distance: f32| presence.update(now, &MessageBody::Distance(Some(distance)));
+
+    assert_eq!(update(0, 0.0), Presence::Unknown);
+    assert_eq!(update(5_000, 0.0), Presence::Absent);
+    // a single reflection doesn't count
+    assert_eq!(update(6_000, 1.0), Presence::Absent);
+    assert_eq!(update(7_000, 0.0), Presence::Absent);
+
+    assert_eq!(update(8_000, 1.0), Presence::Absent);
+    assert_eq!(update(10_000, 1.0), Presence::Present);
+    // moving within the hysteresis keeps the presence
+    assert_eq!(update(11_000, 2.4), Presence::Present);
+    assert_eq!(update(20_000, 2.4), Presence::Present);
+
+    assert_eq!(update(21_000, 0.0), Presence::Present);
+    assert_eq!(update(26_000, 0.0), Presence::Absent);
+
+    assert_eq!(presence.state(40_000), Presence::Unknown);
+}
+
+#[test]
+fn bathroom_vitals_hold() {
+    let mut presence = PresenceDetector::new(PresenceConfig::bathroom());
+    let distance = |distance: f32| MessageBody::Distance(Some(distance));
+
+    // vitals alone don't make the room present
+    assert_eq!(presence.update(0, &distance(0.0)), Presence::Unknown);
+    assert_eq!(presence.update(300_000, &distance(0.0)), Presence::Absent);
+    assert_eq!(
+        presence.update(301_000, &MessageBody::Heartbeat(70.0)),
+        Presence::Absent
+    );
+
+    assert_eq!(presence.update(302_000, &distance(1.5)), Presence::Absent);
+    assert_eq!(presence.update(303_000, &distance(1.5)), Presence::Present);
+
+    // motionless in the shower, the distance drops out but the vitals keep coming
+    let mut now = 303_000;
+    while now < 1_000_000 {
+        now += 10_000;
+        presence.update(now, &distance(0.0));
+        presence.update(now + 5_000, &MessageBody::Respiratory(14.0));
+        assert_eq!(presence.state(now + 5_000), Presence::Present);
+    }
+
+    // zero vitals are no confirmation
+    for t in (10_000..=310_000).step_by(10_000) {
+        presence.update(now + t, &MessageBody::Heartbeat(0.0));
+        presence.update(now + t, &distance(0.0));
+    }
+    assert_eq!(presence.state(now + 310_000), Presence::Absent);
+}
+
+#[test]
+fn vitals_ignored_by_default() {
+    let mut presence = PresenceDetector::new(PresenceConfig {
+        leave_hold: 5_000,
+        ..PresenceConfig::default()
+    });
+    presence.update(0, &MessageBody::Distance(Some(1.0)));
+    presence.update(2_000, &MessageBody::Distance(Some(1.0)));
+    presence.update(3_000, &MessageBody::Distance(None));
+    presence.update(6_000, &MessageBody::Heartbeat(70.0));
+    assert_eq!(
+        presence.update(8_000, &MessageBody::Distance(None)),
+        Presence::Absent
+    );
+}
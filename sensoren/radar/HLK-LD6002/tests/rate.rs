@@ -0,0 +1,86 @@
+//! Measuring the reporting rate of the sensor
+
+mod common;
+
+use common::{HEARTBEAT_72, RESPIRATORY_15_5};
+use hlk_ld6002::{MessageStream, MessageType};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// A stream with heartbeat frames arriving at `times`, using `time` as its clock
+fn receive_heartbeats(
+    time: &Rc<Cell<u64>>,
+    times: &[u64],
+) -> MessageStream<&'static [u8], impl FnMut() -> u64> {
+    let bytes: &'static [u8] = HEARTBEAT_72.repeat(times.len()).leak();
+    let mut messages = MessageStream::with_clock(bytes, {
+        let time = time.clone();
+        move || time.get()
+    });
+    for now in times {
+        time.set(*now);
+        messages.next().unwrap().unwrap();
+    }
+    messages
+}
+
+#[test]
+fn measured_rate() {
+    let times: Vec<u64> = (0..20).map(|i| i * 100).collect();
+    let mut messages = receive_heartbeats(&Rc::default(), &times);
+    let rates = messages.rates();
+    assert_eq!(rates.get(MessageType::Heartbeat), Some(10.0));
+    assert_eq!(rates.get(MessageType::Respiratory), None);
+    assert!(!rates.is_underrun(MessageType::Heartbeat, 9.0));
+    assert!(rates.is_underrun(MessageType::Heartbeat, 11.0));
+    // types that weren't received count as an underrun
+    assert!(rates.is_underrun(MessageType::Respiratory, 1.0));
+}
+
+#[test]
+fn only_the_last_arrivals_count() {
+    // slow at first, then the last 8 frames arrive every 50 ms
+    let mut times: Vec<u64> = (0..10).map(|i| i * 500).collect();
+    times.extend((1..=8).map(|i| 4_500 + i * 50));
+    let mut messages = receive_heartbeats(&Rc::default(), &times);
+    assert_eq!(messages.rates().get(MessageType::Heartbeat), Some(20.0));
+
+    // with 6 fast frames, the last two slow ones are still in the window
+    let mut messages = receive_heartbeats(&Rc::default(), &times[..times.len() - 2]);
+    assert_eq!(
+        messages.rates().get(MessageType::Heartbeat),
+        Some(7.0 / 0.8)
+    );
+}
+
+#[test]
+fn stalled_reports() {
+    let times: Vec<u64> = (0..8).map(|i| i * 100).collect();
+    let time = Rc::default();
+    let mut messages = receive_heartbeats(&time, &times);
+    assert!(!messages.rates().is_underrun(MessageType::Heartbeat, 9.0));
+
+    // nothing received for a second, the gap drags the rate down
+    time.set(1_700);
+    let rate = messages.rates().get(MessageType::Heartbeat).unwrap();
+    assert!((rate - 7.0 / 1.7).abs() < 1e-3, "{rate}");
+    assert!(messages.rates().is_underrun(MessageType::Heartbeat, 9.0));
+}
+
+#[test]
+fn no_clock() {
+    let bytes = [HEARTBEAT_72, RESPIRATORY_15_5, HEARTBEAT_72].concat();
+    let mut messages = MessageStream::new(bytes.as_slice());
+    for _ in 0..3 {
+        messages.next().unwrap().unwrap();
+    }
+    let rates = messages.rates();
+    for ty in [
+        MessageType::Phase,
+        MessageType::Respiratory,
+        MessageType::Heartbeat,
+        MessageType::Distance,
+    ] {
+        assert_eq!(rates.get(ty), None);
+    }
+}
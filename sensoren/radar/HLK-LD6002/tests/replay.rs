@@ -0,0 +1,91 @@
+//! Recording a capture and replaying it
+
+use embedded_io_adapters::std::FromStd;
+use futures::executor::block_on;
+use hlk_ld6002::replay::{Recorder, Replay};
+use hlk_ld6002::testing::{Faults, FaultyTransport, SimulatedSensor, Vitals};
+use hlk_ld6002::{AsyncMessageStream, MessageBody, MessageStream};
+use std::cell::Cell;
+use std::future::ready;
+
+fn record(clock: impl FnMut() -> u64) -> (Vec<MessageBody>, Vec<u8>) {
+    let sensor = FaultyTransport::new(
+        SimulatedSensor::new(Vitals::default()),
+        Faults {
+            max_chunk: 7,
+            ..Faults::default()
+        },
+    );
+    let mut capture = Vec::new();
+    let recorder = Recorder::new(sensor, FromStd::new(&mut capture), clock);
+    let recorded = MessageStream::new(recorder)
+        .take(50)
+        .map(Result::unwrap)
+        .collect();
+    (recorded, capture)
+}
+
+#[test]
+fn replay_recorded_capture() {
+    let time = Cell::new(0);
+    let (recorded, capture) = record(|| {
+        time.set(time.get() + 10);
+        time.get()
+    });
+
+    let mut delays = Vec::new();
+    let replay = Replay::with_pacing(capture.as_slice(), |ms| delays.push(ms));
+    let replayed: Vec<_> = MessageStream::new(replay)
+        .take(50)
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(replayed, recorded);
+    assert_eq!(delays[0], 0);
+    assert!(delays[1..].iter().all(|delay| *delay == 10));
+}
+
+#[test]
+fn replay_async() {
+    let mut time = 0;
+    let (recorded, capture) = record(|| {
+        time += 10;
+        time
+    });
+
+    let mut delays = Vec::new();
+    let replay = Replay::with_async_pacing(capture.as_slice(), |ms| {
+        delays.push(ms);
+        ready(())
+    });
+    let mut messages = AsyncMessageStream::new(replay);
+    let replayed: Vec<_> = block_on(async {
+        let mut replayed = Vec::new();
+        for _ in 0..50 {
+            replayed.push(messages.next().await.unwrap());
+        }
+        replayed
+    });
+    assert_eq!(replayed, recorded);
+    assert!(delays[1..].iter().all(|delay| *delay == 10));
+
+    let mut messages = AsyncMessageStream::new(Replay::new_async(capture.as_slice()));
+    assert_eq!(block_on(messages.next()).unwrap(), recorded[0]);
+}
+
+#[test]
+fn long_recordings_saturate() {
+    let mut times = [1_000, 2_000, 50 * 86_400_000, 60 * 86_400_000, 500].into_iter();
+    let (_, capture) = record(|| times.next().unwrap_or(70 * 86_400_000));
+
+    let mut replay = Replay::new(capture.as_slice());
+    let mut chunk_times = Vec::new();
+    let mut buf = [0; 7];
+    while embedded_io::Read::read(&mut replay, &mut buf).unwrap() > 0 {
+        if chunk_times.last() != Some(&replay.now_ms()) {
+            chunk_times.push(replay.now_ms());
+        }
+    }
+    // the clock going backwards doesn't wrap either
+    assert_eq!(chunk_times, [0, 1_000, u32::MAX as u64, 0, u32::MAX as u64]);
+}
@@ -0,0 +1,45 @@
+//! Serializing the decoded readings
+
+#![cfg(feature = "serde")]
+
+mod common;
+
+use common::HEARTBEAT_72;
+use hlk_ld6002::{Data, LdError, MessageBody, MessageStream};
+
+fn decode(bytes: &[u8]) -> MessageBody {
+    let message: Result<_, LdError<core::convert::Infallible>> =
+        MessageStream::new(bytes).next().expect("no message");
+    message.unwrap()
+}
+
+#[test]
+fn message_roundtrip() {
+    let mut unknown = HEARTBEAT_72.to_vec();
+    unknown[6] = 0x99;
+
+    for message in [
+        decode(HEARTBEAT_72),
+        decode(&unknown),
+        MessageBody::Distance(None),
+        MessageBody::Phase([1.0, -0.5, 0.25]),
+    ] {
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(serde_json::from_str::<MessageBody>(&json).unwrap(), message);
+    }
+}
+
+#[test]
+fn data_json() {
+    let data = Data {
+        respiratory: 15.5,
+        distance: 0.75,
+        heartbeat: 72.0,
+        heartbeat_confidence: 0.5,
+        respiratory_confidence: 1.0,
+    };
+    assert_eq!(
+        serde_json::to_string(&data).unwrap(),
+        r#"{"respiratory":15.5,"distance":0.75,"heartbeat":72.0,"heartbeat_confidence":0.5,"respiratory_confidence":1.0}"#
+    );
+}
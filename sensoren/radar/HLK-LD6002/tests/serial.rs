@@ -0,0 +1,76 @@
+//! Opening serial ports with tokio
+
+#![cfg(all(feature = "std", unix))]
+
+use hlk_ld6002::encode::{encode, MAX_FRAME};
+use hlk_ld6002::serial::{probe, BAUD_RATE};
+use hlk_ld6002::{MessageBody, MessageStream};
+use serialport::{SerialPort, TTYPort};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+fn heartbeat_frame() -> Vec<u8> {
+    let mut frame = [0; MAX_FRAME];
+    let len = encode(1, &MessageBody::Heartbeat(72.0), &mut frame);
+    frame[..len].to_vec()
+}
+
+#[test]
+fn missing_port() {
+    assert!(MessageStream::open("/dev/does-not-exist", BAUD_RATE).is_err());
+    assert!(!probe("/dev/does-not-exist"));
+}
+
+#[test]
+fn probe_pty() {
+    let (mut sensor, port) = TTYPort::pair().unwrap();
+    let path = port.name().unwrap();
+    std::thread::spawn(move || {
+        let frame = heartbeat_frame();
+        while sensor.write_all(&frame).is_ok() {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    });
+    assert!(probe(&path));
+}
+
+#[test]
+fn probe_silent_pty() {
+    let (_sensor, port) = TTYPort::pair().unwrap();
+    assert!(!probe(&port.name().unwrap()));
+}
+
+#[test]
+fn probe_chatty_pty() {
+    let (mut sensor, port) = TTYPort::pair().unwrap();
+    let path = port.name().unwrap();
+    // a GPS receiver, which never sends a start of frame byte
+    std::thread::spawn(move || {
+        let sentence = b"$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n";
+        while sensor.write_all(sentence).is_ok() {}
+    });
+    let start = Instant::now();
+    assert!(!probe(&path));
+    assert!(start.elapsed() < Duration::from_secs(3));
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn reads_from_pty() {
+    use futures::StreamExt;
+    use hlk_ld6002::serial::TokioMessageStream;
+    use tokio::io::AsyncWriteExt;
+    use tokio_serial::SerialStream;
+
+    let (mut sensor, port) = SerialStream::pair().unwrap();
+    let path = port.name().unwrap();
+    let messages = TokioMessageStream::open(&path, BAUD_RATE).unwrap();
+    let mut messages = Box::pin(messages.into_stream());
+
+    AsyncWriteExt::write_all(&mut sensor, &heartbeat_frame())
+        .await
+        .unwrap();
+
+    let message = messages.next().await.unwrap().unwrap();
+    assert_eq!(message, MessageBody::Heartbeat(72.0));
+}
@@ -0,0 +1,31 @@
+//! Decoding the frames of the simulated sensor
+
+use hlk_ld6002::testing::{SimulatedSensor, Vitals};
+use hlk_ld6002::{Data, LdError, MessageStream};
+
+#[test]
+fn simulated_vitals() {
+    let mut stream = MessageStream::new(SimulatedSensor::new(Vitals::default()));
+    let mut data = Data::default();
+    for message in stream.by_ref().take(23 * 3) {
+        data.update(message.unwrap());
+    }
+
+    assert!((data.heartbeat - 70.0).abs() < 70.0 * 0.02);
+    assert!((data.respiratory - 15.0).abs() < 15.0 * 0.02);
+    assert!((data.distance - 0.8).abs() < 0.8 * 0.02);
+    assert_eq!(stream.stats().frames, 23 * 3);
+}
+
+#[test]
+fn simulated_corruption() {
+    let sensor = SimulatedSensor::new(Vitals {
+        corrupt_every: 10,
+        ..Vitals::default()
+    });
+    let errors = MessageStream::new(sensor)
+        .take(100)
+        .filter(|message| matches!(message, Err(LdError::InvalidChecksum { .. })))
+        .count();
+    assert_eq!(errors, 10);
+}
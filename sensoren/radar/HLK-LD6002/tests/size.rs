@@ -0,0 +1,41 @@
+//! Guards the RAM usage documented in the readme
+//!
+//! The documented numbers are for 32-bit targets and checked as upper bounds there, 64-bit hosts
+//! are checked against their own bounds, which are larger because of the wider pointers.
+
+use core::mem::size_of;
+use hlk_ld6002::{Data, MessageStream};
+
+#[test]
+fn core_sizes() {
+    // the latency histograms are kept in the stream
+    let latency = if cfg!(feature = "latency") { 176 } else { 0 };
+    let stream = if cfg!(target_pointer_width = "32") {
+        236
+    } else {
+        288
+    };
+    assert!(size_of::<MessageStream<&[u8]>>() <= stream + latency);
+    assert_eq!(size_of::<Data>(), 20);
+}
+
+#[cfg(feature = "filters")]
+#[test]
+fn window_size() {
+    use hlk_ld6002::window::DataWindow;
+
+    let overhead = if cfg!(target_pointer_width = "32") {
+        24
+    } else {
+        48
+    };
+    assert!(size_of::<DataWindow<64>>() <= 48 * 64 + overhead);
+}
+
+#[cfg(feature = "detectors")]
+#[test]
+fn detector_sizes() {
+    use hlk_ld6002::stuck::StuckDetector;
+
+    assert!(size_of::<StuckDetector>() <= 32);
+}
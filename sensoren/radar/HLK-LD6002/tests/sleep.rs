@@ -0,0 +1,47 @@
+//! Summaries of a night of readings
+
+#![cfg(all(feature = "alloc", feature = "filters"))]
+
+use hlk_ld6002::sleep::SleepSession;
+use hlk_ld6002::Data;
+
+fn data(heartbeat: f32, distance: f32) -> Data {
+    Data {
+        respiratory: 14.0,
+        distance,
+        heartbeat,
+        ..Data::default()
+    }
+}
+
+#[test]
+fn session_summary() {
+    let mut session = SleepSession::new();
+    assert_eq!(session.summary(), None);
+
+    // 2 minutes lying still, 1 minute restless, 1 minute out of bed
+    for second in 0..240u64 {
+        let now = second * 1000;
+        let sample = match second {
+            0..120 => data(60.0 + (second % 5) as f32, 0.8),
+            120..180 => data(80.0, 0.8 + (second % 2) as f32 * 0.2),
+            _ => Data::default(),
+        };
+        session.record(now, sample);
+    }
+
+    let epochs = session.epochs();
+    assert_eq!(epochs.len(), 8);
+    assert!(epochs[..6].iter().all(|epoch| epoch.in_bed));
+    assert!(epochs[4].moving && !epochs[3].moving && !epochs[6].in_bed);
+
+    let summary = session.summary().unwrap();
+    assert_eq!(summary.time_in_bed, 180_000);
+    assert_eq!(summary.movement_epochs, 2);
+    assert_eq!(summary.restlessness, 2.0 / 6.0);
+    let heartbeat = summary.heartbeat.unwrap();
+    assert_eq!(
+        (heartbeat.min, heartbeat.max, heartbeat.count),
+        (60.0, 80.0, 180)
+    );
+}
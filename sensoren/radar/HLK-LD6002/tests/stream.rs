@@ -0,0 +1,55 @@
+//! The async message stream as a futures `Stream`
+
+#![cfg(feature = "futures")]
+
+mod common;
+
+use common::{HEARTBEAT_72, PHASE, RESPIRATORY_15_5};
+use core::pin::pin;
+use futures::executor::block_on;
+use futures::StreamExt;
+use hlk_ld6002::testing::{Faults, FaultyTransport};
+use hlk_ld6002::{AsyncMessageStream, MessageBody};
+
+#[test]
+fn stream_combinators() {
+    let bytes = [HEARTBEAT_72, RESPIRATORY_15_5, PHASE].concat();
+    let transport = FaultyTransport::new(
+        bytes.as_slice(),
+        Faults {
+            max_chunk: 3,
+            delay_every: 2,
+            ..Faults::default()
+        },
+    );
+    let stream = pin!(AsyncMessageStream::new(transport).into_stream());
+
+    let messages: Vec<_> = block_on(
+        stream
+            .take(3)
+            .filter_map(|message| async move { message.ok() })
+            .collect(),
+    );
+    assert_eq!(
+        messages,
+        [
+            MessageBody::Heartbeat(72.0),
+            MessageBody::Respiratory(15.5),
+            MessageBody::Phase([1.0, -0.5, 0.25]),
+        ]
+    );
+}
+
+#[test]
+fn borrowed_stream() {
+    let bytes = [HEARTBEAT_72, RESPIRATORY_15_5, PHASE].concat();
+    let mut messages = AsyncMessageStream::new(bytes.as_slice());
+
+    let first: Vec<_> = block_on(pin!(messages.stream()).take(2).collect());
+    assert_eq!(first.len(), 2);
+    // the message stream continues where the dropped stream stopped
+    assert_eq!(
+        block_on(messages.next()).unwrap(),
+        MessageBody::Phase([1.0, -0.5, 0.25])
+    );
+}
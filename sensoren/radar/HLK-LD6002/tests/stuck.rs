@@ -0,0 +1,67 @@
+//! Detection of frozen readings
+
+#![cfg(feature = "detectors")]
+
+use hlk_ld6002::command::Command;
+use hlk_ld6002::stuck::{Liveness, Restart, StuckDetector};
+
+#[test]
+fn frozen_heartbeat() {
+    let mut detector = StuckDetector::heartbeat();
+    for time in (0..=120_000).step_by(1_000) {
+        assert_eq!(detector.update(time, 72.0), Liveness::Live);
+    }
+    assert_eq!(detector.update(121_000, 72.0), Liveness::Stale);
+    assert_eq!(detector.liveness(200_000), Liveness::Stale);
+    // any change makes it live again
+    assert_eq!(detector.update(201_000, 72.5), Liveness::Live);
+}
+
+#[test]
+fn jittering_heartbeat() {
+    let mut detector = StuckDetector::heartbeat();
+    for time in (0..600_000).step_by(1_000) {
+        let value = 70.0 + (time / 1_000 % 3) as f32;
+        assert_eq!(detector.update(time, value), Liveness::Live);
+    }
+}
+
+#[test]
+fn tolerance() {
+    let mut detector = StuckDetector::new(0.5, 10_000);
+    detector.update(0, 70.0);
+    // small changes around the first value count as stuck
+    detector.update(5_000, 70.4);
+    assert_eq!(detector.update(10_001, 69.6), Liveness::Stale);
+
+    detector.reset();
+    assert_eq!(detector.liveness(20_000), Liveness::Live);
+    assert_eq!(detector.update(20_000, 70.0), Liveness::Live);
+}
+
+#[test]
+fn restart() {
+    let mut detector = StuckDetector::new(0.0, 10_000);
+    let mut restart = Restart::new(Command::Reset, 60_000);
+
+    detector.update(0, 72.0);
+    assert_eq!(restart.update(5_000, detector.update(5_000, 72.0)), None);
+    assert_eq!(
+        restart.update(10_001, detector.update(10_001, 72.0)),
+        Some(Command::Reset)
+    );
+    assert_eq!(restart.update(11_000, detector.update(11_000, 72.0)), None);
+
+    // the sensor didn't recover, try again after the cooldown
+    assert_eq!(restart.update(70_000, detector.update(70_000, 72.0)), None);
+    assert_eq!(
+        restart.update(70_001, detector.update(70_001, 72.0)),
+        Some(Command::Reset)
+    );
+
+    detector.reset();
+    assert_eq!(
+        restart.update(200_000, detector.update(200_000, 72.0)),
+        None
+    );
+}
@@ -0,0 +1,60 @@
+//! Detection of a blocked or repositioned sensor
+
+#![cfg(feature = "detectors")]
+
+use hlk_ld6002::tamper::{Tamper, TamperDetector};
+use hlk_ld6002::MessageBody;
+
+#[test]
+fn blocked_after_max_duration() {
+    let mut detector = TamperDetector::new(0.1, 0.001, 60_000);
+    let blocked = MessageBody::Distance(Some(0.05));
+    assert_eq!(detector.update(0, &blocked), Tamper::Normal);
+    assert_eq!(detector.update(30_000, &blocked), Tamper::Normal);
+    // exactly at the limit isn't blocked yet
+    assert_eq!(detector.update(60_000, &blocked), Tamper::Normal);
+    assert_eq!(detector.update(60_001, &blocked), Tamper::SensorBlocked);
+    // without new reports, the state still advances
+    assert_eq!(detector.state(120_000), Tamper::SensorBlocked);
+}
+
+#[test]
+fn blocked_timer_restarts() {
+    let mut detector = TamperDetector::new(0.1, 0.001, 60_000);
+    let blocked = MessageBody::Distance(Some(0.05));
+    detector.update(0, &blocked);
+    // someone walking by in between, or no target at all
+    detector.update(40_000, &MessageBody::Distance(Some(1.5)));
+    assert_eq!(detector.update(50_000, &blocked), Tamper::Normal);
+    detector.update(70_000, &MessageBody::Distance(None));
+    assert_eq!(detector.update(80_000, &blocked), Tamper::Normal);
+    assert_eq!(detector.update(140_000, &blocked), Tamper::Normal);
+    assert_eq!(detector.update(140_001, &blocked), Tamper::SensorBlocked);
+    // reports of other types don't interrupt the timer
+    assert_eq!(
+        detector.update(150_000, &MessageBody::Heartbeat(70.0)),
+        Tamper::SensorBlocked
+    );
+}
+
+#[test]
+fn flat_phase() {
+    let mut detector = TamperDetector::new(0.1, 0.001, 60_000);
+    for time in (0..=60_000).step_by(1_000) {
+        let phase = MessageBody::Phase([1.0, 0.0, 0.0]);
+        assert_eq!(detector.update(time, &phase), Tamper::Normal);
+    }
+    assert_eq!(detector.state(60_001), Tamper::SensorTampered);
+
+    detector.reset();
+    assert_eq!(detector.state(60_001), Tamper::Normal);
+}
+
+#[test]
+fn moving_phase() {
+    let mut detector = TamperDetector::default();
+    for time in (0..120_000).step_by(50) {
+        let phase = MessageBody::Phase([(time as f32 / 1_000.0).sin(), 0.0, 0.0]);
+        assert_eq!(detector.update(time, &phase), Tamper::Normal);
+    }
+}
@@ -0,0 +1,86 @@
+//! Reading through a transport injecting faults
+
+mod common;
+
+use common::{DISTANCE_0_85, HEARTBEAT_72, PHASE, RESPIRATORY_15_5};
+use embedded_io::{ErrorKind, Read};
+use hlk_ld6002::testing::{Faults, FaultyError, FaultyTransport};
+use hlk_ld6002::{LdError, MessageBody, MessageStream};
+
+fn frames() -> Vec<u8> {
+    [HEARTBEAT_72, RESPIRATORY_15_5, PHASE, DISTANCE_0_85]
+        .concat()
+        .repeat(5)
+}
+
+fn expected() -> Vec<MessageBody> {
+    [
+        MessageBody::Heartbeat(72.0),
+        MessageBody::Respiratory(15.5),
+        MessageBody::Phase([1.0, -0.5, 0.25]),
+        MessageBody::Distance(Some(0.85)),
+    ]
+    .iter()
+    .cycle()
+    .take(20)
+    .cloned()
+    .collect()
+}
+
+#[test]
+fn short_reads_and_timeouts() {
+    let frames = frames();
+    let transport = FaultyTransport::new(
+        frames.as_slice(),
+        Faults {
+            max_chunk: 3,
+            timeout_every: 4,
+            ..Faults::default()
+        },
+    );
+    let mut timeouts = 0;
+    let mut messages = Vec::new();
+    for message in MessageStream::new(transport) {
+        match message {
+            Ok(message) => messages.push(message),
+            // timeouts in the middle of a frame are retried, only those between frames are returned
+            Err(LdError::Read(FaultyError::Injected(ErrorKind::TimedOut))) => timeouts += 1,
+            Err(LdError::Eof) => break,
+            Err(e) => panic!("{e:?}"),
+        }
+    }
+    assert_eq!(messages, expected());
+    assert!(timeouts > 0);
+}
+
+#[test]
+fn injected_errors() {
+    let frames = frames();
+    let faults = Faults {
+        error_rate: 0.2,
+        max_chunk: 5,
+        ..Faults::default()
+    };
+    let read_all = |faults| {
+        let mut transport = FaultyTransport::new(frames.as_slice(), faults);
+        let mut bytes = Vec::new();
+        let mut errors = 0;
+        let mut buf = [0; 16];
+        loop {
+            match transport.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => bytes.extend_from_slice(&buf[..read]),
+                Err(FaultyError::Injected(ErrorKind::Other)) => errors += 1,
+                Err(e) => panic!("{e:?}"),
+            }
+        }
+        (bytes, errors)
+    };
+
+    // errors don't lose data and are reproducible for the same seed
+    let (bytes, errors) = read_all(faults);
+    assert_eq!(bytes, frames);
+    assert!(errors > 0);
+    assert_eq!(read_all(faults).1, errors);
+    assert_ne!(read_all(Faults { seed: 7, ..faults }).1, errors);
+}
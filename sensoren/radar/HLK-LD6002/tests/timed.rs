@@ -0,0 +1,30 @@
+//! Ageing of readings in TimedData
+
+use hlk_ld6002::{DataEvent, Field, MessageBody, TimedData};
+
+#[test]
+fn readings_go_stale() {
+    let mut data = TimedData::default();
+    assert!(data.is_stale(Field::Heartbeat, 0, 5_000));
+
+    assert_eq!(
+        data.update(1_000, MessageBody::Heartbeat(72.0)),
+        DataEvent::Updated(Field::Heartbeat)
+    );
+    data.update(1_000, MessageBody::Distance(Some(0.8)));
+    // the person leaves, the sensor reports no target
+    assert_eq!(
+        data.update(4_000, MessageBody::Distance(Some(0.0))),
+        DataEvent::NoTarget
+    );
+    assert_eq!(
+        data.update(4_000, MessageBody::Heartbeat(0.0)),
+        DataEvent::Ignored
+    );
+
+    assert_eq!(data.age(Field::Heartbeat, 4_000), Some(3_000));
+    assert!(!data.is_stale(Field::Heartbeat, 6_000, 5_000));
+    assert!(data.is_stale(Field::Heartbeat, 6_001, 5_000));
+    assert_eq!(data.data().heartbeat, 72.0);
+    assert_eq!(data.fresh(6_001, 5_000).heartbeat, 0.0);
+}
@@ -0,0 +1,37 @@
+//! Tracking the simulated rates
+
+#![cfg(feature = "tracking")]
+
+use hlk_ld6002::testing::{SimulatedSensor, Vitals};
+use hlk_ld6002::tracking::{TrackingConfig, VitalsTracker};
+use hlk_ld6002::{MessageBody, MessageStream};
+
+#[test]
+fn tracks_the_simulated_rates() {
+    let vitals = Vitals {
+        noise: 0.1,
+        ..Vitals::default()
+    };
+    let mut sensor = MessageStream::new(SimulatedSensor::new(vitals));
+    let mut tracker = VitalsTracker::<512>::new(20.0, TrackingConfig::default());
+
+    assert_eq!(tracker.heartbeat(0), None);
+    let mut now = 0;
+    for _ in 0..60 * 23 {
+        let message = sensor.next().unwrap().unwrap();
+        if let MessageBody::Phase(_) = message {
+            now += SimulatedSensor::PHASE_INTERVAL;
+        }
+        tracker.update(now, &message);
+    }
+
+    let heartbeat = tracker.heartbeat(now).unwrap();
+    let respiratory = tracker.respiratory(now).unwrap();
+    assert!((heartbeat.rate - 70.0).abs() < 3.0, "{heartbeat:?}");
+    assert!((respiratory.rate - 15.0).abs() < 1.0, "{respiratory:?}");
+    assert!(respiratory.std_dev < heartbeat.std_dev);
+
+    // the uncertainty grows without reports
+    let later = tracker.heartbeat(now + 60_000).unwrap();
+    assert!(later.std_dev > heartbeat.std_dev);
+}
@@ -0,0 +1,32 @@
+//! Breathing and heartbeat waveforms from the simulated phase
+
+#![cfg(feature = "filters")]
+
+use hlk_ld6002::testing::{SimulatedSensor, Vitals};
+use hlk_ld6002::waveform::Waveforms;
+use hlk_ld6002::MessageStream;
+
+#[test]
+fn waveforms_follow_the_phase() {
+    let sensor = SimulatedSensor::new(Vitals {
+        noise: 0.0,
+        ..Vitals::default()
+    });
+    let mut stream = MessageStream::new(sensor);
+    let mut waveforms = Waveforms::<200>::new(20.0);
+
+    let mut now = 0;
+    for _ in 0..1_000 {
+        let message = stream.next().unwrap().unwrap();
+        now += 50;
+        waveforms.update(now, &message);
+    }
+
+    assert_eq!(waveforms.breathing().iter().count(), 200);
+    // the simulated breathing has an amplitude of 1, the heartbeat of 0.1,
+    // some of the breathing leaks into the wide heartbeat band
+    let breathing = waveforms.breathing_amplitude(now, 10_000).unwrap();
+    let heart = waveforms.heart_amplitude(now, 10_000).unwrap();
+    assert!((1.5..2.5).contains(&breathing), "{breathing}");
+    assert!((0.1..breathing / 2.0).contains(&heart), "{heart}");
+}
@@ -0,0 +1,86 @@
+//! Aggregates over the recent readings
+
+#![cfg(feature = "filters")]
+
+use hlk_ld6002::window::{Aggregate, DataWindow, Field, Window};
+use hlk_ld6002::Data;
+
+#[test]
+fn aggregate() {
+    let mut window = Window::<8>::default();
+    for (time, value) in [(0, 10.0), (1_000, 20.0), (2_000, 30.0)] {
+        window.push(time, value);
+    }
+    assert_eq!(
+        window.aggregate(2_000, 10_000),
+        Some(Aggregate {
+            min: 10.0,
+            max: 30.0,
+            avg: 20.0,
+            count: 3,
+        })
+    );
+}
+
+#[test]
+fn expired_values() {
+    let mut window = Window::<8>::default();
+    for (time, value) in [(0, 10.0), (1_000, 20.0), (2_000, 30.0)] {
+        window.push(time, value);
+    }
+    // the span includes values exactly at its start
+    assert_eq!(window.aggregate(3_000, 2_000).unwrap().count, 2);
+    assert_eq!(window.aggregate(3_000, 1_000).unwrap().avg, 30.0);
+    assert_eq!(window.aggregate(10_000, 5_000), None);
+    // spans reaching before the start of the clock
+    assert_eq!(window.aggregate(500, 60_000).unwrap().count, 3);
+}
+
+#[test]
+fn overwrites_oldest() {
+    let mut window = Window::<3>::default();
+    for time in 0..5 {
+        window.push(time, time as f32);
+    }
+    assert_eq!(
+        window.iter().collect::<Vec<_>>(),
+        [(2, 2.0), (3, 3.0), (4, 4.0)]
+    );
+    assert_eq!(window.aggregate(4, 100).unwrap().min, 2.0);
+}
+
+#[test]
+fn data_window_skips_missing_readings() {
+    let mut windows = DataWindow::<8>::default();
+    windows.record(
+        0,
+        &Data {
+            heartbeat: 60.0,
+            respiratory: 12.0,
+            ..Data::default()
+        },
+    );
+    windows.record(
+        1_000,
+        &Data {
+            heartbeat: 70.0,
+            ..Data::default()
+        },
+    );
+    assert_eq!(
+        windows
+            .aggregate(Field::Heartbeat, 1_000, 5_000)
+            .unwrap()
+            .avg,
+        65.0
+    );
+    assert_eq!(
+        windows
+            .aggregate(Field::Respiratory, 1_000, 5_000)
+            .unwrap()
+            .count,
+        1
+    );
+    assert_eq!(windows.aggregate(Field::Distance, 1_000, 5_000), None);
+    assert_eq!(windows.aggregate(Field::Respiratory, 6_000, 5_000), None);
+}